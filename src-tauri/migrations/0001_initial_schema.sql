@@ -0,0 +1,92 @@
+-- the tables every install had before versioned migrations,
+-- IF NOT EXISTS lets databases created by older builds adopt this version as-is
+
+CREATE TABLE IF NOT EXISTS parent_folder
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    full_path TEXT NOT NULL,
+    as_child BOOLEAN DEFAULT 0,
+    is_expanded BOOLEAN DEFAULT 0,
+    cover_panel_path TEXT,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(full_path)
+);
+
+-- !!! When updating this table you must update the global table as well !!!
+CREATE TABLE IF NOT EXISTS manga_folder
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    full_path TEXT NOT NULL,
+    as_child BOOLEAN DEFAULT 0,
+    is_expanded BOOLEAN DEFAULT 0,
+    time_spent_reading INTEGER DEFAULT 0,
+    double_panels BOOLEAN DEFAULT 0,
+    is_read BOOLEAN DEFAULT 0,
+    cover_panel_path TEXT,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(full_path)
+);
+
+CREATE TABLE IF NOT EXISTS global_manga
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    full_path TEXT NOT NULL,
+    as_child BOOLEAN DEFAULT 0,
+    is_expanded BOOLEAN DEFAULT 0,
+    time_spent_reading INTEGER DEFAULT 0,
+    double_panels BOOLEAN DEFAULT 0,
+    is_read BOOLEAN DEFAULT 0,
+    cover_panel_path TEXT,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(full_path)
+);
+
+CREATE TABLE IF NOT EXISTS global_parent
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    full_path TEXT NOT NULL,
+    as_child BOOLEAN DEFAULT 0,
+    is_expanded BOOLEAN DEFAULT 0,
+    cover_panel_path TEXT,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(full_path)
+);
+
+CREATE TABLE IF NOT EXISTS manga_panel
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    full_path TEXT NOT NULL,
+    is_read BOOLEAN DEFAULT 0,
+    width INTEGER,
+    height INTEGER,
+    zoom_level INTEGER,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(full_path)
+);
+
+CREATE TABLE IF NOT EXISTS stats
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    total_manga INTEGER DEFAULT 0,
+    total_panels INTEGER DEFAULT 0,
+    total_panels_read INTEGER DEFAULT 0,
+    total_panels_remaining INTEGER DEFAULT 0,
+    total_time_spent_reading INTEGER DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS chart
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    watchtime INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL UNIQUE
+);
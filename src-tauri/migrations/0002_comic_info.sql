@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS series
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE(title)
+);
+
+-- metadata read from a chapter's ComicInfo.xml, one row per manga_folder
+CREATE TABLE IF NOT EXISTS comic_info
+(
+    manga_folder_id TEXT PRIMARY KEY REFERENCES manga_folder(id) ON DELETE CASCADE,
+    series_id TEXT REFERENCES series(id) ON DELETE SET NULL,
+    volume INTEGER,
+    number TEXT,
+    title TEXT,
+    writer TEXT,
+    penciller TEXT,
+    genre TEXT,
+    tags TEXT,
+    language_iso TEXT,
+    manga TEXT,
+    page_count INTEGER
+);
@@ -0,0 +1,8 @@
+-- set by the filesystem watcher when a chapter or panel disappears from disk,
+-- the rows are kept so reading progress survives the folder coming back
+
+ALTER TABLE manga_folder ADD COLUMN is_missing BOOLEAN DEFAULT 0;
+
+ALTER TABLE global_manga ADD COLUMN is_missing BOOLEAN DEFAULT 0;
+
+ALTER TABLE manga_panel ADD COLUMN is_missing BOOLEAN DEFAULT 0;
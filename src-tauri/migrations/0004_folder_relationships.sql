@@ -0,0 +1,54 @@
+-- folders and panels used to be related only by path prefixes, which made `/manga/One`
+-- also match `/manga/One Piece`. these columns link them by id instead.
+
+ALTER TABLE parent_folder ADD COLUMN parent_id TEXT REFERENCES parent_folder(id) ON DELETE CASCADE;
+
+ALTER TABLE manga_folder ADD COLUMN parent_id TEXT REFERENCES parent_folder(id) ON DELETE CASCADE;
+
+ALTER TABLE manga_panel ADD COLUMN folder_id TEXT REFERENCES manga_folder(id) ON DELETE CASCADE;
+
+-- the global tables mirror parent_folder and manga_folder, they only need the column
+ALTER TABLE global_parent ADD COLUMN parent_id TEXT;
+
+ALTER TABLE global_manga ADD COLUMN parent_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_parent_folder_parent_id ON parent_folder(parent_id);
+
+CREATE INDEX IF NOT EXISTS idx_manga_folder_parent_id ON manga_folder(parent_id);
+
+CREATE INDEX IF NOT EXISTS idx_manga_panel_folder_id ON manga_panel(folder_id);
+
+-- backfill from the existing paths, picking the closest folder that contains each row.
+-- a path only counts as inside a folder when the folder path is followed by a separator.
+
+UPDATE parent_folder SET parent_id = (
+    SELECT parent.id FROM parent_folder AS parent
+    WHERE substr(parent_folder.full_path, 1, length(parent.full_path) + 1)
+        IN (parent.full_path || '/', parent.full_path || '\')
+    ORDER BY length(parent.full_path) DESC
+    LIMIT 1
+);
+
+UPDATE manga_folder SET parent_id = (
+    SELECT parent.id FROM parent_folder AS parent
+    WHERE substr(manga_folder.full_path, 1, length(parent.full_path) + 1)
+        IN (parent.full_path || '/', parent.full_path || '\')
+    ORDER BY length(parent.full_path) DESC
+    LIMIT 1
+);
+
+UPDATE manga_panel SET folder_id = (
+    SELECT folder.id FROM manga_folder AS folder
+    WHERE substr(manga_panel.full_path, 1, length(folder.full_path) + 1)
+        IN (folder.full_path || '/', folder.full_path || '\')
+    ORDER BY length(folder.full_path) DESC
+    LIMIT 1
+);
+
+UPDATE global_parent SET parent_id = (
+    SELECT parent_id FROM parent_folder WHERE parent_folder.id = global_parent.id
+);
+
+UPDATE global_manga SET parent_id = (
+    SELECT parent_id FROM manga_folder WHERE manga_folder.id = global_manga.id
+);
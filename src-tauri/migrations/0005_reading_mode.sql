@@ -0,0 +1,9 @@
+-- NULL means inherit: a chapter falls back to its parent folders, then to ComicInfo.xml
+
+ALTER TABLE parent_folder ADD COLUMN reading_mode TEXT;
+
+ALTER TABLE manga_folder ADD COLUMN reading_mode TEXT;
+
+ALTER TABLE global_parent ADD COLUMN reading_mode TEXT;
+
+ALTER TABLE global_manga ADD COLUMN reading_mode TEXT;
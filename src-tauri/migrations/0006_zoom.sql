@@ -0,0 +1,25 @@
+-- zoom used to live on every panel and was overwritten library wide on each change.
+-- it is now kept per series with optional per chapter overrides, NULL means inherit.
+
+ALTER TABLE parent_folder ADD COLUMN fit_mode TEXT;
+
+ALTER TABLE parent_folder ADD COLUMN zoom_level INTEGER;
+
+ALTER TABLE manga_folder ADD COLUMN fit_mode TEXT;
+
+ALTER TABLE manga_folder ADD COLUMN zoom_level INTEGER;
+
+ALTER TABLE global_parent ADD COLUMN fit_mode TEXT;
+
+ALTER TABLE global_parent ADD COLUMN zoom_level INTEGER;
+
+ALTER TABLE global_manga ADD COLUMN fit_mode TEXT;
+
+ALTER TABLE global_manga ADD COLUMN zoom_level INTEGER;
+
+-- every panel held the same last used zoom, keep it for the top level folders
+UPDATE parent_folder SET fit_mode = 'custom', zoom_level = (SELECT MAX(zoom_level) FROM manga_panel)
+WHERE as_child = 0 AND (SELECT MAX(zoom_level) FROM manga_panel) > 0;
+
+UPDATE manga_folder SET fit_mode = 'custom', zoom_level = (SELECT MAX(zoom_level) FROM manga_panel)
+WHERE parent_id IS NULL AND (SELECT MAX(zoom_level) FROM manga_panel) > 0;
@@ -0,0 +1,5 @@
+-- shows the first single page of a chapter alone, shifting every pair after it by one page
+
+ALTER TABLE manga_folder ADD COLUMN spread_offset BOOLEAN DEFAULT 0;
+
+ALTER TABLE global_manga ADD COLUMN spread_offset BOOLEAN DEFAULT 0;
@@ -0,0 +1,10 @@
+-- a cover picked by the user, kept apart from cover_panel_path so rescans never replace it.
+-- it is either a panel path or an image copied into the app data dir.
+
+ALTER TABLE parent_folder ADD COLUMN custom_cover_path TEXT;
+
+ALTER TABLE manga_folder ADD COLUMN custom_cover_path TEXT;
+
+ALTER TABLE global_parent ADD COLUMN custom_cover_path TEXT;
+
+ALTER TABLE global_manga ADD COLUMN custom_cover_path TEXT;
@@ -0,0 +1,66 @@
+-- the folders a user adds to the library. the series, volumes and chapters below a root
+-- are found by the backend, and removing the root removes them through ON DELETE CASCADE.
+
+CREATE TABLE IF NOT EXISTS library_root
+(
+    id TEXT PRIMARY KEY,
+    full_path TEXT NOT NULL UNIQUE,
+    created_at TEXT,
+    updated_at TEXT
+);
+
+ALTER TABLE parent_folder ADD COLUMN root_id TEXT REFERENCES library_root(id) ON DELETE CASCADE;
+
+ALTER TABLE manga_folder ADD COLUMN root_id TEXT REFERENCES library_root(id) ON DELETE CASCADE;
+
+-- a parent folder is either a series or a volume inside one
+ALTER TABLE parent_folder ADD COLUMN kind TEXT NOT NULL DEFAULT 'series';
+
+-- the global tables mirror parent_folder and manga_folder, they only need the columns
+ALTER TABLE global_parent ADD COLUMN root_id TEXT;
+
+ALTER TABLE global_parent ADD COLUMN kind TEXT NOT NULL DEFAULT 'series';
+
+ALTER TABLE global_manga ADD COLUMN root_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_parent_folder_root_id ON parent_folder(root_id);
+
+CREATE INDEX IF NOT EXISTS idx_manga_folder_root_id ON manga_folder(root_id);
+
+-- every folder added before roots existed becomes a root of its own
+INSERT OR IGNORE INTO library_root (id, full_path, created_at, updated_at)
+SELECT lower(hex(randomblob(16))), full_path, created_at, updated_at
+FROM parent_folder WHERE as_child = 0;
+
+INSERT OR IGNORE INTO library_root (id, full_path, created_at, updated_at)
+SELECT lower(hex(randomblob(16))), full_path, created_at, updated_at
+FROM manga_folder WHERE as_child = 0;
+
+-- nested parent folders were always opened from a series
+UPDATE parent_folder SET kind = 'volume' WHERE parent_id IS NOT NULL;
+
+-- backfill from the paths, picking the closest root that contains each folder
+UPDATE parent_folder SET root_id = (
+    SELECT root.id FROM library_root AS root
+    WHERE parent_folder.full_path = root.full_path
+    OR substr(parent_folder.full_path, 1, length(root.full_path) + 1)
+        IN (root.full_path || '/', root.full_path || '\')
+    ORDER BY length(root.full_path) DESC
+    LIMIT 1
+);
+
+UPDATE manga_folder SET root_id = (
+    SELECT root.id FROM library_root AS root
+    WHERE manga_folder.full_path = root.full_path
+    OR substr(manga_folder.full_path, 1, length(root.full_path) + 1)
+        IN (root.full_path || '/', root.full_path || '\')
+    ORDER BY length(root.full_path) DESC
+    LIMIT 1
+);
+
+UPDATE global_parent SET
+    root_id = (SELECT root_id FROM parent_folder WHERE parent_folder.id = global_parent.id),
+    kind = COALESCE((SELECT kind FROM parent_folder WHERE parent_folder.id = global_parent.id), 'series');
+
+UPDATE global_manga SET
+    root_id = (SELECT root_id FROM manga_folder WHERE manga_folder.id = global_manga.id);
@@ -0,0 +1,36 @@
+-- reading time is recorded as sessions, every total is summed from them instead of
+-- being kept in counters that the stats page reset after reading them
+
+CREATE TABLE IF NOT EXISTS reading_session
+(
+    id TEXT PRIMARY KEY,
+    -- kept when the chapter is removed, so the daily and global totals don't shrink
+    folder_id TEXT REFERENCES manga_folder(id) ON DELETE SET NULL,
+    started_at TEXT NOT NULL,
+    ended_at TEXT NOT NULL,
+    pages_turned INTEGER NOT NULL DEFAULT 0,
+    seconds INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_reading_session_folder_id ON reading_session(folder_id);
+
+CREATE INDEX IF NOT EXISTS idx_reading_session_started_at ON reading_session(started_at);
+
+-- the chart held every day's reading time, it becomes one session per day. the chapter
+-- counters only held the time since the stats page last opened, which the chart has too
+INSERT INTO reading_session (id, folder_id, started_at, ended_at, pages_turned, seconds)
+SELECT
+    lower(hex(randomblob(16))),
+    NULL,
+    updated_at || ' 00:00:00',
+    datetime(updated_at || ' 00:00:00', '+' || watchtime || ' seconds'),
+    0,
+    watchtime
+FROM chart
+WHERE watchtime > 0;
+
+DROP TABLE IF EXISTS chart;
+
+ALTER TABLE manga_folder DROP COLUMN time_spent_reading;
+
+ALTER TABLE global_manga DROP COLUMN time_spent_reading;
@@ -0,0 +1,10 @@
+-- at most one goal per period, progress and streaks are computed from reading_session
+
+CREATE TABLE IF NOT EXISTS reading_goal
+(
+    period TEXT PRIMARY KEY,
+    unit TEXT NOT NULL,
+    target INTEGER NOT NULL,
+    created_at TEXT,
+    updated_at TEXT
+);
@@ -0,0 +1,35 @@
+-- the mokuro sidecar a chapter's text was imported from, one row per manga_folder.
+-- the sidecar's modified time lets a rescan skip chapters whose text hasn't changed.
+
+CREATE TABLE IF NOT EXISTS text_source
+(
+    id TEXT PRIMARY KEY,
+    manga_folder_id TEXT NOT NULL UNIQUE REFERENCES manga_folder(id) ON DELETE CASCADE,
+    sidecar_path TEXT NOT NULL,
+    modified_at INTEGER NOT NULL,
+    imported_at TEXT
+);
+
+-- a block of text recognised on a panel. the box is in pixels of the image mokuro read,
+-- which is kept alongside so the reader can scale it to the displayed panel.
+CREATE TABLE IF NOT EXISTS text_block
+(
+    id TEXT PRIMARY KEY,
+    source_id TEXT NOT NULL REFERENCES text_source(id) ON DELETE CASCADE,
+    panel_id TEXT NOT NULL REFERENCES manga_panel(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    x1 REAL NOT NULL,
+    y1 REAL NOT NULL,
+    x2 REAL NOT NULL,
+    y2 REAL NOT NULL,
+    is_vertical BOOLEAN NOT NULL DEFAULT 0,
+    font_size REAL,
+    -- a json array with one string per line of the block
+    lines TEXT NOT NULL,
+    image_width INTEGER NOT NULL,
+    image_height INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_text_block_panel_id ON text_block(panel_id);
+
+CREATE INDEX IF NOT EXISTS idx_text_block_source_id ON text_block(source_id);
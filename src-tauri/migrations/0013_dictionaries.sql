@@ -0,0 +1,69 @@
+-- yomitan dictionaries imported from their zips, lookups go through them by priority.
+-- removing a dictionary removes its terms, kanji and frequencies through ON DELETE CASCADE.
+
+CREATE TABLE IF NOT EXISTS dictionary
+(
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL UNIQUE,
+    revision TEXT,
+    format INTEGER NOT NULL,
+    priority INTEGER NOT NULL,
+    term_count INTEGER NOT NULL DEFAULT 0,
+    kanji_count INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT,
+    updated_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS dictionary_term
+(
+    id INTEGER PRIMARY KEY,
+    dictionary_id TEXT NOT NULL REFERENCES dictionary(id) ON DELETE CASCADE,
+    expression TEXT NOT NULL,
+    reading TEXT NOT NULL,
+    definition_tags TEXT NOT NULL DEFAULT '',
+    rules TEXT NOT NULL DEFAULT '',
+    score INTEGER NOT NULL DEFAULT 0,
+    -- a json array of plain text definitions, structured content is flattened on import
+    glossary TEXT NOT NULL,
+    sequence INTEGER,
+    term_tags TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS dictionary_kanji
+(
+    id INTEGER PRIMARY KEY,
+    dictionary_id TEXT NOT NULL REFERENCES dictionary(id) ON DELETE CASCADE,
+    character TEXT NOT NULL,
+    onyomi TEXT NOT NULL DEFAULT '',
+    kunyomi TEXT NOT NULL DEFAULT '',
+    tags TEXT NOT NULL DEFAULT '',
+    -- a json array
+    meanings TEXT NOT NULL
+);
+
+-- frequency ranks from the meta banks, `kind` is either 'term' or 'kanji'
+CREATE TABLE IF NOT EXISTS dictionary_frequency
+(
+    id INTEGER PRIMARY KEY,
+    dictionary_id TEXT NOT NULL REFERENCES dictionary(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    expression TEXT NOT NULL,
+    -- set when the rank only applies to one reading of the term
+    reading TEXT,
+    value INTEGER NOT NULL,
+    display_value TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_term_expression ON dictionary_term(expression);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_term_reading ON dictionary_term(reading);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_term_dictionary_id ON dictionary_term(dictionary_id);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_kanji_character ON dictionary_kanji(character);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_kanji_dictionary_id ON dictionary_kanji(dictionary_id);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_frequency_expression ON dictionary_frequency(expression, kind);
+
+CREATE INDEX IF NOT EXISTS idx_dictionary_frequency_dictionary_id ON dictionary_frequency(dictionary_id);
@@ -0,0 +1,16 @@
+-- where mined cards go, a single row that only exists once the settings were saved.
+-- `fields` is a json object from an anki field of the note type to what fills it.
+
+CREATE TABLE IF NOT EXISTS anki_settings
+(
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    endpoint TEXT NOT NULL,
+    deck_name TEXT NOT NULL,
+    model_name TEXT NOT NULL,
+    fields TEXT NOT NULL,
+    -- a json array
+    tags TEXT NOT NULL,
+    duplicate_scope TEXT NOT NULL DEFAULT 'deck',
+    allow_duplicates BOOLEAN NOT NULL DEFAULT 0,
+    updated_at TEXT
+);
@@ -0,0 +1,41 @@
+-- words the reader already knows, imported from a word list or an anki export
+
+CREATE TABLE IF NOT EXISTS known_word
+(
+    word TEXT PRIMARY KEY,
+    imported_at TEXT
+);
+
+-- a chapter's text split into dictionary words. the analysis is redone when the text is
+-- reimported or the dictionaries change, which `text_imported_at` and `dictionaries` record.
+CREATE TABLE IF NOT EXISTS vocabulary_analysis
+(
+    manga_folder_id TEXT PRIMARY KEY REFERENCES manga_folder(id) ON DELETE CASCADE,
+    text_imported_at TEXT,
+    dictionaries TEXT NOT NULL,
+    token_count INTEGER NOT NULL,
+    analyzed_at TEXT
+);
+
+-- `word` is the dictionary form, or the text itself for runs no dictionary has a word for.
+-- `frequency_rank` is the best rank any frequency dictionary gives the word.
+CREATE TABLE IF NOT EXISTS vocabulary_word
+(
+    manga_folder_id TEXT NOT NULL REFERENCES vocabulary_analysis(manga_folder_id) ON DELETE CASCADE,
+    word TEXT NOT NULL,
+    reading TEXT,
+    in_dictionary BOOLEAN NOT NULL,
+    occurrences INTEGER NOT NULL,
+    frequency_rank INTEGER,
+    PRIMARY KEY (manga_folder_id, word)
+);
+
+CREATE TABLE IF NOT EXISTS vocabulary_kanji
+(
+    manga_folder_id TEXT NOT NULL REFERENCES vocabulary_analysis(manga_folder_id) ON DELETE CASCADE,
+    character TEXT NOT NULL,
+    occurrences INTEGER NOT NULL,
+    PRIMARY KEY (manga_folder_id, character)
+);
+
+CREATE INDEX IF NOT EXISTS idx_vocabulary_word_word ON vocabulary_word(word);
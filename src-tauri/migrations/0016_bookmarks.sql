@@ -0,0 +1,19 @@
+-- a marked panel with an optional title, note and colour. `panel_path` outlives the panel
+-- row, so a bookmark whose panel was removed, with its library root for example, is linked
+-- to the panel again once a rescan inserts it.
+
+CREATE TABLE IF NOT EXISTS bookmark
+(
+    id TEXT PRIMARY KEY,
+    panel_id TEXT REFERENCES manga_panel(id) ON DELETE SET NULL,
+    panel_path TEXT NOT NULL,
+    title TEXT,
+    note TEXT NOT NULL DEFAULT '',
+    color TEXT,
+    created_at TEXT,
+    updated_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_bookmark_panel_id ON bookmark(panel_id);
+
+CREATE INDEX IF NOT EXISTS idx_bookmark_panel_path ON bookmark(panel_path);
@@ -0,0 +1,5 @@
+-- dictionaries are imported a bank at a time, one transaction per bank. a dictionary stays
+-- out of lookups until its last bank is in, and one left behind by an interrupted import is
+-- removed on the next start.
+
+ALTER TABLE dictionary ADD COLUMN is_importing BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- yomitan frequency dictionaries either rank words, 1 being the most common, or count how
+-- often they occur. only ranks can be compared across dictionaries and bands, so the mode
+-- from index.json is kept. dictionaries imported before this are assumed to be ranked,
+-- importing one again records what it declares.
+
+ALTER TABLE dictionary ADD COLUMN frequency_mode TEXT NOT NULL DEFAULT 'rank-based';
@@ -0,0 +1,856 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{codecs::jpeg::JpegEncoder, ImageEncoder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult, PathContext};
+use crate::source::read_panel_bytes;
+
+/// Where AnkiConnect listens unless it was configured otherwise.
+pub const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:8765";
+
+// the AnkiConnect api version the requests are written against
+const API_VERSION: u8 = 6;
+// anki only answers while it's idle, a dialog left open stalls every request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const PICTURE_QUALITY: u8 = 85;
+// the art kept around a text block, as a share of the block's longer side
+const PICTURE_PADDING: f64 = 0.5;
+
+/// What fills a field of the note type.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CardField {
+    Word,
+    Reading,
+    // the lines of the text block the word was looked up in
+    Sentence,
+    Definition,
+    // the panel cropped to the text block
+    Picture,
+    // the title of the chapter
+    Source,
+}
+
+/// Where AnkiConnect looks for an existing note with the same first field.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum DuplicateScope {
+    Deck,
+    Collection,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnkiSettings {
+    pub endpoint: String,
+    pub deck_name: String,
+    pub model_name: String,
+    // anki field name to what fills it, fields that aren't listed stay empty
+    pub fields: BTreeMap<String, CardField>,
+    pub tags: Vec<String>,
+    pub duplicate_scope: DuplicateScope,
+    pub allow_duplicates: bool,
+}
+
+impl Default for AnkiSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            deck_name: "Default".to_string(),
+            model_name: "Basic".to_string(),
+            fields: BTreeMap::from([
+                ("Front".to_string(), CardField::Word),
+                ("Back".to_string(), CardField::Definition),
+            ]),
+            tags: vec!["manga_shelf".to_string()],
+            duplicate_scope: DuplicateScope::Deck,
+            allow_duplicates: false,
+        }
+    }
+}
+
+// `fields` and `tags` are stored as json
+#[derive(sqlx::FromRow)]
+struct AnkiSettingsRow {
+    endpoint: String,
+    deck_name: String,
+    model_name: String,
+    fields: String,
+    tags: String,
+    duplicate_scope: DuplicateScope,
+    allow_duplicates: bool,
+}
+
+impl From<AnkiSettingsRow> for AnkiSettings {
+    fn from(row: AnkiSettingsRow) -> Self {
+        Self {
+            endpoint: row.endpoint,
+            deck_name: row.deck_name,
+            model_name: row.model_name,
+            fields: serde_json::from_str(&row.fields).unwrap_or_default(),
+            tags: serde_json::from_str(&row.tags).unwrap_or_default(),
+            duplicate_scope: row.duplicate_scope,
+            allow_duplicates: row.allow_duplicates,
+        }
+    }
+}
+
+/// A word looked up in a text block, as it should end up on the card.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MinedWord {
+    pub block_id: String,
+    pub word: String,
+    pub reading: String,
+    pub definition: String,
+}
+
+/// The decks and note types of the collection, to pick from in the settings.
+#[derive(Debug, Serialize, Clone)]
+pub struct AnkiCatalog {
+    pub decks: Vec<String>,
+    pub models: Vec<String>,
+}
+
+// the text block a word was mined from and the panel it was read on
+#[derive(Debug, sqlx::FromRow)]
+struct MinedBlock {
+    lines: String,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    image_width: u32,
+    image_height: u32,
+    panel_path: String,
+    title: String,
+}
+
+/// A note as `addNote` and `canAddNotes` take it.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub deck_name: String,
+    pub model_name: String,
+    pub fields: BTreeMap<String, String>,
+    pub options: NoteOptions,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub picture: Vec<Picture>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteOptions {
+    pub allow_duplicate: bool,
+    pub duplicate_scope: DuplicateScope,
+}
+
+/// An image AnkiConnect stores in the media folder and adds to the end of `fields`.
+#[derive(Debug, Serialize, Clone)]
+pub struct Picture {
+    // base64
+    pub data: String,
+    pub filename: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AnkiResponse<T> {
+    result: Option<T>,
+    error: Option<String>,
+}
+
+/// A client for the AnkiConnect add-on, which takes every action as a json POST.
+pub struct AnkiConnect {
+    endpoint: String,
+    http: reqwest::Client,
+}
+
+impl AnkiConnect {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn invoke<T: DeserializeOwned>(&self, action: &str, params: Value) -> AppResult<T> {
+        let response: AnkiResponse<T> = self
+            .http
+            .post(&self.endpoint)
+            .timeout(REQUEST_TIMEOUT)
+            .json(&json!({ "action": action, "version": API_VERSION, "params": params }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(AppError::Anki(error)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(AppError::Anki(format!("`{action}` returned nothing"))),
+        }
+    }
+
+    pub async fn version(&self) -> AppResult<u32> {
+        self.invoke("version", json!({})).await
+    }
+
+    pub async fn deck_names(&self) -> AppResult<Vec<String>> {
+        self.invoke("deckNames", json!({})).await
+    }
+
+    pub async fn model_names(&self) -> AppResult<Vec<String>> {
+        self.invoke("modelNames", json!({})).await
+    }
+
+    pub async fn model_field_names(&self, model_name: &str) -> AppResult<Vec<String>> {
+        self.invoke("modelFieldNames", json!({ "modelName": model_name }))
+            .await
+    }
+
+    /// Whether each note could be added, false for duplicates unless the note allows them.
+    pub async fn can_add_notes(&self, notes: &[Note]) -> AppResult<Vec<bool>> {
+        self.invoke("canAddNotes", json!({ "notes": notes })).await
+    }
+
+    /// Adds the note and returns its id.
+    pub async fn add_note(&self, note: &Note) -> AppResult<i64> {
+        self.invoke("addNote", json!({ "note": note })).await
+    }
+}
+
+// anki fields are html
+fn html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+impl MinedBlock {
+    fn sentence(&self) -> String {
+        serde_json::from_str::<Vec<String>>(&self.lines)
+            .unwrap_or_default()
+            .concat()
+    }
+
+    /// The part of the panel shown on the card: the block's box, scaled from the image mokuro
+    /// read to the panel's actual size and widened to keep some of the art around it.
+    fn picture_bounds(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        if self.image_width == 0 || self.image_height == 0 {
+            return None;
+        }
+        let scale_x = width as f64 / self.image_width as f64;
+        let scale_y = height as f64 / self.image_height as f64;
+        let padding = PICTURE_PADDING * (self.x2 - self.x1).max(self.y2 - self.y1);
+
+        let clamp = |value: f64, max: u32| value.clamp(0.0, max as f64) as u32;
+        let x1 = clamp(((self.x1 - padding) * scale_x).floor(), width);
+        let y1 = clamp(((self.y1 - padding) * scale_y).floor(), height);
+        let x2 = clamp(((self.x2 + padding) * scale_x).ceil(), width);
+        let y2 = clamp(((self.y2 + padding) * scale_y).ceil(), height);
+
+        (x2 > x1 && y2 > y1).then_some((x1, y1, x2 - x1, y2 - y1))
+    }
+}
+
+fn crop_picture(block: &MinedBlock) -> AppResult<Vec<u8>> {
+    let bytes = read_panel_bytes(&block.panel_path).with_path(&block.panel_path)?;
+    let panel = image::load_from_memory(&bytes)?;
+
+    let (x, y, width, height) = block
+        .picture_bounds(panel.width(), panel.height())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("the text block is outside `{}`", block.panel_path))
+        })?;
+
+    let picture = panel.crop_imm(x, y, width, height).into_rgb8();
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, PICTURE_QUALITY).write_image(
+        &picture,
+        picture.width(),
+        picture.height(),
+        image::ExtendedColorType::Rgb8,
+    )?;
+
+    Ok(encoded)
+}
+
+/// Fills the fields of the note type from the word and its block, the picture is only
+/// attached when one is given, a duplicate check doesn't need it.
+fn build_note(
+    settings: &AnkiSettings,
+    word: &MinedWord,
+    block: &MinedBlock,
+    picture: Option<&[u8]>,
+) -> Note {
+    let fields = settings
+        .fields
+        .iter()
+        .map(|(name, field)| {
+            let value = match field {
+                CardField::Word => html(&word.word),
+                CardField::Reading => html(&word.reading),
+                CardField::Sentence => html(&block.sentence()),
+                CardField::Definition => html(&word.definition),
+                CardField::Picture => String::new(),
+                CardField::Source => html(&block.title),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+
+    let picture_fields: Vec<String> = settings
+        .fields
+        .iter()
+        .filter(|(_, field)| **field == CardField::Picture)
+        .map(|(name, _)| name.clone())
+        .collect();
+    let picture = match picture {
+        Some(bytes) if !picture_fields.is_empty() => vec![Picture {
+            data: STANDARD.encode(bytes),
+            filename: format!("manga_shelf_{}.jpg", uuid::Uuid::new_v4()),
+            fields: picture_fields,
+        }],
+        _ => Vec::new(),
+    };
+
+    Note {
+        deck_name: settings.deck_name.clone(),
+        model_name: settings.model_name.clone(),
+        fields,
+        options: NoteOptions {
+            allow_duplicate: settings.allow_duplicates,
+            duplicate_scope: settings.duplicate_scope,
+        },
+        tags: settings.tags.clone(),
+        picture,
+    }
+}
+
+/// The saved settings, or the defaults for a note of the built-in Basic type.
+pub async fn load_settings(pool: &SqlitePool) -> AppResult<AnkiSettings> {
+    let row: Option<AnkiSettingsRow> = sqlx::query_as(
+        "SELECT endpoint, deck_name, model_name, fields, tags, duplicate_scope, allow_duplicates
+        FROM anki_settings WHERE id = 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(AnkiSettings::from).unwrap_or_default())
+}
+
+async fn find_block(block_id: &str, pool: &SqlitePool) -> AppResult<MinedBlock> {
+    sqlx::query_as(
+        "SELECT
+        text_block.lines,
+        text_block.x1,
+        text_block.y1,
+        text_block.x2,
+        text_block.y2,
+        text_block.image_width,
+        text_block.image_height,
+        manga_panel.full_path AS panel_path,
+        manga_folder.title
+        FROM text_block
+        INNER JOIN manga_panel ON manga_panel.id = text_block.panel_id
+        INNER JOIN text_source ON text_source.id = text_block.source_id
+        INNER JOIN manga_folder ON manga_folder.id = text_source.manga_folder_id
+        WHERE text_block.id = ?",
+    )
+    .bind(block_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::not_found(block_id))
+}
+
+/// Whether a card for `word` can be added, false when it's a duplicate that isn't allowed.
+pub async fn can_mine(
+    word: &MinedWord,
+    settings: &AnkiSettings,
+    pool: &SqlitePool,
+) -> AppResult<bool> {
+    let block = find_block(&word.block_id, pool).await?;
+    let note = build_note(settings, word, &block, None);
+
+    let client = AnkiConnect::new(&settings.endpoint);
+    let can_add = client.can_add_notes(&[note]).await?;
+
+    Ok(can_add.first().copied().unwrap_or(false))
+}
+
+/// Adds a card for `word` and returns the id of its note.
+pub async fn mine(word: &MinedWord, settings: &AnkiSettings, pool: &SqlitePool) -> AppResult<i64> {
+    let block = find_block(&word.block_id, pool).await?;
+    let client = AnkiConnect::new(&settings.endpoint);
+
+    // checked before the panel is cropped, anki would refuse the note anyway
+    let note = build_note(settings, word, &block, None);
+    if !client
+        .can_add_notes(&[note])
+        .await?
+        .first()
+        .copied()
+        .unwrap_or(false)
+    {
+        return Err(AppError::Anki(format!(
+            "`{}` is already in `{}`",
+            word.word, settings.deck_name
+        )));
+    }
+
+    let has_picture = settings
+        .fields
+        .values()
+        .any(|field| *field == CardField::Picture);
+    let (block, picture) = tokio::task::spawn_blocking(move || {
+        let picture = match has_picture {
+            true => Some(crop_picture(&block)?),
+            false => None,
+        };
+        AppResult::Ok((block, picture))
+    })
+    .await??;
+
+    let note = build_note(settings, word, &block, picture.as_deref());
+    client.add_note(&note).await
+}
+
+#[tauri::command]
+pub async fn get_anki_settings(handle: AppHandle) -> AppResult<AnkiSettings> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    load_settings(&pool).await
+}
+
+#[tauri::command]
+pub async fn set_anki_settings(
+    settings: AnkiSettings,
+    handle: AppHandle,
+) -> AppResult<AnkiSettings> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    if settings.deck_name.trim().is_empty() || settings.model_name.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "anki settings need a deck and a note type".into(),
+        ));
+    }
+    if settings.fields.is_empty() {
+        return Err(AppError::InvalidInput(
+            "anki settings need at least one field to fill".into(),
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO anki_settings (
+        id,
+        endpoint,
+        deck_name,
+        model_name,
+        fields,
+        tags,
+        duplicate_scope,
+        allow_duplicates,
+        updated_at
+        )
+        VALUES (1, ?, ?, ?, ?, ?, ?, ?, datetime('now', 'localtime'))
+        ON CONFLICT (id) DO UPDATE SET
+        endpoint = excluded.endpoint,
+        deck_name = excluded.deck_name,
+        model_name = excluded.model_name,
+        fields = excluded.fields,
+        tags = excluded.tags,
+        duplicate_scope = excluded.duplicate_scope,
+        allow_duplicates = excluded.allow_duplicates,
+        updated_at = excluded.updated_at",
+    )
+    .bind(settings.endpoint.trim())
+    .bind(&settings.deck_name)
+    .bind(&settings.model_name)
+    .bind(serde_json::to_string(&settings.fields)?)
+    .bind(serde_json::to_string(&settings.tags)?)
+    .bind(settings.duplicate_scope)
+    .bind(settings.allow_duplicates)
+    .execute(&pool)
+    .await?;
+
+    load_settings(&pool).await
+}
+
+/// The decks and note types of the collection AnkiConnect at `endpoint` serves.
+#[tauri::command]
+pub async fn get_anki_catalog(endpoint: String) -> AppResult<AnkiCatalog> {
+    let client = AnkiConnect::new(&endpoint);
+
+    // older versions answer in a different shape
+    let version = client.version().await?;
+    if version < API_VERSION as u32 {
+        return Err(AppError::Anki(format!(
+            "version {version} is too old, {API_VERSION} or newer is needed"
+        )));
+    }
+
+    Ok(AnkiCatalog {
+        decks: client.deck_names().await?,
+        models: client.model_names().await?,
+    })
+}
+
+#[tauri::command]
+pub async fn get_anki_model_fields(endpoint: String, model_name: String) -> AppResult<Vec<String>> {
+    AnkiConnect::new(&endpoint)
+        .model_field_names(&model_name)
+        .await
+}
+
+#[tauri::command]
+pub async fn can_mine_word(word: MinedWord, handle: AppHandle) -> AppResult<bool> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let settings = load_settings(&pool).await?;
+    can_mine(&word, &settings, &pool).await
+}
+
+/// Creates a card from a looked up word, its text block and the panel cropped around it.
+#[tauri::command]
+pub async fn mine_word(word: MinedWord, handle: AppHandle) -> AppResult<i64> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let settings = load_settings(&pool).await?;
+    mine(&word, &settings, &pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    type Requests = Arc<StdMutex<Vec<Value>>>;
+
+    // a local stand-in for AnkiConnect, it answers with `responses` in order
+    // and keeps the json body of every request
+    async fn stand_in(responses: Vec<Value>) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let body = read_body(&mut stream).await;
+                received
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap());
+
+                let response = response.to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        (endpoint, requests)
+    }
+
+    async fn read_body(stream: &mut TcpStream) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0; 4096];
+        loop {
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "the request ended early");
+            buffer.extend_from_slice(&chunk[..read]);
+
+            let Some(head_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") else {
+                continue;
+            };
+            let head = String::from_utf8_lossy(&buffer[..head_end]).to_lowercase();
+            let length: usize = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |length| length.trim().parse().unwrap());
+
+            let body_start = head_end + 4;
+            while buffer.len() < body_start + length {
+                let read = stream.read(&mut chunk).await.unwrap();
+                assert!(read > 0, "the body ended early");
+                buffer.extend_from_slice(&chunk[..read]);
+            }
+            return buffer[body_start..body_start + length].to_vec();
+        }
+    }
+
+    fn block(panel_path: &str) -> MinedBlock {
+        MinedBlock {
+            lines: json!(["お前は", "もう死んでいる"]).to_string(),
+            x1: 100.0,
+            y1: 200.0,
+            x2: 140.0,
+            y2: 300.0,
+            image_width: 1000,
+            image_height: 1500,
+            panel_path: panel_path.to_string(),
+            title: "Chapter <1>".to_string(),
+        }
+    }
+
+    fn word(block_id: &str) -> MinedWord {
+        MinedWord {
+            block_id: block_id.to_string(),
+            word: "死ぬ".to_string(),
+            reading: "しぬ".to_string(),
+            definition: "to die\nto pass away".to_string(),
+        }
+    }
+
+    fn mining_settings(endpoint: &str) -> AnkiSettings {
+        AnkiSettings {
+            endpoint: endpoint.to_string(),
+            deck_name: "Mining".to_string(),
+            model_name: "Japanese".to_string(),
+            fields: BTreeMap::from([
+                ("Expression".to_string(), CardField::Word),
+                ("Reading".to_string(), CardField::Reading),
+                ("Sentence".to_string(), CardField::Sentence),
+                ("Meaning".to_string(), CardField::Definition),
+                ("Picture".to_string(), CardField::Picture),
+                ("Source".to_string(), CardField::Source),
+            ]),
+            ..AnkiSettings::default()
+        }
+    }
+
+    // a migrated database with one chapter whose only panel has one text block
+    async fn mined_chapter(panel_path: &str) -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query("INSERT INTO manga_folder (id, title, full_path) VALUES ('folder', 'Chapter 1', '/manga/ch1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO manga_panel (id, title, full_path) VALUES ('panel', '001.png', ?)",
+        )
+        .bind(panel_path)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO text_source (id, manga_folder_id, sidecar_path, modified_at) VALUES ('source', 'folder', '/manga/ch1.mokuro', 0)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let block = block(panel_path);
+        sqlx::query(
+            "INSERT INTO text_block (id, source_id, panel_id, position, x1, y1, x2, y2, lines, image_width, image_height)
+            VALUES ('block', 'source', 'panel', 0, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(block.x1)
+        .bind(block.y1)
+        .bind(block.x2)
+        .bind(block.y2)
+        .bind(&block.lines)
+        .bind(block.image_width)
+        .bind(block.image_height)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn actions_are_sent_with_the_api_version() {
+        let (endpoint, requests) = stand_in(vec![
+            json!({ "result": ["Default", "Mining"], "error": null }),
+        ])
+        .await;
+
+        let decks = AnkiConnect::new(&endpoint).deck_names().await.unwrap();
+
+        assert_eq!(decks, ["Default", "Mining"]);
+        assert_eq!(
+            requests.lock().unwrap()[0],
+            json!({ "action": "deckNames", "version": 6, "params": {} })
+        );
+    }
+
+    #[tokio::test]
+    async fn anki_errors_are_returned() {
+        let (endpoint, _) = stand_in(vec![
+            json!({ "result": null, "error": "model was not found: Nope" }),
+        ])
+        .await;
+
+        let error = AnkiConnect::new(&endpoint)
+            .model_field_names("Nope")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AppError::Anki(message) if message.contains("Nope")));
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_anki_is_an_http_error() {
+        // bound and dropped, so nothing listens on the port
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error = AnkiConnect::new(&endpoint).version().await.unwrap_err();
+
+        assert_eq!(error.kind(), "http");
+    }
+
+    #[test]
+    fn fields_are_filled_from_the_mapping_as_html() {
+        let settings = mining_settings(DEFAULT_ENDPOINT);
+
+        let note = build_note(
+            &settings,
+            &word("block"),
+            &block("/panel.png"),
+            Some(b"jpeg"),
+        );
+
+        assert_eq!(note.fields["Expression"], "死ぬ");
+        assert_eq!(note.fields["Reading"], "しぬ");
+        assert_eq!(note.fields["Sentence"], "お前はもう死んでいる");
+        assert_eq!(note.fields["Meaning"], "to die<br>to pass away");
+        assert_eq!(note.fields["Source"], "Chapter &lt;1&gt;");
+        assert_eq!(note.fields["Picture"], "");
+        assert_eq!(note.picture.len(), 1);
+        assert_eq!(note.picture[0].fields, ["Picture"]);
+        assert_eq!(note.picture[0].data, STANDARD.encode(b"jpeg"));
+    }
+
+    #[test]
+    fn notes_serialize_like_anki_connect_expects() {
+        let settings = AnkiSettings::default();
+
+        let note = build_note(
+            &settings,
+            &word("block"),
+            &block("/panel.png"),
+            Some(b"jpeg"),
+        );
+        let value = serde_json::to_value(&note).unwrap();
+
+        assert_eq!(value["deckName"], "Default");
+        assert_eq!(value["modelName"], "Basic");
+        assert_eq!(
+            value["options"],
+            json!({ "allowDuplicate": false, "duplicateScope": "deck" })
+        );
+        // no field takes the picture, so none is sent
+        assert!(value.get("picture").is_none());
+    }
+
+    #[test]
+    fn picture_bounds_scale_the_block_to_the_panel() {
+        // the panel is half the size of the image mokuro read
+        let bounds = block("/panel.png").picture_bounds(500, 750);
+
+        // the block is 40x100, padded by 50 on every side before scaling
+        assert_eq!(bounds, Some((25, 75, 70, 100)));
+    }
+
+    #[test]
+    fn picture_bounds_stay_inside_the_panel() {
+        let mut corner = block("/panel.png");
+        corner.x1 = 0.0;
+        corner.y1 = 0.0;
+        corner.x2 = 1000.0;
+        corner.y2 = 100.0;
+
+        assert_eq!(corner.picture_bounds(1000, 1500), Some((0, 0, 1000, 600)));
+
+        corner.image_width = 0;
+        assert_eq!(corner.picture_bounds(1000, 1500), None);
+    }
+
+    #[tokio::test]
+    async fn mining_adds_a_note_with_the_cropped_panel() {
+        let dir = std::env::temp_dir().join(format!("anki-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let panel_path = dir.join("001.png");
+        image::RgbImage::new(1000, 1500).save(&panel_path).unwrap();
+        let panel_path = panel_path.to_string_lossy().into_owned();
+
+        let pool = mined_chapter(&panel_path).await;
+        let (endpoint, requests) = stand_in(vec![
+            json!({ "result": [true], "error": null }),
+            json!({ "result": 1496198395707_i64, "error": null }),
+        ])
+        .await;
+
+        let note_id = mine(&word("block"), &mining_settings(&endpoint), &pool)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(note_id, 1496198395707);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[0]["action"], "canAddNotes");
+        assert!(requests[0]["params"]["notes"][0].get("picture").is_none());
+        assert_eq!(requests[1]["action"], "addNote");
+
+        let note = &requests[1]["params"]["note"];
+        assert_eq!(note["fields"]["Sentence"], "お前はもう死んでいる");
+        assert_eq!(note["fields"]["Source"], "Chapter 1");
+        let picture = STANDARD
+            .decode(note["picture"][0]["data"].as_str().unwrap())
+            .unwrap();
+        let picture = image::load_from_memory(&picture).unwrap();
+        assert_eq!((picture.width(), picture.height()), (140, 200));
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_refused_before_cropping() {
+        // the panel doesn't exist, cropping it would fail
+        let pool = mined_chapter("/missing/001.png").await;
+        let (endpoint, requests) =
+            stand_in(vec![json!({ "result": [false], "error": null })]).await;
+
+        let error = mine(&word("block"), &mining_settings(&endpoint), &pool)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AppError::Anki(message) if message.contains("死ぬ")));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn settings_default_until_saved() {
+        let pool = mined_chapter("/panel.png").await;
+
+        let settings = load_settings(&pool).await.unwrap();
+
+        assert_eq!(settings.endpoint, DEFAULT_ENDPOINT);
+        assert_eq!(settings.fields["Front"], CardField::Word);
+    }
+}
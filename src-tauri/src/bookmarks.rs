@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult, PathContext};
+use crate::global::set_global_manga_folder;
+use crate::manga::split_path_parts;
+use crate::ordering::{ChapterKey, PanelKey};
+use crate::source::ChapterSource;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum BookmarkColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+/// A bookmarked panel and the chapter it's in. The chapter is `None` while the panel is
+/// missing from the library, until a rescan finds it at `panel_path` again.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct Bookmark {
+    pub id: String,
+    pub panel_id: Option<String>,
+    pub panel_path: String,
+    pub title: Option<String>,
+    pub note: String,
+    pub color: Option<BookmarkColor>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub chapter_path: Option<String>,
+    pub chapter_title: Option<String>,
+}
+
+/// Where the reader opens a bookmark, `panel_index` is the panel's place in `get_manga_panels`.
+#[derive(Debug, Serialize, Clone)]
+pub struct BookmarkTarget {
+    pub chapter_path: String,
+    pub panel_path: String,
+    pub panel_index: usize,
+}
+
+// every bookmark with the chapter its panel is in, filtered by the callers.
+// the linked panel's path wins, it follows renames the stored path may not have seen
+const BOOKMARK_SELECT: &str = "SELECT
+    bookmark.id,
+    bookmark.panel_id,
+    COALESCE(manga_panel.full_path, bookmark.panel_path) AS panel_path,
+    bookmark.title,
+    bookmark.note,
+    bookmark.color,
+    bookmark.created_at,
+    bookmark.updated_at,
+    manga_folder.full_path AS chapter_path,
+    manga_folder.title AS chapter_title
+    FROM bookmark
+    LEFT JOIN manga_panel ON manga_panel.id = bookmark.panel_id
+    LEFT JOIN manga_folder ON manga_folder.id = manga_panel.folder_id";
+
+/// Links bookmarks that lost their panel to the panel now registered at the same path.
+/// Runs after panels are inserted, so bookmarks survive their chapter being removed and rescanned.
+pub async fn relink_bookmarks(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE bookmark SET panel_id = (
+            SELECT id FROM manga_panel WHERE manga_panel.full_path = bookmark.panel_path
+        )
+        WHERE panel_id IS NULL",
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn find_bookmark(id: &str, pool: &SqlitePool) -> AppResult<Bookmark> {
+    sqlx::query_as(&format!("{BOOKMARK_SELECT} WHERE bookmark.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(id))
+}
+
+// chapters in reading order, then their panels
+fn sort_in_reading_order(bookmarks: &mut [Bookmark]) {
+    bookmarks.sort_by_cached_key(|bookmark| {
+        (
+            bookmark
+                .chapter_path
+                .as_deref()
+                .map(|path| ChapterKey::parse(&split_path_parts(path).file_name)),
+            bookmark.chapter_path.clone(),
+            PanelKey::parse(&split_path_parts(&bookmark.panel_path).file_name),
+        )
+    });
+}
+
+// a title or note made of whitespace is the same as none
+fn trimmed(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+#[tauri::command]
+pub async fn create_bookmark(
+    panel_path: String,
+    title: Option<String>,
+    note: Option<String>,
+    color: Option<BookmarkColor>,
+    handle: AppHandle,
+) -> AppResult<Bookmark> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let panel_id: String = sqlx::query_scalar("SELECT id FROM manga_panel WHERE full_path = ?")
+        .bind(&panel_path)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(&panel_path))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO bookmark (id, panel_id, panel_path, title, note, color, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, datetime('now', 'localtime'), datetime('now', 'localtime'))",
+    )
+    .bind(&id)
+    .bind(panel_id)
+    .bind(&panel_path)
+    .bind(trimmed(title))
+    .bind(trimmed(note).unwrap_or_default())
+    .bind(color)
+    .execute(&pool)
+    .await?;
+
+    find_bookmark(&id, &pool).await
+}
+
+/// Replaces the title, note and colour of a bookmark, the panel it marks stays the same.
+#[tauri::command]
+pub async fn update_bookmark(
+    id: String,
+    title: Option<String>,
+    note: Option<String>,
+    color: Option<BookmarkColor>,
+    handle: AppHandle,
+) -> AppResult<Bookmark> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query(
+        "UPDATE bookmark SET
+        title = ?,
+        note = ?,
+        color = ?,
+        updated_at = datetime('now', 'localtime')
+        WHERE id = ?",
+    )
+    .bind(trimmed(title))
+    .bind(trimmed(note).unwrap_or_default())
+    .bind(color)
+    .bind(&id)
+    .execute(&pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&id));
+    }
+
+    find_bookmark(&id, &pool).await
+}
+
+#[tauri::command]
+pub async fn delete_bookmark(id: String, handle: AppHandle) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("DELETE FROM bookmark WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&id));
+    }
+
+    Ok(())
+}
+
+/// The bookmarks of a chapter in reading order.
+#[tauri::command]
+pub async fn get_chapter_bookmarks(
+    chapter_path: String,
+    handle: AppHandle,
+) -> AppResult<Vec<Bookmark>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    list_chapter_bookmarks(&chapter_path, &pool).await
+}
+
+async fn list_chapter_bookmarks(chapter_path: &str, pool: &SqlitePool) -> AppResult<Vec<Bookmark>> {
+    let mut bookmarks: Vec<Bookmark> = sqlx::query_as(&format!(
+        "{BOOKMARK_SELECT} WHERE manga_folder.full_path = ?"
+    ))
+    .bind(chapter_path)
+    .fetch_all(pool)
+    .await?;
+    sort_in_reading_order(&mut bookmarks);
+
+    Ok(bookmarks)
+}
+
+/// The bookmarks of every chapter below a series or volume, in reading order.
+#[tauri::command]
+pub async fn get_series_bookmarks(
+    series_path: String,
+    handle: AppHandle,
+) -> AppResult<Vec<Bookmark>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    list_series_bookmarks(&series_path, &pool).await
+}
+
+async fn list_series_bookmarks(series_path: &str, pool: &SqlitePool) -> AppResult<Vec<Bookmark>> {
+    let mut bookmarks: Vec<Bookmark> = sqlx::query_as(&format!(
+        "WITH RECURSIVE folder(id) AS (
+            SELECT id FROM parent_folder WHERE full_path = ?
+            UNION
+            SELECT parent_folder.id FROM parent_folder
+            INNER JOIN folder ON parent_folder.parent_id = folder.id
+        )
+        {BOOKMARK_SELECT}
+        WHERE manga_folder.parent_id IN folder"
+    ))
+    .bind(series_path)
+    .fetch_all(pool)
+    .await?;
+    sort_in_reading_order(&mut bookmarks);
+
+    Ok(bookmarks)
+}
+
+/// Every bookmark, the most recently changed first. Bookmarks whose panel is missing from
+/// the library are included, without a chapter.
+#[tauri::command]
+pub async fn get_all_bookmarks(handle: AppHandle) -> AppResult<Vec<Bookmark>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    Ok(list_all_bookmarks(&pool).await?)
+}
+
+pub async fn list_all_bookmarks(pool: &SqlitePool) -> Result<Vec<Bookmark>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "{BOOKMARK_SELECT} ORDER BY bookmark.updated_at DESC"
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Makes the bookmarked chapter the one the reader opens and finds the panel in it.
+#[tauri::command]
+pub async fn open_bookmark(id: String, handle: AppHandle) -> AppResult<BookmarkTarget> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let target = find_bookmark_target(&id, &pool).await?;
+    set_global_manga_folder(&target.chapter_path, handle.clone()).await?;
+
+    Ok(target)
+}
+
+async fn find_bookmark_target(id: &str, pool: &SqlitePool) -> AppResult<BookmarkTarget> {
+    let bookmark = find_bookmark(id, pool).await?;
+    let chapter_path = bookmark
+        .chapter_path
+        .ok_or_else(|| AppError::not_found(&bookmark.panel_path))?;
+
+    let path = chapter_path.clone();
+    let panel_paths = tokio::task::spawn_blocking(move || match ChapterSource::open(&path) {
+        Some(source) => source.panel_paths().with_path(&path),
+        None => Err(AppError::not_found(&path)),
+    })
+    .await??;
+    let panel_index = panel_paths
+        .iter()
+        .position(|path| *path == bookmark.panel_path)
+        .ok_or_else(|| AppError::not_found(&bookmark.panel_path))?;
+
+    Ok(BookmarkTarget {
+        chapter_path,
+        panel_path: bookmark.panel_path,
+        panel_index,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::register_new_path;
+    use crate::scanner::{find_panels, insert_panels};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+    use std::path::{Path, PathBuf};
+
+    const PANELS: [&str; 6] = [
+        "Series/Vol 01/Ch 1/1.png",
+        "Series/Vol 01/Ch 2/1.png",
+        "Series/Vol 01/Ch 2/2.png",
+        "Series/Vol 01/Ch 2/10.png",
+        "Series/Vol 02/Ch 10/1.png",
+        "Other/Ch 1/1.png",
+    ];
+
+    fn path(root: &Path, relative: &str) -> String {
+        root.join(relative).to_string_lossy().into_owned()
+    }
+
+    // registers the root and indexes its panels the way adding it and a scan would
+    async fn scan(root: &Path, pool: &SqlitePool) {
+        sqlx::query("INSERT INTO library_root (id, full_path) VALUES ('root', ?)")
+            .bind(root.to_string_lossy())
+            .execute(pool)
+            .await
+            .unwrap();
+        register_new_path(root, pool).await.unwrap();
+
+        let (mut panels, mut errors) = (Vec::new(), Vec::new());
+        find_panels(root, &mut panels, &mut errors);
+        let sized: Vec<(String, u16, u16)> = panels.into_iter().map(|path| (path, 0, 0)).collect();
+        insert_panels(&sized, pool).await.unwrap();
+    }
+
+    async fn bookmarked_library() -> (PathBuf, SqlitePool) {
+        let root = std::env::temp_dir().join(format!("bookmarks-{}", uuid::Uuid::new_v4()));
+        for panel in PANELS {
+            let panel = root.join(panel);
+            create_dir_all(panel.parent().unwrap()).unwrap();
+            write(panel, b"").unwrap();
+        }
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+        scan(&root, &pool).await;
+
+        // bookmarked out of reading order
+        for (id, panel) in [("d", 4), ("c", 3), ("b", 2), ("a", 0), ("other", 5)] {
+            sqlx::query(
+                "INSERT INTO bookmark (id, panel_id, panel_path, updated_at)
+                SELECT ?, id, full_path, datetime('now') FROM manga_panel WHERE full_path = ?",
+            )
+            .bind(id)
+            .bind(path(&root, PANELS[panel]))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        (root, pool)
+    }
+
+    fn ids(bookmarks: &[Bookmark]) -> Vec<&str> {
+        bookmarks
+            .iter()
+            .map(|bookmark| bookmark.id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn lists_bookmarks_in_reading_order() {
+        let (root, pool) = bookmarked_library().await;
+
+        let cases = [
+            (
+                list_series_bookmarks(&path(&root, "Series"), &pool).await,
+                vec!["a", "b", "c", "d"],
+                "series and its volumes",
+            ),
+            (
+                list_series_bookmarks(&path(&root, "Series/Vol 01"), &pool).await,
+                vec!["a", "b", "c"],
+                "one volume",
+            ),
+            (
+                list_chapter_bookmarks(&path(&root, "Series/Vol 01/Ch 2"), &pool).await,
+                vec!["b", "c"],
+                "2 comes before 10",
+            ),
+            (
+                list_series_bookmarks(&path(&root, "Missing"), &pool).await,
+                vec![],
+                "unknown series",
+            ),
+        ];
+        for (bookmarks, expected, message) in cases {
+            assert_eq!(ids(&bookmarks.unwrap()), expected, "{message}");
+        }
+
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bookmarks_survive_removing_the_root() {
+        let (root, pool) = bookmarked_library().await;
+
+        // the chapters and panels go with the root
+        sqlx::query("DELETE FROM library_root")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let orphaned = list_all_bookmarks(&pool).await.unwrap();
+        assert_eq!(orphaned.len(), 5);
+        for bookmark in &orphaned {
+            assert_eq!(bookmark.panel_id, None, "{} lost its panel", bookmark.id);
+            assert_eq!(
+                bookmark.chapter_path, None,
+                "{} lost its chapter",
+                bookmark.id
+            );
+        }
+        assert!(find_bookmark_target("a", &pool).await.is_err());
+
+        scan(&root, &pool).await;
+        for bookmark in list_all_bookmarks(&pool).await.unwrap() {
+            assert!(
+                bookmark.panel_id.is_some(),
+                "{} is linked again",
+                bookmark.id
+            );
+            assert_eq!(
+                bookmark.chapter_path,
+                Some(split_path_parts(&bookmark.panel_path).parent),
+                "{} is in its chapter again",
+                bookmark.id
+            );
+        }
+
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bookmark_target_is_the_panel_index() {
+        let (root, pool) = bookmarked_library().await;
+
+        // `c` is 10.png, after 1.png and 2.png
+        for (id, index) in [("a", 0), ("b", 1), ("c", 2), ("d", 0)] {
+            let target = find_bookmark_target(id, &pool).await.unwrap();
+            let bookmark = find_bookmark(id, &pool).await.unwrap();
+            assert_eq!(target.panel_index, index, "{id}");
+            assert_eq!(target.panel_path, bookmark.panel_path, "{id}");
+            assert_eq!(Some(target.chapter_path), bookmark.chapter_path, "{id}");
+        }
+
+        // a panel deleted from disk but not yet from the library can't be opened
+        std::fs::remove_file(root.join(PANELS[3])).unwrap();
+        assert!(find_bookmark_target("c", &pool).await.is_err());
+
+        remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,459 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+
+// enough for a century of weeks, anything longer is a mistake in the requested range
+const MAX_POINTS: usize = 5500;
+
+/// How much of the calendar each point of a time series covers.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    Day,
+    // weeks start on monday, like the weekly goal
+    Week,
+    Month,
+    Year,
+}
+
+/// Reading done between `start` and `end`, both inclusive. The first and last points are cut
+/// to the requested range, so a week bucket can cover fewer than seven days.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ChartPoint {
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub seconds: u32,
+    pub pages_turned: u32,
+}
+
+/// A reading session with its local wall clock times parsed.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSpan {
+    pub started_at: NaiveDateTime,
+    pub ended_at: NaiveDateTime,
+    pub seconds: u32,
+    pub pages_turned: u32,
+}
+
+#[derive(sqlx::FromRow)]
+struct SessionRow {
+    started_at: String,
+    ended_at: String,
+    seconds: u32,
+    pages_turned: u32,
+}
+
+impl Bucket {
+    /// The first day of the bucket holding `date`.
+    fn start_of(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date.week(Weekday::Mon).first_day(),
+            Self::Month => date - Days::new(date.day0() as u64),
+            Self::Year => date - Days::new(date.ordinal0() as u64),
+        }
+    }
+
+    /// The first day of the bucket after the one starting on `start`.
+    fn next(self, start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Self::Day => start.checked_add_days(Days::new(1)),
+            Self::Week => start.checked_add_days(Days::new(7)),
+            Self::Month => start.checked_add_months(Months::new(1)),
+            Self::Year => start.checked_add_months(Months::new(12)),
+        }
+    }
+
+    fn label(self, start: NaiveDate) -> String {
+        match self {
+            Self::Day => start.format("%Y-%m-%d").to_string(),
+            // the iso year, so the week holding new year's day is labelled once
+            Self::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Self::Month => start.format("%Y-%m").to_string(),
+            Self::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+impl SessionSpan {
+    /// Splits the session's seconds over the calendar days it ran on, in proportion to the wall
+    /// clock time spent on each. The shares always add up to `seconds`, even when a DST change
+    /// makes the wall clock disagree with the recorded length.
+    fn seconds_per_day(&self) -> Vec<(NaiveDate, u32)> {
+        let first_day = self.started_at.date();
+        let last_day = self.ended_at.date();
+        let wall_seconds = (self.ended_at - self.started_at).num_seconds();
+        if last_day <= first_day || wall_seconds <= 0 {
+            return vec![(first_day, self.seconds)];
+        }
+
+        let mut shares = Vec::new();
+        let mut assigned = 0;
+        for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+            let share = if day == last_day {
+                self.seconds - assigned
+            } else {
+                let day_start = day.and_time(NaiveTime::MIN).max(self.started_at);
+                let day_end = day
+                    .succ_opt()
+                    .map_or(self.ended_at, |next| next.and_time(NaiveTime::MIN))
+                    .min(self.ended_at);
+                let overlap = (day_end - day_start).num_seconds().max(0) as u64;
+                (self.seconds as u64 * overlap / wall_seconds as u64) as u32
+            };
+            assigned += share;
+            shares.push((day, share));
+        }
+
+        shares
+    }
+}
+
+/// Buckets the sessions into consecutive points covering `start..=end`. Pages turned are
+/// counted on the day a session started, since there's no telling when each page was turned.
+pub fn time_series(
+    sessions: &[SessionSpan],
+    start: NaiveDate,
+    end: NaiveDate,
+    bucket: Bucket,
+) -> AppResult<Vec<ChartPoint>> {
+    if end < start {
+        return Err(AppError::InvalidInput(format!(
+            "chart range ends on {end} before it starts on {start}"
+        )));
+    }
+
+    let mut points = Vec::new();
+    let mut bucket_start = bucket.start_of(start);
+    loop {
+        if points.len() == MAX_POINTS {
+            return Err(AppError::InvalidInput(format!(
+                "chart range from {start} to {end} has more than {MAX_POINTS} points"
+            )));
+        }
+
+        let next = bucket.next(bucket_start);
+        let bucket_end = next
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(NaiveDate::MAX);
+        points.push(ChartPoint {
+            label: bucket.label(bucket_start),
+            start: bucket_start.max(start),
+            end: bucket_end.min(end),
+            seconds: 0,
+            pages_turned: 0,
+        });
+
+        match next {
+            Some(next) if next <= end => bucket_start = next,
+            _ => break,
+        }
+    }
+
+    // points are sorted and don't overlap, so the first one not ending before a day holds it
+    let point_of = |points: &[ChartPoint], day: NaiveDate| {
+        (start..=end)
+            .contains(&day)
+            .then(|| points.partition_point(|point| point.end < day))
+    };
+
+    for session in sessions {
+        for (day, seconds) in session.seconds_per_day() {
+            if let Some(index) = point_of(&points, day) {
+                points[index].seconds += seconds;
+            }
+        }
+        if let Some(index) = point_of(&points, session.started_at.date()) {
+            points[index].pages_turned += session.pages_turned;
+        }
+    }
+
+    Ok(points)
+}
+
+/// Reading time from `start` to `end`, both inclusive local dates, one point per bucket.
+#[tauri::command]
+pub async fn get_reading_chart(
+    start: NaiveDate,
+    end: NaiveDate,
+    bucket: Bucket,
+    handle: AppHandle,
+) -> AppResult<Vec<ChartPoint>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let sessions = sessions_between(start, end, &pool).await?;
+
+    time_series(&sessions, start, end, bucket)
+}
+
+/// Sessions that ran on any day from `start` to `end`.
+async fn sessions_between(
+    start: NaiveDate,
+    end: NaiveDate,
+    pool: &SqlitePool,
+) -> AppResult<Vec<SessionSpan>> {
+    let rows: Vec<SessionRow> = sqlx::query_as(
+        "SELECT started_at, ended_at, seconds, pages_turned FROM reading_session
+        WHERE date(started_at) <= ? AND date(ended_at) >= ?",
+    )
+    .bind(end.to_string())
+    .bind(start.to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let parse = |date: &str| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S");
+
+    let mut sessions = Vec::with_capacity(rows.len());
+    for row in rows {
+        // skip rows with a malformed date rather than failing the whole chart
+        let (Ok(started_at), Ok(ended_at)) = (parse(&row.started_at), parse(&row.ended_at)) else {
+            eprintln!(
+                "Error Parsing Session Dates: {} {}",
+                row.started_at, row.ended_at
+            );
+            continue;
+        };
+        sessions.push(SessionSpan {
+            started_at,
+            ended_at,
+            seconds: row.seconds,
+            pages_turned: row.pages_turned,
+        });
+    }
+
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        date(year, month, day).and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn session(started_at: NaiveDateTime, ended_at: NaiveDateTime, seconds: u32) -> SessionSpan {
+        SessionSpan {
+            started_at,
+            ended_at,
+            seconds,
+            pages_turned: 10,
+        }
+    }
+
+    fn labels(points: &[ChartPoint]) -> Vec<&str> {
+        points.iter().map(|point| point.label.as_str()).collect()
+    }
+
+    #[test]
+    fn day_buckets_reach_the_last_day_of_every_month() {
+        for month in 1..=12 {
+            let first = date(2025, month, 1);
+            let last = Bucket::Month.next(first).unwrap().pred_opt().unwrap();
+            let sessions = [session(
+                at(2025, month, last.day(), 20, 0),
+                at(2025, month, last.day(), 21, 0),
+                3600,
+            )];
+
+            let points = time_series(&sessions, first, last, Bucket::Day).unwrap();
+
+            assert_eq!(points.len(), last.day() as usize);
+            assert_eq!(points.last().unwrap().start, last);
+            assert_eq!(points.last().unwrap().seconds, 3600);
+        }
+    }
+
+    #[test]
+    fn month_buckets_include_december() {
+        let sessions = [
+            session(at(2025, 12, 31, 22, 0), at(2025, 12, 31, 23, 0), 3600),
+            session(at(2025, 1, 31, 22, 0), at(2025, 1, 31, 23, 0), 1800),
+        ];
+
+        let points = time_series(
+            &sessions,
+            date(2025, 1, 1),
+            date(2025, 12, 31),
+            Bucket::Month,
+        )
+        .unwrap();
+
+        assert_eq!(points.len(), 12);
+        assert_eq!(points[0].label, "2025-01");
+        assert_eq!(points[0].end, date(2025, 1, 31));
+        assert_eq!(points[0].seconds, 1800);
+        assert_eq!(points[1].start, date(2025, 2, 1));
+        assert_eq!(points[11].label, "2025-12");
+        assert_eq!(points[11].end, date(2025, 12, 31));
+        assert_eq!(points[11].seconds, 3600);
+    }
+
+    #[test]
+    fn month_buckets_starting_on_the_31st_stay_on_month_starts() {
+        let points = time_series(&[], date(2025, 1, 31), date(2025, 4, 30), Bucket::Month).unwrap();
+
+        assert_eq!(
+            labels(&points),
+            ["2025-01", "2025-02", "2025-03", "2025-04"]
+        );
+        assert_eq!(points[0].start, date(2025, 1, 31));
+        assert_eq!(points[1].start, date(2025, 2, 1));
+        assert_eq!(points[1].end, date(2025, 2, 28));
+        assert_eq!(points[2].start, date(2025, 3, 1));
+    }
+
+    #[test]
+    fn leap_years_have_a_29th_of_february() {
+        let leap_day = [session(
+            at(2024, 2, 29, 12, 0),
+            at(2024, 2, 29, 12, 30),
+            1800,
+        )];
+
+        let leap =
+            time_series(&leap_day, date(2024, 2, 1), date(2024, 2, 29), Bucket::Day).unwrap();
+        assert_eq!(leap.len(), 29);
+        assert_eq!(leap[28].label, "2024-02-29");
+        assert_eq!(leap[28].seconds, 1800);
+
+        let common = time_series(&[], date(2023, 2, 1), date(2023, 2, 28), Bucket::Day).unwrap();
+        assert_eq!(common.len(), 28);
+
+        let months = time_series(
+            &leap_day,
+            date(2024, 1, 1),
+            date(2024, 12, 31),
+            Bucket::Month,
+        )
+        .unwrap();
+        assert_eq!(months[1].end, date(2024, 2, 29));
+        assert_eq!(months[1].seconds, 1800);
+
+        let years = time_series(
+            &leap_day,
+            date(2023, 1, 1),
+            date(2025, 12, 31),
+            Bucket::Year,
+        )
+        .unwrap();
+        assert_eq!(labels(&years), ["2023", "2024", "2025"]);
+        assert_eq!(years[1].seconds, 1800);
+    }
+
+    #[test]
+    fn week_buckets_start_on_monday_and_cross_years() {
+        // 2020-12-31 is a thursday in iso week 53 of 2020
+        let sessions = [session(at(2021, 1, 2, 10, 0), at(2021, 1, 2, 11, 0), 3600)];
+
+        let points = time_series(
+            &sessions,
+            date(2020, 12, 31),
+            date(2021, 1, 11),
+            Bucket::Week,
+        )
+        .unwrap();
+
+        assert_eq!(labels(&points), ["2020-W53", "2021-W01", "2021-W02"]);
+        assert_eq!(points[0].start, date(2020, 12, 31));
+        assert_eq!(points[0].end, date(2021, 1, 3));
+        assert_eq!(points[0].seconds, 3600);
+        assert_eq!(points[1].start, date(2021, 1, 4));
+        assert_eq!(points[2].start, date(2021, 1, 11));
+        assert_eq!(points[2].end, date(2021, 1, 11));
+    }
+
+    #[test]
+    fn dst_changes_keep_one_bucket_per_calendar_day() {
+        // clocks go forward on 2025-03-30 and back on 2025-10-26 in europe,
+        // and on 2025-03-09 and 2025-11-02 in the us
+        for (day_before, day_after) in [
+            (date(2025, 3, 29), date(2025, 3, 31)),
+            (date(2025, 10, 25), date(2025, 10, 27)),
+            (date(2025, 3, 8), date(2025, 3, 10)),
+            (date(2025, 11, 1), date(2025, 11, 3)),
+        ] {
+            let points = time_series(&[], day_before, day_after, Bucket::Day).unwrap();
+
+            assert_eq!(points.len(), 3);
+            assert!(points.iter().all(|point| point.start == point.end));
+        }
+    }
+
+    #[test]
+    fn sessions_over_a_dst_change_keep_their_recorded_length() {
+        // the wall clock shows two hours, but only one passed as the clocks went forward
+        let sessions = [session(
+            at(2025, 3, 30, 1, 30),
+            at(2025, 3, 30, 3, 30),
+            3600,
+        )];
+
+        let points =
+            time_series(&sessions, date(2025, 3, 30), date(2025, 3, 30), Bucket::Day).unwrap();
+
+        assert_eq!(points[0].seconds, 3600);
+    }
+
+    #[test]
+    fn sessions_past_midnight_are_split_between_days() {
+        let sessions = [session(
+            at(2025, 12, 31, 23, 30),
+            at(2026, 1, 1, 0, 30),
+            3600,
+        )];
+
+        let days =
+            time_series(&sessions, date(2025, 12, 31), date(2026, 1, 1), Bucket::Day).unwrap();
+        assert_eq!(days[0].seconds, 1800);
+        assert_eq!(days[1].seconds, 1800);
+        // pages go to the day the session started
+        assert_eq!(days[0].pages_turned, 10);
+        assert_eq!(days[1].pages_turned, 0);
+
+        // only the part inside the range is counted
+        let new_year = time_series(
+            &sessions,
+            date(2026, 1, 1),
+            date(2026, 1, 31),
+            Bucket::Month,
+        )
+        .unwrap();
+        assert_eq!(new_year[0].seconds, 1800);
+        assert_eq!(new_year[0].pages_turned, 0);
+    }
+
+    #[test]
+    fn split_sessions_add_up_to_their_recorded_seconds() {
+        let span = session(at(2025, 6, 1, 23, 59), at(2025, 6, 3, 0, 1), 1001);
+
+        let shares = span.seconds_per_day();
+
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares.iter().map(|(_, seconds)| seconds).sum::<u32>(), 1001);
+    }
+
+    #[test]
+    fn ranges_must_be_in_order_and_bounded() {
+        assert!(time_series(&[], date(2025, 2, 1), date(2025, 1, 31), Bucket::Day).is_err());
+        assert!(time_series(&[], date(1900, 1, 1), date(2025, 1, 1), Bucket::Day).is_err());
+        assert_eq!(
+            time_series(&[], date(1900, 1, 1), date(2025, 1, 1), Bucket::Year)
+                .unwrap()
+                .len(),
+            126
+        );
+    }
+}
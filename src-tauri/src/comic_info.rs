@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::AppResult;
+use crate::source::ChapterSource;
+
+// the raw ComicInfo.xml document, every field is optional and kept as text
+// because taggers are not consistent about empty or malformed values
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, rename_all = "PascalCase")]
+struct ComicInfoXml {
+    series: Option<String>,
+    volume: Option<String>,
+    number: Option<String>,
+    title: Option<String>,
+    writer: Option<String>,
+    penciller: Option<String>,
+    genre: Option<String>,
+    tags: Option<String>,
+    #[serde(rename = "LanguageISO")]
+    language_iso: Option<String>,
+    manga: Option<String>,
+    page_count: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, sqlx::FromRow)]
+pub struct Series {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, sqlx::FromRow)]
+pub struct ComicInfo {
+    pub manga_folder_id: String,
+    pub series_id: Option<String>,
+    pub series: Option<String>,
+    pub volume: Option<i64>,
+    pub number: Option<String>,
+    pub title: Option<String>,
+    pub writer: Option<String>,
+    pub penciller: Option<String>,
+    pub genre: Option<String>,
+    pub tags: Option<String>,
+    pub language_iso: Option<String>,
+    pub manga: Option<String>,
+    pub page_count: Option<i64>,
+    // `Manga=YesAndRightToLeft` marks pages that are meant to be read right to left
+    #[sqlx(default)]
+    pub right_to_left: bool,
+}
+
+impl ComicInfo {
+    pub fn parse(xml: &str) -> Option<Self> {
+        let raw: ComicInfoXml = match quick_xml::de::from_str(xml) {
+            Ok(raw) => raw,
+            Err(e) => {
+                eprintln!("Error Parsing ComicInfo.xml -> {}", e);
+                return None;
+            }
+        };
+
+        let manga = non_empty(raw.manga);
+        let right_to_left = manga
+            .as_deref()
+            .is_some_and(|manga| manga.eq_ignore_ascii_case("YesAndRightToLeft"));
+
+        Some(ComicInfo {
+            series: non_empty(raw.series),
+            // -1 is the ComicInfo default for "unknown"
+            volume: parse_int(raw.volume).filter(|v| *v >= 0),
+            number: non_empty(raw.number),
+            title: non_empty(raw.title),
+            writer: non_empty(raw.writer),
+            penciller: non_empty(raw.penciller),
+            genre: non_empty(raw.genre),
+            tags: non_empty(raw.tags),
+            language_iso: non_empty(raw.language_iso),
+            manga,
+            page_count: parse_int(raw.page_count).filter(|v| *v > 0),
+            right_to_left,
+            ..Default::default()
+        })
+    }
+
+    pub fn read(folder_path: &str) -> Option<Self> {
+        let xml = ChapterSource::open(folder_path)?.comic_info_xml()?;
+        Self::parse(&xml)
+    }
+
+    /// The title shown in the library, e.g. `Title` or `Series Vol.2 Ch.10`.
+    pub fn display_title(&self) -> Option<String> {
+        if let Some(title) = &self.title {
+            return Some(title.clone());
+        }
+
+        let series = self.series.as_ref()?;
+        let mut display_title = series.clone();
+        if let Some(volume) = self.volume {
+            display_title.push_str(&format!(" Vol.{volume}"));
+        }
+        if let Some(number) = &self.number {
+            display_title.push_str(&format!(" Ch.{number}"));
+        }
+
+        Some(display_title)
+    }
+
+    /// The volume and chapter number chapters are ordered by, `None` without a chapter number.
+    pub fn reading_position(&self) -> Option<(i64, f64)> {
+        Some((self.volume.unwrap_or(0), self.number_value()?))
+    }
+
+    fn number_value(&self) -> Option<f64> {
+        self.number.as_deref()?.trim().parse().ok()
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_int(value: Option<String>) -> Option<i64> {
+    value?.trim().parse().ok()
+}
+
+/// Stores the ComicInfo of a manga folder, creating its series if needed.
+pub async fn upsert_comic_info(
+    manga_folder_id: &str,
+    info: &ComicInfo,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut series_id: Option<String> = None;
+
+    if let Some(series_title) = &info.series {
+        sqlx::query(
+            "INSERT INTO series (id, title, created_at, updated_at)
+            VALUES (?, ?, datetime('now', 'localtime'), datetime('now', 'localtime'))
+            ON CONFLICT (title) DO UPDATE SET
+            updated_at = datetime('now', 'localtime')",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(series_title)
+        .execute(pool)
+        .await?;
+
+        let series: Series = sqlx::query_as("SELECT * FROM series WHERE title = ?")
+            .bind(series_title)
+            .fetch_one(pool)
+            .await?;
+        series_id = Some(series.id);
+    }
+
+    sqlx::query(
+        "INSERT INTO comic_info
+        (
+            manga_folder_id,
+            series_id,
+            volume,
+            number,
+            title,
+            writer,
+            penciller,
+            genre,
+            tags,
+            language_iso,
+            manga,
+            page_count
+        )
+        VALUES
+        (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        )
+        ON CONFLICT (manga_folder_id) DO UPDATE SET
+        series_id = excluded.series_id,
+        volume = excluded.volume,
+        number = excluded.number,
+        title = excluded.title,
+        writer = excluded.writer,
+        penciller = excluded.penciller,
+        genre = excluded.genre,
+        tags = excluded.tags,
+        language_iso = excluded.language_iso,
+        manga = excluded.manga,
+        page_count = excluded.page_count",
+    )
+    .bind(manga_folder_id)
+    .bind(series_id)
+    .bind(info.volume)
+    .bind(&info.number)
+    .bind(&info.title)
+    .bind(&info.writer)
+    .bind(&info.penciller)
+    .bind(&info.genre)
+    .bind(&info.tags)
+    .bind(&info.language_iso)
+    .bind(&info.manga)
+    .bind(info.page_count)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// the series title lives in the `series` table, so join it back in for the frontend
+const SELECT_COMIC_INFO: &str = "SELECT comic_info.*, series.title AS series,
+    comic_info.manga LIKE 'YesAndRightToLeft' AS right_to_left
+    FROM comic_info
+    LEFT JOIN series ON series.id = comic_info.series_id";
+
+pub async fn get_comic_info_by_folder_id(
+    manga_folder_id: &str,
+    pool: &SqlitePool,
+) -> AppResult<Option<ComicInfo>> {
+    let comic_info = sqlx::query_as(&format!("{SELECT_COMIC_INFO} WHERE manga_folder_id = ?"))
+        .bind(manga_folder_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(comic_info)
+}
+
+#[tauri::command]
+pub async fn get_comic_info(
+    folder_path: String,
+    handle: AppHandle,
+) -> AppResult<Option<ComicInfo>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let comic_info = sqlx::query_as(&format!(
+        "{SELECT_COMIC_INFO}
+        INNER JOIN manga_folder ON manga_folder.id = comic_info.manga_folder_id
+        WHERE manga_folder.full_path = ?"
+    ))
+    .bind(folder_path)
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(comic_info)
+}
+
+#[tauri::command]
+pub async fn get_series(handle: AppHandle) -> AppResult<Vec<Series>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let series = sqlx::query_as("SELECT * FROM series ORDER BY title")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(series)
+}
@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{codecs::jpeg::JpegEncoder, ImageEncoder};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult, PathContext};
+use crate::global::{get_manga_folder_by_path, get_parent_folder_by_path};
+use crate::source::read_panel_bytes;
+
+// cropped covers are kept at full resolution, thumbnails are made from them later
+const COVER_QUALITY: u8 = 90;
+
+/// Managed state pointing at the directory holding imported and cropped covers.
+pub struct CoverStore {
+    dir: PathBuf,
+}
+
+impl CoverStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn new_cover_path(&self, extension: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", uuid::Uuid::new_v4(), extension))
+    }
+
+    /// Whether the cover is a file the store created rather than a panel of the library.
+    pub fn holds(&self, cover_path: &str) -> bool {
+        let path = Path::new(cover_path);
+        path.starts_with(&self.dir)
+            && !path
+                .components()
+                .any(|part| part == std::path::Component::ParentDir)
+    }
+
+    /// Only files the store created are removed, panels picked as covers belong to the library.
+    fn remove(&self, cover_path: &str) {
+        if self.holds(cover_path) {
+            if let Err(e) = fs::remove_file(cover_path) {
+                eprintln!("Error Removing Cover: {} -> {}", cover_path, e);
+            }
+        }
+    }
+}
+
+/// The folder whose cover is changed, `for_series` moves a chapter up to its series.
+enum CoverOwner {
+    Series(String),
+    Chapter(String),
+}
+
+impl CoverOwner {
+    async fn find(folder_path: &str, for_series: bool, pool: &SqlitePool) -> AppResult<Self> {
+        if let Some(chapter) = get_manga_folder_by_path(folder_path, pool).await? {
+            if !for_series {
+                return Ok(Self::Chapter(chapter.id));
+            }
+            return chapter.parent_id.map(Self::Series).ok_or_else(|| {
+                AppError::InvalidInput(format!("`{}` is not part of a series", folder_path))
+            });
+        }
+
+        get_parent_folder_by_path(folder_path, pool)
+            .await?
+            .map(|series| Self::Series(series.id))
+            .ok_or_else(|| AppError::not_found(folder_path))
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Series(_) => "parent_folder",
+            Self::Chapter(_) => "manga_folder",
+        }
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            Self::Series(id) | Self::Chapter(id) => id,
+        }
+    }
+
+    /// Stores the new cover and returns the one it replaced.
+    async fn set_cover(
+        &self,
+        cover_path: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<String>> {
+        let mut tx = pool.begin().await?;
+
+        let previous: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT custom_cover_path FROM {} WHERE id = ?",
+            self.table()
+        ))
+        .bind(self.id())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(&format!(
+            "UPDATE {} SET custom_cover_path = ? WHERE id = ?",
+            self.table()
+        ))
+        .bind(cover_path)
+        .bind(self.id())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(previous)
+    }
+}
+
+#[tauri::command]
+pub async fn set_custom_cover_panel(
+    folder_path: String,
+    panel_path: String,
+    for_series: bool,
+    handle: AppHandle,
+) -> AppResult<String> {
+    let exists = panel_path.clone();
+    if !tokio::task::spawn_blocking(move || read_panel_bytes(&exists).is_ok()).await? {
+        return Err(AppError::not_found(&panel_path));
+    }
+
+    replace_cover(&folder_path, for_series, panel_path, &handle).await
+}
+
+/// Copies an image from outside the library into the cover store.
+#[tauri::command]
+pub async fn set_custom_cover_file(
+    folder_path: String,
+    file_path: String,
+    for_series: bool,
+    handle: AppHandle,
+) -> AppResult<String> {
+    let store = handle.state::<CoverStore>();
+
+    // the format comes from the contents, so a mislabeled file still gets the right extension
+    let bytes = tokio::fs::read(&file_path).await.with_path(&file_path)?;
+    let format = image::guess_format(&bytes)?;
+    image::load_from_memory_with_format(&bytes, format)?;
+    let extension = format.extensions_str().first().copied().unwrap_or("img");
+
+    let cover_path = store.new_cover_path(extension);
+    write_cover(&cover_path, &bytes).await?;
+
+    replace_cover(
+        &folder_path,
+        for_series,
+        cover_path.to_string_lossy().to_string(),
+        &handle,
+    )
+    .await
+}
+
+/// Crops a region of a panel, given in the panel's own pixels, and stores it as the cover.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn set_custom_cover_crop(
+    folder_path: String,
+    panel_path: String,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    for_series: bool,
+    handle: AppHandle,
+) -> AppResult<String> {
+    let store = handle.state::<CoverStore>();
+
+    let panel = panel_path.clone();
+    let encoded =
+        tokio::task::spawn_blocking(move || crop_panel(&panel, x, y, width, height)).await??;
+
+    let cover_path = store.new_cover_path("jpg");
+    write_cover(&cover_path, &encoded).await?;
+
+    replace_cover(
+        &folder_path,
+        for_series,
+        cover_path.to_string_lossy().to_string(),
+        &handle,
+    )
+    .await
+}
+
+/// Goes back to the cover picked by the scanner.
+#[tauri::command]
+pub async fn reset_custom_cover(
+    folder_path: String,
+    for_series: bool,
+    handle: AppHandle,
+) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+    let store = handle.state::<CoverStore>();
+
+    let owner = CoverOwner::find(&folder_path, for_series, &pool).await?;
+    if let Some(previous) = owner.set_cover(None, &pool).await? {
+        store.remove(&previous);
+    }
+
+    Ok(())
+}
+
+async fn replace_cover(
+    folder_path: &str,
+    for_series: bool,
+    cover_path: String,
+    handle: &AppHandle,
+) -> AppResult<String> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+    let store = handle.state::<CoverStore>();
+
+    let owner = match CoverOwner::find(folder_path, for_series, &pool).await {
+        Ok(owner) => owner,
+        Err(e) => {
+            store.remove(&cover_path);
+            return Err(e);
+        }
+    };
+
+    if let Some(previous) = owner.set_cover(Some(&cover_path), &pool).await? {
+        if previous != cover_path {
+            store.remove(&previous);
+        }
+    }
+
+    Ok(cover_path)
+}
+
+async fn write_cover(cover_path: &Path, bytes: &[u8]) -> AppResult<()> {
+    let path = cover_path.to_string_lossy();
+    if let Some(dir) = cover_path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_path(&dir.to_string_lossy())?;
+    }
+    tokio::fs::write(cover_path, bytes).await.with_path(&path)
+}
+
+fn crop_panel(panel_path: &str, x: u32, y: u32, width: u32, height: u32) -> AppResult<Vec<u8>> {
+    let bytes = read_panel_bytes(panel_path).with_path(panel_path)?;
+    let panel = image::load_from_memory(&bytes)?;
+
+    let fits =
+        |start: u32, len: u32, max: u32| start.checked_add(len).is_some_and(|end| end <= max);
+    if width == 0
+        || height == 0
+        || !fits(x, width, panel.width())
+        || !fits(y, height, panel.height())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "crop {}x{} at {},{} is outside the {}x{} panel",
+            width,
+            height,
+            x,
+            y,
+            panel.width(),
+            panel.height()
+        )));
+    }
+
+    let cover = panel.crop_imm(x, y, width, height).into_rgb8();
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, COVER_QUALITY).write_image(
+        &cover,
+        cover.width(),
+        cover.height(),
+        image::ExtendedColorType::Rgb8,
+    )?;
+
+    Ok(encoded)
+}
@@ -1,178 +1,379 @@
-use sqlx::{migrate::MigrateDatabase, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, Executor, Sqlite, SqlitePool};
 use tauri::{AppHandle, Manager};
 use tokio::sync::Mutex;
 
-pub fn create_database(path: &str, handle: AppHandle) {
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+// Every schema change is a new entry at the end of this list.
+// Never edit or reorder a migration that has already shipped, existing databases
+// only run the ones newer than their recorded `schema_version`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "comic info",
+        sql: include_str!("../migrations/0002_comic_info.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "missing flags",
+        sql: include_str!("../migrations/0003_missing_flags.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "folder relationships",
+        sql: include_str!("../migrations/0004_folder_relationships.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "reading mode",
+        sql: include_str!("../migrations/0005_reading_mode.sql"),
+    },
+    Migration {
+        version: 6,
+        description: "zoom",
+        sql: include_str!("../migrations/0006_zoom.sql"),
+    },
+    Migration {
+        version: 7,
+        description: "spread offset",
+        sql: include_str!("../migrations/0007_spread_offset.sql"),
+    },
+    Migration {
+        version: 8,
+        description: "custom cover",
+        sql: include_str!("../migrations/0008_custom_cover.sql"),
+    },
+    Migration {
+        version: 9,
+        description: "library roots",
+        sql: include_str!("../migrations/0009_library_roots.sql"),
+    },
+    Migration {
+        version: 10,
+        description: "reading sessions",
+        sql: include_str!("../migrations/0010_reading_sessions.sql"),
+    },
+    Migration {
+        version: 11,
+        description: "reading goals",
+        sql: include_str!("../migrations/0011_reading_goals.sql"),
+    },
+    Migration {
+        version: 12,
+        description: "text blocks",
+        sql: include_str!("../migrations/0012_text_blocks.sql"),
+    },
+    Migration {
+        version: 13,
+        description: "dictionaries",
+        sql: include_str!("../migrations/0013_dictionaries.sql"),
+    },
+    Migration {
+        version: 14,
+        description: "anki settings",
+        sql: include_str!("../migrations/0014_anki_settings.sql"),
+    },
+    Migration {
+        version: 15,
+        description: "vocabulary",
+        sql: include_str!("../migrations/0015_vocabulary.sql"),
+    },
+    Migration {
+        version: 16,
+        description: "bookmarks",
+        sql: include_str!("../migrations/0016_bookmarks.sql"),
+    },
+    Migration {
+        version: 17,
+        description: "dictionary imports",
+        sql: include_str!("../migrations/0017_dictionary_imports.sql"),
+    },
+    Migration {
+        version: 18,
+        description: "frequency mode",
+        sql: include_str!("../migrations/0018_frequency_mode.sql"),
+    },
+];
+
+pub fn create_database(path: &str, handle: AppHandle) -> Result<(), sqlx::Error> {
     tokio::task::block_in_place(move || {
         tauri::async_runtime::block_on(async move {
-            println!("Creating database at {}", path);
             if !Sqlite::database_exists(path).await.unwrap_or(false) {
                 Sqlite::create_database(path).await?;
             }
 
-            let sqlite_pool = SqlitePool::connect_lazy(path).unwrap();
+            let sqlite_pool = SqlitePool::connect_lazy(path)?;
             handle.manage(Mutex::new(sqlite_pool.clone()));
 
-            migrate_parent_folder_table(&sqlite_pool).await.unwrap();
-            migrate_manga_folder_table(&sqlite_pool).await.unwrap();
-            migrate_global_manga_table(&sqlite_pool).await.unwrap();
-            migrate_global_parent_folder_table(&sqlite_pool)
-                .await
-                .unwrap();
-            migrate_manga_panel_table(&sqlite_pool).await.unwrap();
-            migrate_stats_table(&sqlite_pool).await.unwrap();
-            migrate_chart_table(&sqlite_pool).await.unwrap();
+            migrate_database(&sqlite_pool).await?;
+            crate::dictionary::remove_unfinished_imports(&sqlite_pool).await?;
 
             Ok::<(), sqlx::Error>(())
         })
     })
-    .unwrap();
 }
 
-pub async fn migrate_parent_folder_table(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
+/// Brings the database up to the latest schema version, one transaction per migration.
+/// Refuses to touch a database that was migrated by a newer build.
+pub async fn migrate_database(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "CREATE TABLE IF NOT EXISTS parent_folder
+        "CREATE TABLE IF NOT EXISTS schema_version
         (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            full_path TEXT NOT NULL,
-            as_child BOOLEAN DEFAULT 0,
-            is_expanded BOOLEAN DEFAULT 0,
-            cover_panel_path TEXT,
-            created_at TEXT,
-            updated_at TEXT,
-            UNIQUE(full_path)
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
         )",
     )
     .execute(sqlite_pool)
     .await?;
 
-    Ok(())
-}
+    let current_version: i64 =
+        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+            .fetch_one(sqlite_pool)
+            .await?;
+    let latest_version = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
 
-// !!! When updating this table you must update the global table as well !!!
-pub async fn migrate_manga_folder_table(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS manga_folder
-        (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            full_path TEXT NOT NULL,
-            as_child BOOLEAN DEFAULT 0,
-            is_expanded BOOLEAN DEFAULT 0,
-            time_spent_reading INTEGER DEFAULT 0,
-            double_panels BOOLEAN DEFAULT 0,
-            is_read BOOLEAN DEFAULT 0,
-            cover_panel_path TEXT,
-            created_at TEXT,
-            updated_at TEXT,
-            UNIQUE(full_path)
-        )",
-    )
-    .execute(sqlite_pool)
-    .await?;
+    if current_version > latest_version {
+        return Err(sqlx::Error::Configuration(
+            format!(
+                "database is at schema version {current_version} but this build only supports up to {latest_version}, please update Manga Shelf"
+            )
+            .into(),
+        ));
+    }
 
-    Ok(())
-}
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+        eprintln!(
+            "Migrating database to version {} ({})",
+            migration.version, migration.description
+        );
 
-pub async fn migrate_global_manga_table(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS global_manga
-        (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            full_path TEXT NOT NULL,
-            as_child BOOLEAN DEFAULT 0,
-            is_expanded BOOLEAN DEFAULT 0,
-            time_spent_reading INTEGER DEFAULT 0,
-            double_panels BOOLEAN DEFAULT 0,
-            is_read BOOLEAN DEFAULT 0,
-            cover_panel_path TEXT,
-            created_at TEXT,
-            updated_at TEXT,
-            UNIQUE(full_path)
-        )",
-    )
-    .execute(sqlite_pool)
-    .await?;
+        let mut tx = sqlite_pool.begin().await?;
 
-    Ok(())
-}
+        tx.execute(migration.sql).await?;
 
-pub async fn migrate_global_parent_folder_table(
-    sqlite_pool: &SqlitePool,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS global_parent
-        (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            full_path TEXT NOT NULL,
-            as_child BOOLEAN DEFAULT 0,
-            is_expanded BOOLEAN DEFAULT 0,
-            cover_panel_path TEXT,
-            created_at TEXT,
-            updated_at TEXT,
-            UNIQUE(full_path)
-        )",
-    )
-    .execute(sqlite_pool)
-    .await?;
+        sqlx::query(
+            "INSERT INTO schema_version (version, description, applied_at)
+            VALUES (?, ?, datetime('now', 'localtime'))",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    }
 
     Ok(())
 }
 
-pub async fn migrate_manga_panel_table(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS manga_panel
-        (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            full_path TEXT NOT NULL,
-            is_read BOOLEAN DEFAULT 0,
-            width INTEGER,
-            height INTEGER,
-            zoom_level INTEGER,
-            created_at TEXT,
-            updated_at TEXT,
-            UNIQUE(full_path)
-        )",
-    )
-    .execute(sqlite_pool)
-    .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
 
-    Ok(())
-}
+    async fn baseline_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        // the tables as builds before versioned migrations left them, without schema_version
+        pool.execute(MIGRATIONS[0].sql).await.unwrap();
+        pool
+    }
 
-pub async fn migrate_stats_table(sqlite_pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS stats
-        (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            total_manga INTEGER DEFAULT 0,
-            total_panels INTEGER DEFAULT 0,
-            total_panels_read INTEGER DEFAULT 0,
-            total_panels_remaining INTEGER DEFAULT 0,
-            total_time_spent_reading INTEGER DEFAULT 0
-        )",
-    )
-    .execute(sqlite_pool)
-    .await?;
+    #[tokio::test]
+    async fn migrates_rows_from_the_baseline_schema() {
+        let pool = baseline_pool().await;
+        pool.execute(
+            "INSERT INTO parent_folder (id, title, full_path, as_child) VALUES
+                ('series', 'Berserk', '/manga/Berserk', 0),
+                ('volume', 'Vol 1', '/manga/Berserk/Vol 1', 1);
+            INSERT INTO manga_folder (id, title, full_path, as_child, time_spent_reading) VALUES
+                ('chapter', 'Ch 1', '/manga/Berserk/Vol 1/Ch 1', 1, 50);
+            INSERT INTO manga_panel (id, title, full_path, zoom_level) VALUES
+                ('panel', '001.png', '/manga/Berserk/Vol 1/Ch 1/001.png', 150);
+            INSERT INTO chart (watchtime, updated_at) VALUES (120, '2024-01-02'), (0, '2024-01-03');",
+        )
+        .await
+        .unwrap();
 
-    Ok(())
-}
+        migrate_database(&pool).await.unwrap();
 
-pub async fn migrate_chart_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS chart (
-            id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
-            watchtime INTEGER NOT NULL DEFAULT 0,
-            updated_at TEXT NOT NULL UNIQUE
-        )",
-    )
-    .execute(pool)
-    .await
-    .unwrap();
+        let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.last().unwrap().version);
 
-    Ok(())
+        // 0004 links the folders by id, 0009 turns the top level folder into a root
+        let folders: Vec<(String, Option<String>, String, Option<String>)> = sqlx::query_as(
+            "SELECT parent_folder.id, parent_id, kind, library_root.full_path FROM parent_folder
+            LEFT JOIN library_root ON parent_folder.root_id = library_root.id
+            ORDER BY parent_folder.full_path",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            folders,
+            [
+                (
+                    "series".into(),
+                    None,
+                    "series".into(),
+                    Some("/manga/Berserk".into())
+                ),
+                (
+                    "volume".into(),
+                    Some("series".into()),
+                    "volume".into(),
+                    Some("/manga/Berserk".into())
+                ),
+            ]
+        );
+        let chapter_parent: Option<String> =
+            sqlx::query_scalar("SELECT parent_id FROM manga_folder WHERE id = 'chapter'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(chapter_parent.as_deref(), Some("volume"));
+        let panel_folder: Option<String> =
+            sqlx::query_scalar("SELECT folder_id FROM manga_panel WHERE id = 'panel'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(panel_folder.as_deref(), Some("chapter"));
+
+        // 0006 keeps the last used zoom on the series
+        let zoom: (Option<String>, Option<i64>) =
+            sqlx::query_as("SELECT fit_mode, zoom_level FROM parent_folder WHERE id = 'series'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(zoom, (Some("custom".into()), Some(150)));
+
+        // 0010 turns every day of the chart with reading time into a session
+        let sessions: Vec<(String, String, i64)> =
+            sqlx::query_as("SELECT started_at, ended_at, seconds FROM reading_session")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            sessions,
+            [(
+                "2024-01-02 00:00:00".into(),
+                "2024-01-02 00:02:00".into(),
+                120
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn backfills_folder_ids_by_whole_path_segments() {
+        let pool = baseline_pool().await;
+        pool.execute(
+            "INSERT INTO parent_folder (id, title, full_path, as_child) VALUES
+                ('one', 'One', '/manga/One', 0),
+                ('one-piece', 'One Piece', '/manga/One Piece', 0);
+            INSERT INTO manga_folder (id, title, full_path, as_child) VALUES
+                ('one-ch1', 'Ch 1', '/manga/One/Ch 1', 1),
+                ('one-piece-ch1', 'Ch 1', '/manga/One Piece/Ch 1', 1),
+                ('orphan-ch1', 'Ch 1', '/gone/Series/Ch 1', 1);
+            INSERT INTO manga_panel (id, title, full_path) VALUES
+                ('one-panel', '001.png', '/manga/One/Ch 1/001.png'),
+                ('one-piece-panel', '001.png', '/manga/One Piece/Ch 1/001.png'),
+                ('prefix-panel', '001.png', '/manga/One/Ch 10/001.png'),
+                ('orphan-panel', '001.png', '/gone/Other/001.png');",
+        )
+        .await
+        .unwrap();
+
+        migrate_database(&pool).await.unwrap();
+
+        let folders: Vec<(String, Option<String>)> =
+            sqlx::query_as("SELECT id, parent_id FROM manga_folder ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            folders,
+            [
+                ("one-ch1".into(), Some("one".into())),
+                ("one-piece-ch1".into(), Some("one-piece".into())),
+                // rows outside every folder stay unlinked instead of breaking the foreign keys
+                ("orphan-ch1".into(), None),
+            ]
+        );
+
+        let panels: Vec<(String, Option<String>)> =
+            sqlx::query_as("SELECT id, folder_id FROM manga_panel ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            panels,
+            [
+                ("one-panel".into(), Some("one-ch1".into())),
+                ("one-piece-panel".into(), Some("one-piece-ch1".into())),
+                ("orphan-panel".into(), None),
+                // `Ch 10` is not inside `Ch 1`
+                ("prefix-panel".into(), None),
+            ]
+        );
+
+        let violations: Vec<(String, i64, String, i64)> =
+            sqlx::query_as("PRAGMA foreign_key_check")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert!(violations.is_empty(), "{violations:?}");
+
+        // deleting a series takes its chapters and panels with it, and nothing of its sibling
+        sqlx::query("DELETE FROM parent_folder WHERE id = 'one'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let remaining: Vec<String> = sqlx::query_scalar("SELECT id FROM manga_panel ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            remaining,
+            ["one-piece-panel", "orphan-panel", "prefix-panel"]
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_a_newer_schema() {
+        let pool = baseline_pool().await;
+        migrate_database(&pool).await.unwrap();
+        // migrating again is a no-op
+        migrate_database(&pool).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO schema_version (version, description, applied_at)
+            VALUES (?, 'from a newer build', datetime('now'))",
+        )
+        .bind(MIGRATIONS.last().unwrap().version + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let result = migrate_database(&pool).await;
+        assert!(
+            matches!(result, Err(sqlx::Error::Configuration(_))),
+            "a newer schema_version must be refused, got {result:?}"
+        );
+    }
 }
@@ -0,0 +1,428 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+// word types a form can have, as bit flags so a rule can accept several
+pub const V1: u32 = 1 << 0;
+pub const V5: u32 = 1 << 1;
+pub const VS: u32 = 1 << 2;
+pub const VK: u32 = 1 << 3;
+pub const ADJ_I: u32 = 1 << 4;
+// the て of a form that continues, like 食べている or 食べちゃう
+pub const IRU: u32 = 1 << 5;
+
+/// Turns an inflected ending back into the ending of the word it came from, e.g. `かった` into
+/// `い` for the past of an i-adjective. `rules_in` is the word type the inflected form must
+/// have when it's itself the result of an earlier rule, 0 only allows it on the looked up text.
+#[derive(Debug, Clone)]
+struct Rule {
+    reason: &'static str,
+    kana_in: String,
+    kana_out: String,
+    rules_in: u32,
+    rules_out: u32,
+}
+
+/// A possible dictionary form of the looked up text and the inflections that lead to it,
+/// outermost first. `rules` is 0 when nothing was deinflected and any word type matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deinflection {
+    pub term: String,
+    pub rules: u32,
+    pub reasons: Vec<&'static str>,
+}
+
+// the stems and irregular endings of one conjugation class
+struct Conjugation {
+    rules: u32,
+    dictionary: String,
+    // before ない and ず
+    negative: String,
+    // before ます and たい
+    continuative: String,
+    // before ば
+    conditional: String,
+    imperative: Vec<String>,
+    volitional: String,
+    potential: Vec<String>,
+    passive: String,
+    causative: String,
+    te: String,
+    past: String,
+}
+
+// dictionary ending, then the a, i, e and o rows, then the て and た forms of every godan ending
+const GODAN: [[&str; 7]; 9] = [
+    ["う", "わ", "い", "え", "お", "って", "った"],
+    ["く", "か", "き", "け", "こ", "いて", "いた"],
+    ["ぐ", "が", "ぎ", "げ", "ご", "いで", "いだ"],
+    ["す", "さ", "し", "せ", "そ", "して", "した"],
+    ["つ", "た", "ち", "て", "と", "って", "った"],
+    ["ぬ", "な", "に", "ね", "の", "んで", "んだ"],
+    ["ぶ", "ば", "び", "べ", "ぼ", "んで", "んだ"],
+    ["む", "ま", "み", "め", "も", "んで", "んだ"],
+    ["る", "ら", "り", "れ", "ろ", "って", "った"],
+];
+
+static RULES: LazyLock<Vec<Rule>> = LazyLock::new(build_rules);
+
+fn conjugations() -> Vec<Conjugation> {
+    let s = |value: &str| value.to_string();
+
+    let mut conjugations = vec![Conjugation {
+        rules: V1,
+        dictionary: s("る"),
+        negative: s(""),
+        continuative: s(""),
+        conditional: s("れ"),
+        imperative: vec![s("ろ"), s("よ")],
+        volitional: s("よう"),
+        // れる is the colloquial potential without ら
+        potential: vec![s("られる"), s("れる")],
+        passive: s("られる"),
+        causative: s("させる"),
+        te: s("て"),
+        past: s("た"),
+    }];
+
+    for [dictionary, a, i, e, o, te, past] in GODAN {
+        conjugations.push(Conjugation {
+            rules: V5,
+            dictionary: s(dictionary),
+            negative: s(a),
+            continuative: s(i),
+            conditional: s(e),
+            imperative: vec![s(e)],
+            volitional: format!("{o}う"),
+            potential: vec![format!("{e}る")],
+            passive: format!("{a}れる"),
+            causative: format!("{a}せる"),
+            te: s(te),
+            past: s(past),
+        });
+    }
+
+    conjugations.push(Conjugation {
+        rules: VS,
+        dictionary: s("する"),
+        negative: s("し"),
+        continuative: s("し"),
+        conditional: s("すれ"),
+        imperative: vec![s("しろ"), s("せよ")],
+        volitional: s("しよう"),
+        // できる is a verb of its own
+        potential: Vec::new(),
+        passive: s("される"),
+        causative: s("させる"),
+        te: s("して"),
+        past: s("した"),
+    });
+
+    // 来る keeps its kanji through every form while the reading changes
+    for (dictionary, [ko, ki, ku]) in [("くる", ["こ", "き", "く"]), ("来る", ["来", "来", "来"])]
+    {
+        conjugations.push(Conjugation {
+            rules: VK,
+            dictionary: s(dictionary),
+            negative: s(ko),
+            continuative: s(ki),
+            conditional: format!("{ku}れ"),
+            imperative: vec![format!("{ko}い")],
+            volitional: format!("{ko}よう"),
+            potential: vec![format!("{ko}られる"), format!("{ko}れる")],
+            passive: format!("{ko}られる"),
+            causative: format!("{ko}させる"),
+            te: format!("{ki}て"),
+            past: format!("{ki}た"),
+        });
+    }
+
+    conjugations
+}
+
+fn build_rules() -> Vec<Rule> {
+    let mut rules = Vec::new();
+    let mut add = |reason, kana_in: String, kana_out: &str, rules_in, rules_out| {
+        // an empty ending would match everything, like the bare stem of 食べる
+        if !kana_in.is_empty() {
+            rules.push(Rule {
+                reason,
+                kana_in,
+                kana_out: kana_out.to_string(),
+                rules_in,
+                rules_out,
+            });
+        }
+    };
+
+    for c in conjugations() {
+        let out = c.dictionary.as_str();
+        let stem = |suffix: &str| format!("{}{suffix}", c.continuative);
+
+        add("polite", stem("ます"), out, 0, c.rules);
+        add("polite past", stem("ました"), out, 0, c.rules);
+        add("polite negative", stem("ません"), out, 0, c.rules);
+        add(
+            "polite past negative",
+            stem("ませんでした"),
+            out,
+            0,
+            c.rules,
+        );
+        add("polite volitional", stem("ましょう"), out, 0, c.rules);
+        add("polite te", stem("まして"), out, 0, c.rules);
+        add("masu stem", stem(""), out, 0, c.rules);
+        add("-tai", stem("たい"), out, ADJ_I, c.rules);
+        add("-sou", stem("そう"), out, 0, c.rules);
+        add("-sugiru", stem("すぎる"), out, V1, c.rules);
+        add("-nagara", stem("ながら"), out, 0, c.rules);
+
+        add(
+            "negative",
+            format!("{}ない", c.negative),
+            out,
+            ADJ_I,
+            c.rules,
+        );
+        // する keeps the older せ stem before ず and ぬ
+        let classical = if c.rules == VS { "せ" } else { &c.negative };
+        add("-zu", format!("{classical}ず"), out, 0, c.rules);
+        add("-nu", format!("{classical}ぬ"), out, 0, c.rules);
+
+        add("past", c.past.clone(), out, 0, c.rules);
+        add("-tara", format!("{}ら", c.past), out, 0, c.rules);
+        add("-tari", format!("{}り", c.past), out, 0, c.rules);
+        add("-te", c.te.clone(), out, IRU, c.rules);
+        add("-ba", format!("{}ば", c.conditional), out, 0, c.rules);
+        add("volitional", c.volitional.clone(), out, 0, c.rules);
+        for imperative in &c.imperative {
+            add("imperative", imperative.clone(), out, 0, c.rules);
+        }
+        for potential in &c.potential {
+            add("potential", potential.clone(), out, V1, c.rules);
+        }
+        add("passive", c.passive.clone(), out, V1, c.rules);
+        add("causative", c.causative.clone(), out, V1, c.rules);
+    }
+
+    // 行く is the one godan verb with irregular て and た forms
+    for (stem, dictionary) in [("行っ", "行く"), ("いっ", "いく")] {
+        add("-te", format!("{stem}て"), dictionary, IRU, V5);
+        add("past", format!("{stem}た"), dictionary, 0, V5);
+        add("-tara", format!("{stem}たら"), dictionary, 0, V5);
+        add("-tari", format!("{stem}たり"), dictionary, 0, V5);
+    }
+
+    // verbs that follow the て form, which is then deinflected on its own
+    for te in ["て", "で"] {
+        add("progressive or perfect", format!("{te}いる"), te, V1, IRU);
+        add("progressive or perfect", format!("{te}る"), te, V1, IRU);
+        add("-te shimau", format!("{te}しまう"), te, V5, IRU);
+        add("-te oku", format!("{te}おく"), te, V5, IRU);
+    }
+    add("-te shimau", "ちゃう".to_string(), "て", V5, IRU);
+    add("-te shimau", "じゃう".to_string(), "で", V5, IRU);
+    add("-te oku", "とく".to_string(), "て", V5, IRU);
+    add("-te oku", "どく".to_string(), "で", V5, IRU);
+
+    add("past", "かった".to_string(), "い", 0, ADJ_I);
+    add("negative", "くない".to_string(), "い", ADJ_I, ADJ_I);
+    add("adv", "く".to_string(), "い", 0, ADJ_I);
+    add("-te", "くて".to_string(), "い", 0, ADJ_I);
+    add("-ba", "ければ".to_string(), "い", 0, ADJ_I);
+    add("-tara", "かったら".to_string(), "い", 0, ADJ_I);
+    add("-tari", "かったり".to_string(), "い", 0, ADJ_I);
+    add("noun", "さ".to_string(), "い", 0, ADJ_I);
+    add("-sou", "そう".to_string(), "い", 0, ADJ_I);
+    add("-sugiru", "すぎる".to_string(), "い", V1, ADJ_I);
+
+    rules
+}
+
+/// Every form `source` could have been inflected from, including `source` itself.
+pub fn deinflect(source: &str) -> Vec<Deinflection> {
+    let mut results = vec![Deinflection {
+        term: source.to_string(),
+        rules: 0,
+        reasons: Vec::new(),
+    }];
+    let mut seen = HashSet::from([(source.to_string(), 0)]);
+
+    // results grow while they're walked, each one is deinflected further
+    let mut index = 0;
+    while index < results.len() {
+        let Deinflection { term, rules, .. } = results[index].clone();
+
+        for rule in RULES.iter() {
+            if (rules != 0 && rules & rule.rules_in == 0) || !term.ends_with(&rule.kana_in) {
+                continue;
+            }
+            let stem = &term[..term.len() - rule.kana_in.len()];
+            if stem.is_empty() && rule.kana_out.is_empty() {
+                continue;
+            }
+
+            let deinflected = format!("{stem}{}", rule.kana_out);
+            if !seen.insert((deinflected.clone(), rule.rules_out)) {
+                continue;
+            }
+            let mut reasons = vec![rule.reason];
+            reasons.extend(&results[index].reasons);
+            results.push(Deinflection {
+                term: deinflected,
+                rules: rule.rules_out,
+                reasons,
+            });
+        }
+
+        index += 1;
+    }
+
+    results
+}
+
+/// The word types a dictionary lists in a term's rules, like `v5 vt`.
+pub fn parse_rules(rules: &str) -> u32 {
+    rules
+        .split_whitespace()
+        .map(|rule| match rule {
+            "adj-i" => ADJ_I,
+            "vk" => VK,
+            rule if rule.starts_with("v1") => V1,
+            rule if rule.starts_with("v5") => V5,
+            rule if rule.starts_with("vs") => VS,
+            _ => 0,
+        })
+        .fold(0, |rules, rule| rules | rule)
+}
+
+/// Whether a term with the dictionary `term_rules` can be the result of `deinflection`.
+pub fn matches_rules(deinflection: &Deinflection, term_rules: u32) -> bool {
+    deinflection.rules == 0 || deinflection.rules & term_rules != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // inflected text, the dictionary form it comes from and the rules a dictionary lists for it
+    const CORPUS: &[(&str, &str, &str)] = &[
+        ("食べる", "食べる", "v1 vt"),
+        ("食べた", "食べる", "v1 vt"),
+        ("食べない", "食べる", "v1 vt"),
+        ("食べなかった", "食べる", "v1 vt"),
+        ("食べます", "食べる", "v1 vt"),
+        ("食べませんでした", "食べる", "v1 vt"),
+        ("食べたい", "食べる", "v1 vt"),
+        ("食べたかった", "食べる", "v1 vt"),
+        ("食べられる", "食べる", "v1 vt"),
+        ("食べさせられた", "食べる", "v1 vt"),
+        ("食べちゃった", "食べる", "v1 vt"),
+        ("食べている", "食べる", "v1 vt"),
+        ("食べれば", "食べる", "v1 vt"),
+        ("食べよう", "食べる", "v1 vt"),
+        ("食べろ", "食べる", "v1 vt"),
+        ("食べず", "食べる", "v1 vt"),
+        ("書いた", "書く", "v5k vt"),
+        ("書かない", "書く", "v5k vt"),
+        ("書きます", "書く", "v5k vt"),
+        ("書ける", "書く", "v5k vt"),
+        ("書かれていた", "書く", "v5k vt"),
+        ("書いておく", "書く", "v5k vt"),
+        ("書いとく", "書く", "v5k vt"),
+        ("泳いだ", "泳ぐ", "v5g vi"),
+        ("話して", "話す", "v5s vt"),
+        ("待った", "待つ", "v5t vi"),
+        ("死んだ", "死ぬ", "v5n vi"),
+        ("遊んでいる", "遊ぶ", "v5b vi"),
+        ("読んでた", "読む", "v5m vt"),
+        ("読んじゃう", "読む", "v5m vt"),
+        ("買った", "買う", "v5u vt"),
+        ("帰ろう", "帰る", "v5r vi"),
+        ("行った", "行く", "v5k-s vi"),
+        ("行って", "行く", "v5k-s vi"),
+        ("いったら", "いく", "v5k-s vi"),
+        ("した", "する", "vs-i"),
+        ("しない", "する", "vs-i"),
+        ("させる", "する", "vs-i"),
+        ("勉強しています", "勉強する", "vs-i"),
+        ("勉強しなかった", "勉強する", "vs-i"),
+        ("きた", "くる", "vk"),
+        ("こなかった", "くる", "vk"),
+        ("来られる", "来る", "vk"),
+        ("来ます", "来る", "vk"),
+        ("高かった", "高い", "adj-i"),
+        ("高くない", "高い", "adj-i"),
+        ("高くなかった", "高い", "adj-i"),
+        ("高くて", "高い", "adj-i"),
+        ("高ければ", "高い", "adj-i"),
+        ("高さ", "高い", "adj-i"),
+        ("高すぎる", "高い", "adj-i"),
+    ];
+
+    // forms that look inflected but can't come from a word of that type
+    const MISMATCHES: &[(&str, &str, &str)] = &[
+        // a godan verb has no ichidan past
+        ("書くた", "書くる", "v5k vt"),
+        // an i-adjective past doesn't lead to a verb
+        ("高かった", "高く", "v5k vt"),
+        // いだ is only the past of a godan verb ending in ぐ
+        ("泳いだ", "泳ぐ", "v1 vi"),
+    ];
+
+    fn deinflects_to(source: &str, term: &str, rules: &str) -> bool {
+        deinflect(source).iter().any(|deinflection| {
+            deinflection.term == term && matches_rules(deinflection, parse_rules(rules))
+        })
+    }
+
+    #[test]
+    fn deinflects_corpus() {
+        for (source, term, rules) in CORPUS {
+            assert!(
+                deinflects_to(source, term, rules),
+                "{source} should deinflect to {term} ({rules}), got {:?}",
+                deinflect(source)
+                    .iter()
+                    .map(|d| (&d.term, d.rules))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_types() {
+        for (source, term, rules) in MISMATCHES {
+            assert!(
+                !deinflects_to(source, term, rules),
+                "{source} shouldn't deinflect to {term} ({rules})"
+            );
+        }
+    }
+
+    #[test]
+    fn reasons_are_outermost_first() {
+        let deinflection = deinflect("食べなかった")
+            .into_iter()
+            .find(|deinflection| deinflection.term == "食べる")
+            .unwrap();
+        assert_eq!(deinflection.reasons, ["negative", "past"]);
+        assert_eq!(deinflection.rules, V1);
+    }
+
+    #[test]
+    fn parses_dictionary_rules() {
+        for (rules, parsed) in [
+            ("v1", V1),
+            ("v1 vt", V1),
+            ("v5k vt", V5),
+            ("v5k-s vi", V5),
+            ("vs-i", VS),
+            ("vk", VK),
+            ("adj-i", ADJ_I),
+            ("v1 v5r", V1 | V5),
+            ("n", 0),
+            ("", 0),
+        ] {
+            assert_eq!(parse_rules(rules), parsed, "{rules}");
+        }
+    }
+}
@@ -0,0 +1,1136 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::deinflect::{deinflect, matches_rules, parse_rules, Deinflection};
+use crate::error::{AppError, AppResult, PathContext};
+use crate::source::{list_archive_entries, read_archive_entry, ArchiveKind};
+
+pub const DICTIONARY_PROGRESS_EVENT: &str = "dictionary://progress";
+
+// how far past the cursor a lookup reads, the same as yomitan's default scan length
+const MAX_LOOKUP_LENGTH: usize = 16;
+const MAX_LOOKUP_TERMS: usize = 32;
+// stays under the 999 bound parameters older sqlite builds allow
+const LOOKUP_BATCH_SIZE: usize = 400;
+// rows per insert of a bank, a term row binds 9 parameters
+const INSERT_BATCH_SIZE: usize = 100;
+
+// the parts of index.json an import needs, `version` is what format 1 dictionaries call it
+#[derive(Debug, Deserialize)]
+struct DictionaryIndex {
+    title: String,
+    #[serde(default)]
+    revision: Option<String>,
+    #[serde(default)]
+    format: Option<u8>,
+    #[serde(default)]
+    version: Option<u8>,
+    // dictionaries from before yomitan added the field are rank based
+    #[serde(default, rename = "frequencyMode")]
+    frequency_mode: FrequencyMode,
+}
+
+/// Whether a frequency dictionary's values are ranks, lower is more common, or occurrence
+/// counts, higher is more common.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "kebab-case")]
+#[sqlx(type_name = "TEXT", rename_all = "kebab-case")]
+pub enum FrequencyMode {
+    #[default]
+    RankBased,
+    OccurrenceBased,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct Dictionary {
+    pub id: String,
+    pub title: String,
+    pub revision: Option<String>,
+    pub format: u8,
+    pub priority: u32,
+    pub frequency_mode: FrequencyMode,
+    pub term_count: u32,
+    pub kanji_count: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DictionaryProgress {
+    pub title: String,
+    pub banks_imported: usize,
+    pub total_banks: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct Frequency {
+    pub dictionary: String,
+    pub value: i64,
+    pub display_value: Option<String>,
+}
+
+/// A dictionary entry found by a lookup. `source` is the looked up text it matched and
+/// `reasons` the inflections, outermost first, that turn `expression` into it.
+#[derive(Debug, Serialize, Clone)]
+pub struct TermEntry {
+    pub expression: String,
+    pub reading: String,
+    pub source: String,
+    pub reasons: Vec<String>,
+    pub definition_tags: Vec<String>,
+    pub term_tags: Vec<String>,
+    pub glossary: Vec<String>,
+    pub score: i64,
+    pub dictionary: String,
+    pub frequencies: Vec<Frequency>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KanjiEntry {
+    pub character: String,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub tags: Vec<String>,
+    pub meanings: Vec<String>,
+    pub dictionary: String,
+    pub frequencies: Vec<Frequency>,
+}
+
+/// Terms starting at the cursor, longest match first, and the kanji under the cursor.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct LookupResult {
+    pub terms: Vec<TermEntry>,
+    pub kanji: Vec<KanjiEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BankKind {
+    Term,
+    TermMeta,
+    Kanji,
+    KanjiMeta,
+}
+
+// one bank file, parsed on the blocking pool before it's inserted
+enum Bank {
+    Terms(Vec<ParsedTerm>),
+    Kanji(Vec<ParsedKanji>),
+    Frequencies(&'static str, Vec<ParsedFrequency>),
+}
+
+struct ParsedTerm {
+    expression: String,
+    reading: String,
+    definition_tags: String,
+    rules: String,
+    score: i64,
+    glossary: Vec<String>,
+    sequence: Option<i64>,
+    term_tags: String,
+}
+
+struct ParsedKanji {
+    character: String,
+    onyomi: String,
+    kunyomi: String,
+    tags: String,
+    meanings: Vec<String>,
+}
+
+struct ParsedFrequency {
+    expression: String,
+    reading: Option<String>,
+    value: i64,
+    display_value: Option<String>,
+}
+
+/// A word found by `WordFinder::longest_word`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordMatch {
+    pub length: usize,
+    pub expression: String,
+    pub reading: String,
+}
+
+// a term that matched the start of the looked up text through one of its deinflections
+struct TermMatch {
+    source: String,
+    deinflection: Deinflection,
+    row: TermRow,
+}
+
+#[derive(Clone, sqlx::FromRow)]
+struct TermRow {
+    id: i64,
+    expression: String,
+    reading: String,
+    definition_tags: String,
+    rules: String,
+    score: i64,
+    glossary: String,
+    term_tags: String,
+    dictionary: String,
+    priority: u32,
+}
+
+#[derive(sqlx::FromRow)]
+struct FrequencyRow {
+    kind: String,
+    expression: String,
+    reading: Option<String>,
+    dictionary: String,
+    value: i64,
+    display_value: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct KanjiRow {
+    character: String,
+    onyomi: String,
+    kunyomi: String,
+    tags: String,
+    meanings: String,
+    dictionary: String,
+}
+
+impl BankKind {
+    fn from_name(name: &str) -> Option<Self> {
+        let file_name = Path::new(name).file_name()?.to_str()?;
+        if !file_name.ends_with(".json") {
+            return None;
+        }
+        [
+            ("term_meta_bank_", Self::TermMeta),
+            ("kanji_meta_bank_", Self::KanjiMeta),
+            ("term_bank_", Self::Term),
+            ("kanji_bank_", Self::Kanji),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| file_name.starts_with(prefix))
+        .map(|(_, kind)| kind)
+    }
+}
+
+impl Bank {
+    fn parse(kind: BankKind, format: u8, bytes: &[u8]) -> AppResult<Self> {
+        let rows: Vec<Vec<Value>> = serde_json::from_slice(bytes)?;
+
+        Ok(match kind {
+            BankKind::Term => Self::Terms(
+                rows.iter()
+                    .filter_map(|row| ParsedTerm::parse(row, format))
+                    .collect(),
+            ),
+            BankKind::Kanji => Self::Kanji(
+                rows.iter()
+                    .filter_map(|row| ParsedKanji::parse(row, format))
+                    .collect(),
+            ),
+            BankKind::TermMeta => Self::Frequencies(
+                "term",
+                rows.iter()
+                    .filter_map(|row| ParsedFrequency::parse(row))
+                    .collect(),
+            ),
+            BankKind::KanjiMeta => Self::Frequencies(
+                "kanji",
+                rows.iter()
+                    .filter_map(|row| ParsedFrequency::parse(row))
+                    .collect(),
+            ),
+        })
+    }
+}
+
+fn text(value: Option<&Value>) -> String {
+    value
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl ParsedTerm {
+    // format 1: [expression, reading, definition tags, rules, score, ...glossary]
+    // format 3: [expression, reading, definition tags, rules, score, [glossary], sequence, term tags]
+    fn parse(row: &[Value], format: u8) -> Option<Self> {
+        let expression = text(row.first());
+        if expression.is_empty() {
+            return None;
+        }
+        // an empty reading means the expression is already written in kana
+        let reading = Some(text(row.get(1)))
+            .filter(|reading| !reading.is_empty())
+            .unwrap_or_else(|| expression.clone());
+
+        let (glossary, sequence, term_tags) = match format {
+            1 => (row.get(5..).unwrap_or_default(), None, String::new()),
+            _ => (
+                row.get(5)
+                    .and_then(Value::as_array)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+                row.get(6).and_then(Value::as_i64),
+                text(row.get(7)),
+            ),
+        };
+
+        Some(Self {
+            expression,
+            reading,
+            definition_tags: text(row.get(2)),
+            rules: text(row.get(3)),
+            score: row.get(4).and_then(Value::as_f64).unwrap_or(0.0) as i64,
+            glossary: glossary.iter().filter_map(glossary_text).collect(),
+            sequence,
+            term_tags,
+        })
+    }
+}
+
+impl ParsedKanji {
+    // format 1: [character, onyomi, kunyomi, tags, ...meanings]
+    // format 3: [character, onyomi, kunyomi, tags, [meanings], stats]
+    fn parse(row: &[Value], format: u8) -> Option<Self> {
+        let character = text(row.first());
+        if character.is_empty() {
+            return None;
+        }
+
+        let meanings = match format {
+            1 => row.get(4..).unwrap_or_default(),
+            _ => row
+                .get(4)
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        };
+
+        Some(Self {
+            character,
+            onyomi: text(row.get(1)),
+            kunyomi: text(row.get(2)),
+            tags: text(row.get(3)),
+            meanings: meanings
+                .iter()
+                .filter_map(|meaning| meaning.as_str().map(str::to_string))
+                .collect(),
+        })
+    }
+}
+
+impl ParsedFrequency {
+    // [expression, mode, data], only the "freq" mode is kept, pitch accents are skipped.
+    // data is a rank, or `{ reading, frequency }` when the rank is for one reading
+    fn parse(row: &[Value]) -> Option<Self> {
+        let expression = text(row.first());
+        if expression.is_empty() || row.get(1)?.as_str()? != "freq" {
+            return None;
+        }
+
+        let data = row.get(2)?;
+        let (reading, frequency) = match data.get("frequency") {
+            Some(frequency) => (data.get("reading").and_then(Value::as_str), frequency),
+            None => (None, data),
+        };
+        let (value, display_value) = frequency_value(frequency)?;
+
+        Some(Self {
+            expression,
+            reading: reading.map(str::to_string),
+            value,
+            display_value,
+        })
+    }
+}
+
+// a rank is a number, a string like "1234" or "1234㋕", or `{ value, displayValue }`
+fn frequency_value(frequency: &Value) -> Option<(i64, Option<String>)> {
+    match frequency {
+        Value::Number(number) => Some((number.as_f64()? as i64, None)),
+        Value::String(display) => {
+            let digits: String = display
+                .trim()
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            Some((digits.parse().ok()?, Some(display.clone())))
+        }
+        Value::Object(object) => {
+            let value = object.get("value")?.as_f64()? as i64;
+            let display = object
+                .get("displayValue")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            Some((value, display))
+        }
+        _ => None,
+    }
+}
+
+/// The plain text of one definition. Images and the deinflection arrays of format 3
+/// dictionaries have no text and are dropped.
+fn glossary_text(definition: &Value) -> Option<String> {
+    let text = match definition {
+        Value::String(text) => text.clone(),
+        Value::Object(object) => match object.get("type").and_then(Value::as_str) {
+            Some("text") => object.get("text")?.as_str()?.to_string(),
+            Some("structured-content") => {
+                let mut text = String::new();
+                structured_text(object.get("content")?, &mut text);
+                text
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    Some(lines.join("\n")).filter(|text| !text.is_empty())
+}
+
+// structured content is html-like, block elements become lines of their own
+fn structured_text(content: &Value, text: &mut String) {
+    match content {
+        Value::String(content) => text.push_str(content),
+        Value::Array(items) => {
+            for item in items {
+                structured_text(item, text);
+            }
+        }
+        Value::Object(node) => {
+            let tag = node.get("tag").and_then(Value::as_str).unwrap_or_default();
+            if tag == "br" {
+                text.push('\n');
+                return;
+            }
+            if tag == "img" {
+                return;
+            }
+
+            let is_block = matches!(
+                tag,
+                "div" | "p" | "ol" | "ul" | "li" | "table" | "tr" | "details" | "summary"
+            );
+            if is_block {
+                text.push('\n');
+            }
+            if let Some(content) = node.get("content") {
+                structured_text(content, text);
+            }
+            if is_block {
+                text.push('\n');
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reads index.json and lists the banks of a dictionary zip.
+fn read_index(archive_path: &Path) -> AppResult<(DictionaryIndex, Vec<(String, BankKind)>)> {
+    let path = archive_path.to_string_lossy();
+    let names = list_archive_entries(ArchiveKind::Zip, archive_path).with_path(&path)?;
+
+    let index_name = names
+        .iter()
+        .find(|name| {
+            Path::new(name)
+                .file_name()
+                .is_some_and(|name| name == "index.json")
+        })
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "{path} is not a yomitan dictionary, it has no index.json"
+            ))
+        })?;
+    let index_bytes =
+        read_archive_entry(ArchiveKind::Zip, archive_path, index_name).with_path(&path)?;
+    let index: DictionaryIndex = serde_json::from_slice(&index_bytes)?;
+
+    let banks = names
+        .into_iter()
+        .filter_map(|name| {
+            let kind = BankKind::from_name(&name)?;
+            Some((name, kind))
+        })
+        .collect();
+
+    Ok((index, banks))
+}
+
+/// Imports a yomitan dictionary zip, replacing an earlier import with the same title.
+/// Every bank is committed on its own so the import doesn't hold the database's write lock
+/// for minutes, and `DICTIONARY_PROGRESS_EVENT` is emitted after each one.
+#[tauri::command]
+pub async fn import_dictionary(path: String, handle: AppHandle) -> AppResult<Dictionary> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let archive_path = PathBuf::from(&path);
+    let (index, banks) = tokio::task::spawn_blocking({
+        let archive_path = archive_path.clone();
+        move || read_index(&archive_path)
+    })
+    .await??;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    start_import(&id, &index, &pool).await?;
+
+    match import_banks(&id, &index, &archive_path, banks, &handle, &pool).await {
+        Ok(dictionary) => Ok(dictionary),
+        Err(e) => {
+            // the banks committed so far go with the dictionary
+            if let Err(e) = sqlx::query("DELETE FROM dictionary WHERE id = ?")
+                .bind(&id)
+                .execute(&pool)
+                .await
+            {
+                eprintln!(
+                    "Error Removing Unfinished Dictionary: {} -> {}",
+                    index.title, e
+                );
+            }
+            Err(e)
+        }
+    }
+}
+
+fn index_format(index: &DictionaryIndex) -> u8 {
+    index.format.or(index.version).unwrap_or(3)
+}
+
+// replaces an earlier import with a dictionary row that stays hidden until its banks are in
+async fn start_import(id: &str, index: &DictionaryIndex, pool: &SqlitePool) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    // importing a dictionary again keeps its place in the lookup order
+    let priority: u32 = sqlx::query_scalar(
+        "SELECT COALESCE(
+            (SELECT priority FROM dictionary WHERE title = ?),
+            (SELECT COALESCE(MAX(priority), 0) + 1 FROM dictionary)
+        )",
+    )
+    .bind(&index.title)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM dictionary WHERE title = ?")
+        .bind(&index.title)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO dictionary
+        (id, title, revision, format, priority, frequency_mode, is_importing, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, 1, datetime('now', 'localtime'), datetime('now', 'localtime'))",
+    )
+    .bind(id)
+    .bind(&index.title)
+    .bind(&index.revision)
+    .bind(index_format(index))
+    .bind(priority)
+    .bind(index.frequency_mode)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn import_banks(
+    id: &str,
+    index: &DictionaryIndex,
+    archive_path: &Path,
+    banks: Vec<(String, BankKind)>,
+    handle: &AppHandle,
+    pool: &SqlitePool,
+) -> AppResult<Dictionary> {
+    let format = index_format(index);
+    let total_banks = banks.len();
+    for (banks_imported, (name, kind)) in banks.into_iter().enumerate() {
+        let archive_path = archive_path.to_path_buf();
+        let bank = tokio::task::spawn_blocking(move || {
+            let bytes = read_archive_entry(ArchiveKind::Zip, &archive_path, &name)
+                .with_path(&archive_path.to_string_lossy())?;
+            Bank::parse(kind, format, &bytes)
+        })
+        .await??;
+
+        let mut tx = pool.begin().await?;
+        insert_bank(id, &bank, &mut tx).await?;
+        tx.commit().await?;
+
+        let progress = DictionaryProgress {
+            title: index.title.clone(),
+            banks_imported: banks_imported + 1,
+            total_banks,
+        };
+        if let Err(e) = handle.emit(DICTIONARY_PROGRESS_EVENT, progress) {
+            eprintln!("Error Emitting {} -> {}", DICTIONARY_PROGRESS_EVENT, e);
+        }
+    }
+
+    finish_import(id, pool).await
+}
+
+async fn finish_import(id: &str, pool: &SqlitePool) -> AppResult<Dictionary> {
+    sqlx::query(
+        "UPDATE dictionary SET
+        term_count = (SELECT COUNT(*) FROM dictionary_term WHERE dictionary_id = ?),
+        kanji_count = (SELECT COUNT(*) FROM dictionary_kanji WHERE dictionary_id = ?),
+        is_importing = 0,
+        updated_at = datetime('now', 'localtime')
+        WHERE id = ?",
+    )
+    .bind(id)
+    .bind(id)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    let dictionary = sqlx::query_as("SELECT * FROM dictionary WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(dictionary)
+}
+
+/// Removes what an import that was interrupted, by the app closing for example, left behind.
+pub async fn remove_unfinished_imports(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM dictionary WHERE is_importing = 1")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// `INSERT INTO <into> VALUES` with a row of placeholders for each of `rows`
+fn insert_values_sql(into: &str, columns: usize, rows: usize) -> String {
+    let row = format!("({})", vec!["?"; columns].join(", "));
+    format!("INSERT INTO {into} VALUES {}", vec![row; rows].join(", "))
+}
+
+async fn insert_bank(
+    dictionary_id: &str,
+    bank: &Bank,
+    conn: &mut SqliteConnection,
+) -> AppResult<()> {
+    match bank {
+        Bank::Terms(terms) => {
+            for chunk in terms.chunks(INSERT_BATCH_SIZE) {
+                let sql = insert_values_sql(
+                    "dictionary_term (
+                    dictionary_id,
+                    expression,
+                    reading,
+                    definition_tags,
+                    rules,
+                    score,
+                    glossary,
+                    sequence,
+                    term_tags
+                    )",
+                    9,
+                    chunk.len(),
+                );
+                let mut query = sqlx::query(&sql);
+                for term in chunk {
+                    query = query
+                        .bind(dictionary_id)
+                        .bind(&term.expression)
+                        .bind(&term.reading)
+                        .bind(&term.definition_tags)
+                        .bind(&term.rules)
+                        .bind(term.score)
+                        .bind(serde_json::to_string(&term.glossary)?)
+                        .bind(term.sequence)
+                        .bind(&term.term_tags);
+                }
+                query.execute(&mut *conn).await?;
+            }
+        }
+        Bank::Kanji(kanji) => {
+            for chunk in kanji.chunks(INSERT_BATCH_SIZE) {
+                let sql = insert_values_sql(
+                    "dictionary_kanji (dictionary_id, character, onyomi, kunyomi, tags, meanings)",
+                    6,
+                    chunk.len(),
+                );
+                let mut query = sqlx::query(&sql);
+                for kanji in chunk {
+                    query = query
+                        .bind(dictionary_id)
+                        .bind(&kanji.character)
+                        .bind(&kanji.onyomi)
+                        .bind(&kanji.kunyomi)
+                        .bind(&kanji.tags)
+                        .bind(serde_json::to_string(&kanji.meanings)?);
+                }
+                query.execute(&mut *conn).await?;
+            }
+        }
+        Bank::Frequencies(kind, frequencies) => {
+            for chunk in frequencies.chunks(INSERT_BATCH_SIZE) {
+                let sql = insert_values_sql(
+                    "dictionary_frequency
+                    (dictionary_id, kind, expression, reading, value, display_value)",
+                    6,
+                    chunk.len(),
+                );
+                let mut query = sqlx::query(&sql);
+                for frequency in chunk {
+                    query = query
+                        .bind(dictionary_id)
+                        .bind(kind)
+                        .bind(&frequency.expression)
+                        .bind(&frequency.reading)
+                        .bind(frequency.value)
+                        .bind(&frequency.display_value);
+                }
+                query.execute(&mut *conn).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_dictionaries(handle: AppHandle) -> AppResult<Vec<Dictionary>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let dictionaries =
+        sqlx::query_as("SELECT * FROM dictionary WHERE is_importing = 0 ORDER BY priority")
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(dictionaries)
+}
+
+#[tauri::command]
+pub async fn remove_dictionary(id: String, handle: AppHandle) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("DELETE FROM dictionary WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&id));
+    }
+
+    Ok(())
+}
+
+/// Looks up the words starting at `cursor`, a character index into `text`. Every prefix of
+/// the text after the cursor is deinflected and matched against expressions and readings,
+/// so `食べなかった` finds `食べる` and `たべなかった` finds it by its reading.
+#[tauri::command]
+pub async fn lookup_text(
+    text: String,
+    cursor: usize,
+    handle: AppHandle,
+) -> AppResult<LookupResult> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    lookup(&text, cursor, &pool).await
+}
+
+pub async fn lookup(text: &str, cursor: usize, pool: &SqlitePool) -> AppResult<LookupResult> {
+    let chars: Vec<char> = text.chars().skip(cursor).take(MAX_LOOKUP_LENGTH).collect();
+    let Some(first_char) = chars.first() else {
+        return Ok(LookupResult::default());
+    };
+
+    let mut matches = match_terms(&chars, pool).await?;
+    matches.truncate(MAX_LOOKUP_TERMS);
+
+    let character = first_char.to_string();
+    let kanji: Vec<KanjiRow> = sqlx::query_as(
+        "SELECT dictionary_kanji.*, dictionary.title AS dictionary FROM dictionary_kanji
+        INNER JOIN dictionary ON dictionary.id = dictionary_kanji.dictionary_id
+        WHERE dictionary_kanji.character = ? AND dictionary.is_importing = 0
+        ORDER BY dictionary.priority",
+    )
+    .bind(&character)
+    .fetch_all(pool)
+    .await?;
+
+    let expressions: Vec<&str> = matches
+        .iter()
+        .map(|term| term.row.expression.as_str())
+        .chain(Some(character.as_str()).filter(|_| !kanji.is_empty()))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let frequencies = find_frequencies(&expressions, pool).await?;
+
+    let mut result = LookupResult::default();
+    for TermMatch {
+        source,
+        deinflection,
+        row,
+    } in matches
+    {
+        result.terms.push(TermEntry {
+            reasons: deinflection.reasons.iter().map(|r| r.to_string()).collect(),
+            definition_tags: split_tags(&row.definition_tags),
+            term_tags: split_tags(&row.term_tags),
+            glossary: serde_json::from_str(&row.glossary).unwrap_or_default(),
+            score: row.score,
+            frequencies: frequencies_of(&frequencies, "term", &row.expression, Some(&row.reading)),
+            expression: row.expression,
+            reading: row.reading,
+            source,
+            dictionary: row.dictionary,
+        });
+    }
+
+    for row in kanji {
+        result.kanji.push(KanjiEntry {
+            onyomi: split_tags(&row.onyomi),
+            kunyomi: split_tags(&row.kunyomi),
+            tags: split_tags(&row.tags),
+            meanings: serde_json::from_str(&row.meanings).unwrap_or_default(),
+            frequencies: frequencies_of(&frequencies, "kanji", &character, None),
+            character: row.character,
+            dictionary: row.dictionary,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Finds the longest dictionary word at any position of a text. The terms every position
+/// could start with are fetched together up front, so the words of a whole text take a
+/// handful of queries instead of a lookup per character.
+pub struct WordFinder {
+    // the deinflected prefixes starting at every position of the text
+    candidates: Vec<Vec<(String, Deinflection)>>,
+    index: TermIndex,
+}
+
+impl WordFinder {
+    pub async fn new(chars: &[char], pool: &SqlitePool) -> AppResult<Self> {
+        let candidates: Vec<_> = (0..chars.len())
+            .map(|position| {
+                let end = (position + MAX_LOOKUP_LENGTH).min(chars.len());
+                prefix_candidates(&chars[position..end])
+            })
+            .collect();
+        let index = TermIndex::load(candidates.iter().flatten(), pool).await?;
+
+        Ok(Self { candidates, index })
+    }
+
+    /// The longest word starting at `position` in its dictionary form, `None` when no
+    /// dictionary has a word starting there. `length` counts the characters it took.
+    pub fn longest_word(&self, position: usize) -> Option<WordMatch> {
+        let mut matches = self.index.matches(self.candidates.get(position)?);
+        let length = matches.first()?.source.chars().count();
+        // a kana word is read as the word written that way before the ones only read like it,
+        // so `は` stays the particle instead of becoming `歯`
+        let best = matches
+            .iter()
+            .take_while(|term| term.source.chars().count() == length)
+            .position(|term| term.row.expression == term.deinflection.term)
+            .unwrap_or(0);
+        let term = matches.swap_remove(best);
+
+        Some(WordMatch {
+            length,
+            expression: term.row.expression,
+            reading: term.row.reading,
+        })
+    }
+}
+
+// the rows of fetched terms, reachable by their expression and by their reading
+struct TermIndex {
+    rows: Vec<TermRow>,
+    by_term: HashMap<String, Vec<usize>>,
+}
+
+impl TermIndex {
+    async fn load<'a>(
+        candidates: impl IntoIterator<Item = &'a (String, Deinflection)>,
+        pool: &SqlitePool,
+    ) -> Result<Self, sqlx::Error> {
+        let terms: Vec<&str> = candidates
+            .into_iter()
+            .map(|(_, deinflection)| deinflection.term.as_str())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let rows = find_terms(&terms, pool).await?;
+
+        let mut by_term: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, row) in rows.iter().enumerate() {
+            by_term
+                .entry(row.expression.clone())
+                .or_default()
+                .push(index);
+            if row.reading != row.expression {
+                by_term.entry(row.reading.clone()).or_default().push(index);
+            }
+        }
+
+        Ok(Self { rows, by_term })
+    }
+
+    /// Every term one of the candidates deinflects to, the longest match first, then by
+    /// dictionary priority and score.
+    fn matches(&self, candidates: &[(String, Deinflection)]) -> Vec<TermMatch> {
+        let mut matches = Vec::new();
+        let mut seen = HashSet::new();
+        for (source, deinflection) in candidates {
+            for row in self
+                .by_term
+                .get(deinflection.term.as_str())
+                .into_iter()
+                .flatten()
+                .map(|index| &self.rows[*index])
+            {
+                if matches_rules(deinflection, parse_rules(&row.rules)) && seen.insert(row.id) {
+                    matches.push(TermMatch {
+                        source: source.clone(),
+                        deinflection: deinflection.clone(),
+                        row: row.clone(),
+                    });
+                }
+            }
+        }
+        // the sort is stable, so entries keep the order of their deinflections within a length
+        matches.sort_by_key(|term| {
+            (
+                std::cmp::Reverse(term.source.chars().count()),
+                term.row.priority,
+                std::cmp::Reverse(term.row.score),
+            )
+        });
+
+        matches
+    }
+}
+
+// every prefix of `chars` with the forms it could be inflected from, longest prefix first
+// so the longest match wins when entries are deduplicated
+fn prefix_candidates(chars: &[char]) -> Vec<(String, Deinflection)> {
+    let mut candidates = Vec::new();
+    for length in (1..=chars.len()).rev() {
+        let source: String = chars[..length].iter().collect();
+        let hiragana = to_hiragana(&source);
+
+        candidates.extend(deinflect(&source).into_iter().map(|d| (source.clone(), d)));
+        if hiragana != source {
+            candidates.extend(
+                deinflect(&hiragana)
+                    .into_iter()
+                    .map(|d| (source.clone(), d)),
+            );
+        }
+    }
+    candidates
+}
+
+/// Every term matching a prefix of `chars`, see `TermIndex::matches`.
+async fn match_terms(chars: &[char], pool: &SqlitePool) -> AppResult<Vec<TermMatch>> {
+    let candidates = prefix_candidates(chars);
+    let index = TermIndex::load(&candidates, pool).await?;
+
+    Ok(index.matches(&candidates))
+}
+
+/// Every term whose expression or reading is one of `terms`.
+async fn find_terms(terms: &[&str], pool: &SqlitePool) -> Result<Vec<TermRow>, sqlx::Error> {
+    let mut rows = Vec::new();
+
+    for batch in terms.chunks(LOOKUP_BATCH_SIZE) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let sql = format!(
+            "SELECT dictionary_term.*, dictionary.title AS dictionary, dictionary.priority
+            FROM dictionary_term
+            INNER JOIN dictionary ON dictionary.id = dictionary_term.dictionary_id
+            WHERE dictionary.is_importing = 0
+            AND (dictionary_term.expression IN ({placeholders})
+            OR dictionary_term.reading IN ({placeholders}))"
+        );
+
+        let mut query = sqlx::query_as(&sql);
+        for term in batch.iter().chain(batch) {
+            query = query.bind(term);
+        }
+        rows.extend(query.fetch_all(pool).await?);
+    }
+
+    Ok(rows)
+}
+
+/// The frequencies of every term and kanji in `expressions` in dictionary priority order,
+/// the most common first within a dictionary.
+/// A lookup shows a few dozen entries at most, so they fit one query.
+async fn find_frequencies(
+    expressions: &[&str],
+    pool: &SqlitePool,
+) -> Result<Vec<FrequencyRow>, sqlx::Error> {
+    let mut rows = Vec::new();
+
+    for batch in expressions.chunks(LOOKUP_BATCH_SIZE) {
+        let placeholders = vec!["?"; batch.len()].join(", ");
+        let sql = format!(
+            "SELECT kind, expression, dictionary_frequency.reading,
+            dictionary.title AS dictionary, value, display_value
+            FROM dictionary_frequency
+            INNER JOIN dictionary ON dictionary.id = dictionary_frequency.dictionary_id
+            WHERE dictionary.is_importing = 0 AND expression IN ({placeholders})
+            ORDER BY dictionary.priority,
+            CASE dictionary.frequency_mode WHEN 'occurrence-based' THEN -value ELSE value END"
+        );
+
+        let mut query = sqlx::query_as(&sql);
+        for expression in batch {
+            query = query.bind(expression);
+        }
+        rows.extend(query.fetch_all(pool).await?);
+    }
+
+    Ok(rows)
+}
+
+// the frequencies of one entry, a frequency given for one reading only applies to that reading
+fn frequencies_of(
+    rows: &[FrequencyRow],
+    kind: &str,
+    expression: &str,
+    reading: Option<&str>,
+) -> Vec<Frequency> {
+    rows.iter()
+        .filter(|row| row.kind == kind && row.expression == expression)
+        .filter(|row| row.reading.is_none() || row.reading.as_deref() == reading)
+        .map(|row| Frequency {
+            dictionary: row.dictionary.clone(),
+            value: row.value,
+            display_value: row.display_value.clone(),
+        })
+        .collect()
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split_whitespace().map(str::to_string).collect()
+}
+
+// katakana sits 0x60 code points after the matching hiragana
+fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn count(table: &str, pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn insert(id: &str, bank: Bank, pool: &SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_bank(id, &bank, &mut conn).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn imports_banks_before_showing_the_dictionary() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+        let index = DictionaryIndex {
+            title: "Test".to_string(),
+            revision: None,
+            format: Some(3),
+            version: None,
+            frequency_mode: FrequencyMode::RankBased,
+        };
+
+        start_import("first", &index, &pool).await.unwrap();
+        // more rows than fit one insert
+        let terms: Vec<Value> = (0..250)
+            .map(|i| {
+                json!([
+                    format!("語{i}"),
+                    format!("ご{i}"),
+                    "",
+                    "",
+                    0,
+                    ["word"],
+                    i,
+                    ""
+                ])
+            })
+            .collect();
+        let terms = Bank::parse(BankKind::Term, 3, &serde_json::to_vec(&terms).unwrap()).unwrap();
+        insert("first", terms, &pool).await;
+        let frequencies = json!([
+            ["語1", "freq", { "reading": "ご1", "frequency": 10 }],
+            ["語1", "freq", { "reading": "ごいち", "frequency": 5 }],
+            ["語1", "freq", 20],
+        ]);
+        let frequencies = Bank::parse(
+            BankKind::TermMeta,
+            3,
+            &serde_json::to_vec(&frequencies).unwrap(),
+        )
+        .unwrap();
+        insert("first", frequencies, &pool).await;
+
+        assert_eq!(count("dictionary_term", &pool).await, 250);
+        assert!(
+            lookup("語1", 0, &pool).await.unwrap().terms.is_empty(),
+            "a dictionary being imported isn't looked up"
+        );
+
+        let dictionary = finish_import("first", &pool).await.unwrap();
+        assert_eq!(dictionary.term_count, 250);
+        let result = lookup("語1", 0, &pool).await.unwrap();
+        let term = result
+            .terms
+            .iter()
+            .find(|term| term.expression == "語1")
+            .unwrap();
+        let values: Vec<i64> = term.frequencies.iter().map(|f| f.value).collect();
+        assert_eq!(
+            values,
+            [10, 20],
+            "the other reading's frequency is left out"
+        );
+
+        // importing it again replaces it, an import that never finished is cleaned up
+        start_import("second", &index, &pool).await.unwrap();
+        assert_eq!(count("dictionary_term", &pool).await, 0);
+        remove_unfinished_imports(&pool).await.unwrap();
+        assert_eq!(count("dictionary", &pool).await, 0);
+    }
+}
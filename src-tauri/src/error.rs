@@ -0,0 +1,98 @@
+use std::io;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// The error every `#[tauri::command]` returns.
+/// It reaches the frontend as `{ kind, message, path }` so the UI can show what went wrong.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{source}")]
+    Io {
+        source: io::Error,
+        path: Option<String>,
+    },
+    #[error("`{path}` was not found")]
+    NotFound { path: String },
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    #[error("background task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[error("could not decode image: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("could not reach AnkiConnect: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("AnkiConnect: {0}")]
+    Anki(String),
+}
+
+impl AppError {
+    pub fn io(source: io::Error, path: impl Into<String>) -> Self {
+        Self::Io {
+            source,
+            path: Some(path.into()),
+        }
+    }
+
+    pub fn not_found(path: impl Into<String>) -> Self {
+        Self::NotFound { path: path.into() }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database",
+            Self::Io { source, .. } if source.kind() == io::ErrorKind::NotFound => "not_found",
+            Self::Io { .. } => "io",
+            Self::NotFound { .. } => "not_found",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::Task(_) => "task",
+            Self::Image(_) => "image",
+            Self::Http(_) => "http",
+            Self::Anki(_) => "anki",
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::Io { path, .. } => path.as_deref(),
+            Self::NotFound { path } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(source: io::Error) -> Self {
+        Self::Io { source, path: None }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("path", &self.path())?;
+        state.end()
+    }
+}
+
+/// Attaches the offending path to filesystem errors.
+pub trait PathContext<T> {
+    fn with_path(self, path: &str) -> AppResult<T>;
+}
+
+impl<T> PathContext<T> for Result<T, io::Error> {
+    fn with_path(self, path: &str) -> AppResult<T> {
+        self.map_err(|e| AppError::io(e, path))
+    }
+}
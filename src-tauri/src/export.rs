@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::bookmarks::{list_all_bookmarks, Bookmark};
+use crate::error::{AppResult, PathContext};
+
+// bumped when a field is renamed or removed, new fields don't need it
+const EXPORT_VERSION: u32 = 1;
+
+/// The user's own data in the library, the folders themselves are found again by adding the roots.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LibraryExport {
+    pub version: u32,
+    pub exported_at: String,
+    pub roots: Vec<String>,
+    pub chapters: Vec<ExportedChapter>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ExportedChapter {
+    pub full_path: String,
+    pub title: String,
+    pub is_read: bool,
+}
+
+/// Writes the library roots, reading progress and bookmarks to a JSON file.
+#[tauri::command]
+pub async fn export_library(path: String, handle: AppHandle) -> AppResult<LibraryExport> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let export = build_library_export(&pool).await?;
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&export)?)
+        .await
+        .with_path(&path)?;
+
+    Ok(export)
+}
+
+async fn build_library_export(pool: &SqlitePool) -> AppResult<LibraryExport> {
+    let roots = sqlx::query_scalar("SELECT full_path FROM library_root ORDER BY full_path")
+        .fetch_all(pool)
+        .await?;
+    let chapters =
+        sqlx::query_as("SELECT full_path, title, is_read FROM manga_folder ORDER BY full_path")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(LibraryExport {
+        version: EXPORT_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        roots,
+        chapters,
+        bookmarks: list_all_bookmarks(pool).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn exports_bookmarks_with_the_library() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query("INSERT INTO library_root (id, full_path) VALUES ('root', '/manga')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO manga_folder (id, root_id, title, full_path, is_read)
+            VALUES ('chapter', 'root', 'Chapter 1', '/manga/ch1', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO manga_panel (id, folder_id, title, full_path)
+            VALUES ('panel', 'chapter', '001.png', '/manga/ch1/001.png')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // one bookmark on a panel in the library and one whose panel is gone
+        sqlx::query(
+            "INSERT INTO bookmark (id, panel_id, panel_path, note) VALUES
+            ('kept', 'panel', '/manga/ch1/001.png', 'a note'),
+            ('orphaned', NULL, '/old/ch1/001.png', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let export = build_library_export(&pool).await.unwrap();
+        assert_eq!(export.roots, ["/manga"]);
+        assert_eq!(export.chapters.len(), 1);
+        assert!(export.chapters[0].is_read);
+
+        let mut bookmarks: Vec<(&str, Option<&str>, &str)> = export
+            .bookmarks
+            .iter()
+            .map(|bookmark| {
+                (
+                    bookmark.id.as_str(),
+                    bookmark.chapter_path.as_deref(),
+                    bookmark.note.as_str(),
+                )
+            })
+            .collect();
+        bookmarks.sort();
+        assert_eq!(
+            bookmarks,
+            [
+                ("kept", Some("/manga/ch1"), "a note"),
+                ("orphaned", None, ""),
+            ]
+        );
+
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["version"], EXPORT_VERSION);
+        assert_eq!(json["bookmarks"].as_array().map(Vec::len), Some(2));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{
+    plugin::{Builder, TauriPlugin},
+    AppHandle, Emitter, Manager, Runtime,
+};
+use tokio::sync::Mutex;
+
+use crate::global::{get_manga_folder_by_path, get_parent_folder_by_path};
+
+/// Emitted to the frontend when the app is launched again while it's already running.
+pub const OPENED_EVENT: &str = "instance://opened";
+
+// the running instance answers with this, so a stale socket or a port reused by
+// another program isn't mistaken for it
+const ACK: &str = "manga_shelf";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a second launch hands over to the running instance.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Launch {
+    args: Vec<String>,
+    cwd: PathBuf,
+}
+
+impl Launch {
+    fn current() -> Self {
+        Self {
+            args: env::args().skip(1).collect(),
+            cwd: env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    /// The first argument naming something on disk, relative paths are taken from where the
+    /// second launch was started.
+    fn open_path(&self) -> Option<PathBuf> {
+        self.args
+            .iter()
+            .filter(|arg| !arg.starts_with('-'))
+            .map(|arg| self.cwd.join(arg))
+            .find(|path| path.exists())
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenedKind {
+    Chapter,
+    Series,
+    // not in the library yet, the frontend offers to add it as a root
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Opened {
+    pub args: Vec<String>,
+    pub path: Option<String>,
+    pub kind: Option<OpenedKind>,
+}
+
+/// Keeps the app to a single instance. A second launch forwards its arguments to the
+/// running instance over a socket in the app data dir and exits before any window opens.
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("single-instance")
+        .setup(|app, _api| {
+            let app_data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&app_data_dir)?;
+
+            let launch = Launch::current();
+            if forward(&app_data_dir, &launch).is_ok() {
+                std::process::exit(0);
+            }
+
+            let listener = match listen(&app_data_dir) {
+                Ok(listener) => listener,
+                Err(_) => match forward(&app_data_dir, &launch) {
+                    // another launch started listening in the meantime
+                    Ok(()) => std::process::exit(0),
+                    Err(e) if is_stale(&e) => {
+                        remove_stale(&app_data_dir)?;
+                        listen(&app_data_dir)?
+                    }
+                    // a running instance that was too slow to answer keeps its socket
+                    Err(e) => return Err(e.into()),
+                },
+            };
+            let handle = app.clone();
+            thread::spawn(move || accept_launches(listener, handle));
+
+            Ok(())
+        })
+        .build()
+}
+
+fn forward(app_data_dir: &Path, launch: &Launch) -> io::Result<()> {
+    let mut stream = connect(app_data_dir)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+
+    let mut message = serde_json::to_string(launch)?;
+    message.push('\n');
+    stream.write_all(message.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim_end() != ACK {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not answered by a running instance",
+        ));
+    }
+
+    Ok(())
+}
+
+fn accept_launches<R: Runtime>(listener: Listener, handle: AppHandle<R>) {
+    for stream in listener.incoming() {
+        let launch = match stream.and_then(read_launch) {
+            Ok(launch) => launch,
+            Err(e) => {
+                eprintln!("Error Reading Launch: {}", e);
+                continue;
+            }
+        };
+
+        if let Some(window) = handle.get_webview_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let opened = resolve_launch(launch, &handle).await;
+            if let Err(e) = handle.emit(OPENED_EVENT, opened) {
+                eprintln!("Error Emitting Launch: {}", e);
+            }
+        });
+    }
+}
+
+fn read_launch(stream: Stream) -> io::Result<Launch> {
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut message = String::new();
+    reader.read_line(&mut message)?;
+    let launch = serde_json::from_str(&message)?;
+
+    let mut stream = reader.into_inner();
+    stream.write_all(format!("{}\n", ACK).as_bytes())?;
+
+    Ok(launch)
+}
+
+async fn find_opened_kind(path: &str, pool: &SqlitePool) -> Result<OpenedKind, sqlx::Error> {
+    if get_manga_folder_by_path(path, pool).await?.is_some() {
+        Ok(OpenedKind::Chapter)
+    } else if get_parent_folder_by_path(path, pool).await?.is_some() {
+        Ok(OpenedKind::Series)
+    } else {
+        Ok(OpenedKind::Unknown)
+    }
+}
+
+async fn resolve_launch<R: Runtime>(launch: Launch, handle: &AppHandle<R>) -> Opened {
+    let Some(path) = launch.open_path() else {
+        return Opened {
+            args: launch.args,
+            path: None,
+            kind: None,
+        };
+    };
+
+    let path = path.canonicalize().unwrap_or(path);
+    let path = path.to_string_lossy().to_string();
+
+    // a launch can arrive before the database is opened in setup
+    let kind = match handle.try_state::<Mutex<SqlitePool>>() {
+        Some(pool) => {
+            let pool = pool.lock().await.clone();
+            match find_opened_kind(&path, &pool).await {
+                Ok(kind) => kind,
+                Err(e) => {
+                    eprintln!("Error Finding Opened Folder: {} -> {}", path, e);
+                    OpenedKind::Unknown
+                }
+            }
+        }
+        None => OpenedKind::Unknown,
+    };
+
+    Opened {
+        args: launch.args,
+        path: Some(path),
+        kind: Some(kind),
+    }
+}
+
+#[cfg(unix)]
+type Listener = std::os::unix::net::UnixListener;
+#[cfg(unix)]
+type Stream = std::os::unix::net::UnixStream;
+
+#[cfg(unix)]
+fn socket_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("instance.sock")
+}
+
+#[cfg(unix)]
+fn connect(app_data_dir: &Path) -> io::Result<Stream> {
+    Stream::connect(socket_path(app_data_dir))
+}
+
+#[cfg(unix)]
+fn listen(app_data_dir: &Path) -> io::Result<Listener> {
+    Listener::bind(socket_path(app_data_dir))
+}
+
+/// Nothing is listening on the socket, so it was left behind by an instance that crashed.
+fn is_stale(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::NotFound
+    )
+}
+
+/// Only called once `is_stale` says nothing is listening on the socket.
+#[cfg(unix)]
+fn remove_stale(app_data_dir: &Path) -> io::Result<()> {
+    std::fs::remove_file(socket_path(app_data_dir))
+}
+
+// windows has no unix sockets in std, the running instance listens on a loopback port
+// and writes it to a lock file instead
+#[cfg(not(unix))]
+type Listener = std::net::TcpListener;
+#[cfg(not(unix))]
+type Stream = std::net::TcpStream;
+
+#[cfg(not(unix))]
+fn lock_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("instance.lock")
+}
+
+#[cfg(not(unix))]
+fn connect(app_data_dir: &Path) -> io::Result<Stream> {
+    let port: u16 = std::fs::read_to_string(lock_path(app_data_dir))?
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Stream::connect_timeout(
+        &(std::net::Ipv4Addr::LOCALHOST, port).into(),
+        CONNECT_TIMEOUT,
+    )
+}
+
+#[cfg(not(unix))]
+fn listen(app_data_dir: &Path) -> io::Result<Listener> {
+    let listener = Listener::bind((std::net::Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    std::fs::write(lock_path(app_data_dir), port.to_string())?;
+    Ok(listener)
+}
+
+// binding a new loopback port can't collide with a crashed instance, its lock file is overwritten
+#[cfg(not(unix))]
+fn remove_stale(_app_data_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_an_unanswered_socket_is_stale() {
+        let dir = env::temp_dir().join(format!("instance-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let launch = Launch {
+            args: Vec::new(),
+            cwd: dir.clone(),
+        };
+
+        let error = forward(&dir, &launch).unwrap_err();
+        assert!(is_stale(&error), "no socket yet: {error}");
+
+        // a listener that never answers, like an instance busy starting up
+        let listener = listen(&dir).unwrap();
+        let error = forward(&dir, &launch).unwrap_err();
+        assert!(!is_stale(&error), "slow instance: {error}");
+
+        // the socket file outlives the listener, like after a crash
+        drop(listener);
+        let error = forward(&dir, &launch).unwrap_err();
+        assert!(is_stale(&error), "crashed instance: {error}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
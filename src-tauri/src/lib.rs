@@ -1,50 +1,124 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::sync::Arc;
+
 use tauri::Manager;
+mod anki;
+mod bookmarks;
+mod chart;
+mod comic_info;
+mod covers;
 mod db;
+mod deinflect;
+mod dictionary;
+mod error;
+mod export;
 mod global;
+mod instance;
+mod library;
 mod manga;
 mod misc;
+mod mokuro;
+mod ordering;
+mod reading_mode;
+mod scanner;
+mod sessions;
+mod source;
+mod spreads;
 mod stats;
+mod thumbnails;
+mod vocabulary;
+mod watcher;
+mod zoom;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // registered first so a second launch exits before the other plugins start
+        .plugin(instance::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(scanner::ScannerState::default())
+        .manage(watcher::LibraryWatcher::default())
+        // both protocols check the path against the library before reading it
+        .register_asynchronous_uri_scheme_protocol(
+            source::PANEL_PROTOCOL,
+            |ctx, request, responder| {
+                let handle = ctx.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    responder.respond(source::panel_protocol(&handle, &request).await);
+                });
+            },
+        )
+        // decoding covers is slow, so thumbnails are answered from the async runtime
+        .register_asynchronous_uri_scheme_protocol(
+            thumbnails::THUMBNAIL_PROTOCOL,
+            |ctx, request, responder| {
+                let handle = ctx.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    responder.respond(thumbnails::thumbnail_protocol(&handle, &request).await);
+                });
+            },
+        )
         .setup(|app| {
             let handle = app.handle();
-            let app_data_dir = handle.path().app_data_dir().unwrap();
+            let app_data_dir = handle.path().app_data_dir()?;
             if !app_data_dir.exists() {
-                std::fs::create_dir(&app_data_dir).unwrap();
+                std::fs::create_dir(&app_data_dir)?;
             }
 
             db::create_database(
-                app_data_dir.join("main.db").to_str().unwrap(),
+                &app_data_dir.join("main.db").to_string_lossy(),
                 handle.clone(),
-            );
+            )?;
+
+            handle.manage(Arc::new(thumbnails::ThumbnailCache::new(
+                app_data_dir.join("thumbnails"),
+            )));
+            thumbnails::spawn_thumbnail_eviction(handle.clone());
+            handle.manage(covers::CoverStore::new(app_data_dir.join("covers")));
+            handle.manage(source::ArchiveCache::default());
 
-            misc::close_open_instance();
+            // index panels in the background so startup and the stats page stay responsive
+            scanner::spawn_library_scan(handle.clone());
+            // keep the library in sync with chapters added, removed or renamed on disk
+            watcher::start_library_watcher(handle.clone());
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            manga::update_parent_folders,
-            manga::update_manga_folders,
+            manga::set_folder_expanded,
+            manga::get_child_folders,
             manga::update_manga_panel,
             manga::get_parent_folders,
             manga::get_manga_folders,
             manga::get_manga_panel,
+            manga::get_manga_panels,
             manga::find_last_read_panel,
             manga::delete_folder,
-            manga::update_folder_time_spent_reading,
             manga::get_next_or_previous_manga_folder,
             manga::update_folder_double_panels,
             manga::set_folder_read,
             manga::set_folder_unread,
             manga::find_last_read_manga_folder,
+            comic_info::get_comic_info,
+            comic_info::get_series,
+            reading_mode::get_reading_mode,
+            reading_mode::set_chapter_reading_mode,
+            reading_mode::set_series_reading_mode,
+            zoom::get_zoom,
+            zoom::update_zoom,
+            zoom::set_chapter_zoom,
+            zoom::set_series_zoom,
+            spreads::get_panel_layout,
+            spreads::set_spread_offset,
+            thumbnails::rebuild_thumbnails,
+            covers::set_custom_cover_panel,
+            covers::set_custom_cover_file,
+            covers::set_custom_cover_crop,
+            covers::reset_custom_cover,
             global::set_global_manga_folder,
             global::set_global_parent_folder,
             global::get_global_manga,
@@ -52,8 +126,43 @@ pub fn run() {
             stats::fetch_daily_manga_folders,
             stats::create_manga_stats,
             stats::update_global_stats,
-            stats::create_chart_stats,
-            stats::update_chart_watchtime,
+            chart::get_reading_chart,
+            stats::set_reading_goal,
+            stats::remove_reading_goal,
+            stats::get_goal_progress,
+            scanner::start_library_scan,
+            sessions::start_reading_session,
+            sessions::update_reading_session,
+            sessions::get_reading_time,
+            library::get_library_roots,
+            library::add_library_root,
+            library::rescan_library_root,
+            library::remove_library_root,
+            mokuro::get_panel_text_blocks,
+            dictionary::import_dictionary,
+            dictionary::get_dictionaries,
+            dictionary::remove_dictionary,
+            dictionary::lookup_text,
+            anki::get_anki_settings,
+            anki::set_anki_settings,
+            anki::get_anki_catalog,
+            anki::get_anki_model_fields,
+            anki::can_mine_word,
+            anki::mine_word,
+            vocabulary::analyze_library_vocabulary,
+            vocabulary::get_vocabulary_stats,
+            vocabulary::rank_series_by_comprehension,
+            vocabulary::import_known_words,
+            vocabulary::get_known_word_count,
+            vocabulary::clear_known_words,
+            bookmarks::create_bookmark,
+            bookmarks::update_bookmark,
+            bookmarks::delete_bookmark,
+            bookmarks::get_chapter_bookmarks,
+            bookmarks::get_series_bookmarks,
+            bookmarks::get_all_bookmarks,
+            bookmarks::open_bookmark,
+            export::export_library,
             misc::show_in_folder,
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,498 @@
+use std::{
+    collections::HashSet,
+    fs::read_dir,
+    path::{Path, MAIN_SEPARATOR},
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+use crate::manga::{
+    find_parent_folder_id, split_path_parts, upsert_manga_folder, upsert_parent_folder,
+};
+use crate::ordering::ChapterKey;
+use crate::scanner::spawn_library_scan;
+use crate::source::{is_archive, ChapterSource};
+use crate::watcher::{mark_missing, unwatch_parent_folder, watch_parent_folder};
+
+/// A folder the user added, the series, volumes and chapters inside it are found by the backend.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct LibraryRoot {
+    pub id: String,
+    pub full_path: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum FolderKind {
+    #[default]
+    Series,
+    Volume,
+}
+
+/// What a scan of a root found and registered.
+#[derive(Debug, Serialize, Clone)]
+pub struct RootScan {
+    pub root: LibraryRoot,
+    pub series: usize,
+    pub volumes: usize,
+    pub chapters: usize,
+    pub missing: usize,
+}
+
+// more loose images than this next to other folders are a chapter, not a cover and a back cover
+const COVER_PANELS: usize = 2;
+
+/// A directory or archive below a root, read from disk before anything is registered.
+enum LibraryEntry {
+    Chapter(String),
+    // a chapter whose folder also holds other folders, like an `Extras` folder
+    ChapterWithFolders {
+        path: String,
+        folders: Vec<LibraryEntry>,
+    },
+    Folder {
+        path: String,
+        // 1 for a folder of chapters, 2 for a folder of those, and so on
+        levels: usize,
+        children: Vec<LibraryEntry>,
+    },
+}
+
+impl LibraryEntry {
+    fn read(path: &Path) -> Option<Self> {
+        let path_str = path.to_string_lossy().into_owned();
+        if is_archive(path) {
+            return Some(Self::Chapter(path_str));
+        }
+        if !path.is_dir() {
+            return None;
+        }
+
+        let children: Vec<Self> = match read_dir(path) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| Self::read(&entry.path()))
+                .collect(),
+            Err(e) => {
+                eprintln!("Error Reading: {} -> {}", path_str, e);
+                Vec::new()
+            }
+        };
+
+        let panels = ChapterSource::open(path)
+            .and_then(|source| source.panel_paths().ok())
+            .map_or(0, |paths| paths.len());
+        if children.is_empty() {
+            return (panels > 0).then_some(Self::Chapter(path_str));
+        }
+        // one or two loose images next to chapters are covers, more are a chapter of their own
+        if panels > COVER_PANELS {
+            return Some(Self::ChapterWithFolders {
+                path: path_str,
+                folders: children,
+            });
+        }
+
+        let levels = 1 + children.iter().map(Self::levels).max().unwrap_or(0);
+        Some(Self::Folder {
+            path: path_str,
+            levels,
+            children,
+        })
+    }
+
+    fn levels(&self) -> usize {
+        match self {
+            Self::Chapter(_) | Self::ChapterWithFolders { .. } => 0,
+            Self::Folder { levels, .. } => *levels,
+        }
+    }
+
+    /// A folder of chapters is a series, and so is a folder of volumes.
+    /// Folders any deeper only group series together, like `Manga/Shounen/Naruto`.
+    fn is_series(&self) -> bool {
+        match self {
+            Self::Chapter(_) | Self::ChapterWithFolders { .. } => false,
+            Self::Folder { levels: 1, .. } => true,
+            Self::Folder {
+                levels: 2,
+                children,
+                ..
+            } => children.iter().all(|child| match child {
+                Self::Chapter(_) | Self::ChapterWithFolders { .. } => true,
+                Self::Folder { path, .. } => {
+                    ChapterKey::parse(&split_path_parts(path).file_name).has_volume()
+                }
+            }),
+            Self::Folder { .. } => false,
+        }
+    }
+
+    /// Flattens the entry into what gets registered, every folder before the ones inside it.
+    fn classify(self, in_series: bool, classified: &mut Vec<Classified>) {
+        let is_series = self.is_series();
+        match self {
+            Self::Chapter(path) => classified.push(Classified::Chapter(path)),
+            // the folders inside a chapter can't belong to it, they are classified on their own
+            Self::ChapterWithFolders { path, folders } => {
+                classified.push(Classified::Chapter(path));
+                for folder in folders {
+                    folder.classify(false, classified);
+                }
+            }
+            Self::Folder { path, children, .. } if in_series || is_series => {
+                classified.push(if in_series {
+                    Classified::Volume(path)
+                } else {
+                    Classified::Series(path)
+                });
+                for child in children {
+                    child.classify(true, classified);
+                }
+            }
+            // grouping folders aren't registered, the series inside them show up on the dashboard
+            Self::Folder { children, .. } => {
+                for child in children {
+                    child.classify(false, classified);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Classified {
+    Series(String),
+    Volume(String),
+    Chapter(String),
+}
+
+#[tauri::command]
+pub async fn get_library_roots(handle: AppHandle) -> AppResult<Vec<LibraryRoot>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let roots = sqlx::query_as("SELECT * FROM library_root ORDER BY full_path")
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(roots)
+}
+
+#[tauri::command]
+pub async fn add_library_root(root_path: String, handle: AppHandle) -> AppResult<RootScan> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let path = Path::new(&root_path);
+    if !path.is_dir() && !is_archive(path) {
+        return Err(AppError::not_found(&root_path));
+    }
+
+    sqlx::query(
+        "INSERT INTO library_root (id, full_path, created_at, updated_at)
+        VALUES (?, ?, datetime('now', 'localtime'), datetime('now', 'localtime'))
+        ON CONFLICT (full_path) DO NOTHING",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&root_path)
+    .execute(&pool)
+    .await?;
+
+    let scan = scan_library_root(&root_path, &pool).await?;
+    watch_parent_folder(&handle, &root_path);
+    // index the new chapters' panels in the background
+    spawn_library_scan(handle.clone());
+
+    Ok(scan)
+}
+
+/// Picks up series and chapters added while the app was closed and flags the ones that are gone.
+#[tauri::command]
+pub async fn rescan_library_root(root_path: String, handle: AppHandle) -> AppResult<RootScan> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let scan = scan_library_root(&root_path, &pool).await?;
+    spawn_library_scan(handle.clone());
+
+    Ok(scan)
+}
+
+/// Removes a root and every folder found in it, `all_data` also drops the panels and their progress.
+#[tauri::command]
+pub async fn remove_library_root(
+    root_path: String,
+    all_data: bool,
+    handle: AppHandle,
+) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let mut tx = pool.begin().await?;
+
+    if !all_data {
+        // keep the panels and their progress, they are linked again if the root is re-added
+        sqlx::query(
+            "UPDATE manga_panel SET folder_id = NULL WHERE folder_id IN (
+                SELECT manga_folder.id FROM manga_folder
+                INNER JOIN library_root ON manga_folder.root_id = library_root.id
+                WHERE library_root.full_path = ?
+            )",
+        )
+        .bind(&root_path)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // series, volumes and chapters go with it through ON DELETE CASCADE
+    let result = sqlx::query("DELETE FROM library_root WHERE full_path = ?")
+        .bind(&root_path)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&root_path));
+    }
+
+    tx.commit().await?;
+
+    unwatch_parent_folder(&handle, &root_path);
+
+    Ok(())
+}
+
+async fn scan_library_root(root_path: &str, pool: &SqlitePool) -> AppResult<RootScan> {
+    let root: LibraryRoot = sqlx::query_as("SELECT * FROM library_root WHERE full_path = ?")
+        .bind(root_path)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(root_path))?;
+
+    let found = register_path(Path::new(root_path), pool).await?;
+
+    // chapters that were in the root before but weren't found now have been moved or deleted
+    let chapters: HashSet<&String> = found
+        .iter()
+        .filter_map(|classified| match classified {
+            Classified::Chapter(path) => Some(path),
+            _ => None,
+        })
+        .collect();
+    let known: Vec<String> = sqlx::query_scalar(
+        "SELECT full_path FROM manga_folder WHERE root_id = ? AND is_missing = 0",
+    )
+    .bind(&root.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut missing = 0;
+    for path in known.iter().filter(|path| !chapters.contains(path)) {
+        if mark_missing(path, pool).await? {
+            missing += 1;
+        }
+    }
+
+    let count = |matches: fn(&Classified) -> bool| found.iter().filter(|c| matches(c)).count();
+    Ok(RootScan {
+        series: count(|c| matches!(c, Classified::Series(_))),
+        volumes: count(|c| matches!(c, Classified::Volume(_))),
+        chapters: chapters.len(),
+        missing,
+        root,
+    })
+}
+
+/// Classifies and registers everything at or below `path`, returns what was registered.
+/// A folder inside a registered series or volume can only be a volume itself.
+async fn register_path(path: &Path, pool: &SqlitePool) -> AppResult<Vec<Classified>> {
+    let in_series = find_parent_folder_id(&path.to_string_lossy(), pool)
+        .await?
+        .is_some();
+
+    let walk_path = path.to_path_buf();
+    let classified = tokio::task::spawn_blocking(move || {
+        let mut classified = Vec::new();
+        if let Some(entry) = LibraryEntry::read(&walk_path) {
+            entry.classify(in_series, &mut classified);
+        }
+        classified
+    })
+    .await?;
+
+    for entry in &classified {
+        match entry {
+            Classified::Series(path) => {
+                upsert_parent_folder(path, FolderKind::Series, pool).await?;
+            }
+            Classified::Volume(path) => {
+                upsert_parent_folder(path, FolderKind::Volume, pool).await?;
+            }
+            Classified::Chapter(path) => {
+                upsert_manga_folder(path, pool).await?;
+            }
+        }
+    }
+
+    Ok(classified)
+}
+
+/// Whether a scan would register the folder or archive at `path` as a chapter.
+pub async fn is_chapter_path(path: &Path) -> AppResult<bool> {
+    let path = path.to_path_buf();
+    let entry = tokio::task::spawn_blocking(move || LibraryEntry::read(&path)).await?;
+    Ok(matches!(
+        entry,
+        Some(LibraryEntry::Chapter(_) | LibraryEntry::ChapterWithFolders { .. })
+    ))
+}
+
+/// Registers series and chapters that appeared on disk, returns `false` if there were none.
+pub async fn register_new_path(path: &Path, pool: &SqlitePool) -> AppResult<bool> {
+    Ok(!register_path(path, pool).await?.is_empty())
+}
+
+/// The id of the closest library root containing `path`.
+pub async fn find_library_root_id(
+    path: &str,
+    pool: &SqlitePool,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM library_root
+        WHERE full_path = ? OR substr(?, 1, length(full_path) + 1) = full_path || ?
+        ORDER BY length(full_path) DESC
+        LIMIT 1",
+    )
+    .bind(path)
+    .bind(path)
+    .bind(MAIN_SEPARATOR.to_string())
+    .fetch_optional(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify_tree(files: &[&str]) -> Vec<Classified> {
+        let root = std::env::temp_dir().join(format!("library-{}", uuid::Uuid::new_v4()));
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"").unwrap();
+        }
+
+        // each top level folder on its own, like folders added to a grouping root
+        let mut classified = Vec::new();
+        for entry in read_dir(&root).unwrap().flatten() {
+            if let Some(entry) = LibraryEntry::read(&entry.path()) {
+                entry.classify(false, &mut classified);
+            }
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // paths relative to the root, in a stable order
+        let prefix = format!("{}{}", root.to_string_lossy(), MAIN_SEPARATOR);
+        let relative = |path: String| path.replace(&prefix, "").replace(MAIN_SEPARATOR, "/");
+        let mut classified: Vec<Classified> = classified
+            .into_iter()
+            .map(|entry| match entry {
+                Classified::Series(path) => Classified::Series(relative(path)),
+                Classified::Volume(path) => Classified::Volume(relative(path)),
+                Classified::Chapter(path) => Classified::Chapter(relative(path)),
+            })
+            .collect();
+        classified.sort();
+        classified
+    }
+
+    #[test]
+    fn classifies_library_folders() {
+        use Classified::{Chapter, Series, Volume};
+        let cases: Vec<(&str, Vec<&str>, Vec<Classified>)> = vec![
+            (
+                "series of chapters with a cover",
+                vec![
+                    "Berserk/cover.jpg",
+                    "Berserk/Ch 1/001.png",
+                    "Berserk/Ch 2/001.png",
+                ],
+                vec![
+                    Series("Berserk".into()),
+                    Chapter("Berserk/Ch 1".into()),
+                    Chapter("Berserk/Ch 2".into()),
+                ],
+            ),
+            (
+                "series of volumes",
+                vec!["Berserk/Vol 01/Ch 1/001.png", "Berserk/Vol 02/Ch 2/001.png"],
+                vec![
+                    Series("Berserk".into()),
+                    Volume("Berserk/Vol 01".into()),
+                    Volume("Berserk/Vol 02".into()),
+                    Chapter("Berserk/Vol 01/Ch 1".into()),
+                    Chapter("Berserk/Vol 02/Ch 2".into()),
+                ],
+            ),
+            (
+                "grouping folder",
+                vec![
+                    "Seinen/Berserk/Ch 1/001.png",
+                    "Seinen/Vagabond/Ch 1/001.png",
+                ],
+                vec![
+                    Series("Seinen/Berserk".into()),
+                    Series("Seinen/Vagabond".into()),
+                    Chapter("Seinen/Berserk/Ch 1".into()),
+                    Chapter("Seinen/Vagabond/Ch 1".into()),
+                ],
+            ),
+            (
+                "series of archives",
+                vec![
+                    "Berserk/Vol 01.cbz",
+                    "Berserk/Vol 02.cbz",
+                    "Berserk/notes.txt",
+                ],
+                vec![
+                    Series("Berserk".into()),
+                    Chapter("Berserk/Vol 01.cbz".into()),
+                    Chapter("Berserk/Vol 02.cbz".into()),
+                ],
+            ),
+            (
+                "chapter with a folder of extras",
+                vec![
+                    "Oneshot/001.png",
+                    "Oneshot/002.png",
+                    "Oneshot/003.png",
+                    "Oneshot/Extras/001.png",
+                ],
+                vec![Chapter("Oneshot".into()), Chapter("Oneshot/Extras".into())],
+            ),
+            (
+                "series whose chapters have extras",
+                vec![
+                    "Berserk/Ch 1/001.png",
+                    "Berserk/Ch 1/002.png",
+                    "Berserk/Ch 1/003.png",
+                    "Berserk/Ch 1/Extras/001.png",
+                    "Berserk/Ch 2/001.png",
+                ],
+                vec![
+                    Series("Berserk".into()),
+                    Chapter("Berserk/Ch 1".into()),
+                    Chapter("Berserk/Ch 1/Extras".into()),
+                    Chapter("Berserk/Ch 2".into()),
+                ],
+            ),
+            ("no images", vec!["Empty/notes.txt"], vec![]),
+        ];
+
+        for (name, files, expected) in cases {
+            assert_eq!(classify_tree(&files), expected, "{name}");
+        }
+    }
+}
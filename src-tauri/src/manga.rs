@@ -1,24 +1,34 @@
-use std::{
-    fs::read_dir,
-    io,
-    path::{Path, PathBuf},
-};
+use std::{collections::HashMap, fs::read_dir, io, path::MAIN_SEPARATOR};
 
 use serde::{Deserialize, Serialize};
 use sqlx::{query_as, SqlitePool};
 use tauri::{AppHandle, Manager};
 use tokio::sync::Mutex;
 
-use crate::misc::NUMBER_REGEX;
+use crate::comic_info::{get_comic_info_by_folder_id, upsert_comic_info, ComicInfo};
+use crate::error::{AppError, AppResult, PathContext};
+use crate::library::{find_library_root_id, FolderKind};
+use crate::ordering::{ChapterKey, PanelKey};
+use crate::reading_mode::ReadingMode;
+use crate::source::{is_archive, panel_image_size, split_archive_path, ChapterSource, PanelEntry};
+use crate::watcher::unwatch_parent_folder;
+use crate::zoom::FitMode;
 
 #[derive(Debug, Serialize, Deserialize, Default, sqlx::FromRow)]
 pub struct ParentFolder {
     pub id: String,
+    pub parent_id: Option<String>,
+    pub root_id: Option<String>,
+    pub kind: FolderKind,
     pub title: String,
     pub full_path: String,
     pub as_child: bool,
     pub is_expanded: bool,
     pub cover_panel_path: Option<String>,
+    pub custom_cover_path: Option<String>,
+    pub reading_mode: Option<ReadingMode>,
+    pub fit_mode: Option<FitMode>,
+    pub zoom_level: Option<u16>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -26,14 +36,21 @@ pub struct ParentFolder {
 #[derive(Debug, Serialize, Clone, Deserialize, Default, sqlx::FromRow)]
 pub struct MangaFolder {
     pub id: String,
+    pub parent_id: Option<String>,
+    pub root_id: Option<String>,
     pub title: String,
     pub full_path: String,
     pub as_child: bool,
     pub is_expanded: bool,
-    pub time_spent_reading: u32,
     pub double_panels: bool,
     pub is_read: bool,
     pub cover_panel_path: Option<String>,
+    pub custom_cover_path: Option<String>,
+    pub is_missing: bool,
+    pub reading_mode: Option<ReadingMode>,
+    pub fit_mode: Option<FitMode>,
+    pub zoom_level: Option<u16>,
+    pub spread_offset: bool,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -41,12 +58,14 @@ pub struct MangaFolder {
 #[derive(Debug, Serialize, Clone, Deserialize, Default, sqlx::FromRow)]
 pub struct MangaPanel {
     pub id: String,
+    pub folder_id: Option<String>,
     pub title: String,
     pub full_path: String,
     pub is_read: bool,
     pub width: u16,
     pub height: u16,
     pub zoom_level: u16,
+    pub is_missing: bool,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -58,44 +77,30 @@ pub struct PathParts {
     pub extension: Option<String>,
 }
 
-#[tauri::command]
-pub async fn update_parent_folders(
-    dir_paths: String,
-    handle: AppHandle,
-    as_child: bool,
-    is_expanded: bool,
-) -> Vec<ParentFolder> {
-    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
-    let parsed_paths = serde_json::from_str::<Vec<String>>(&dir_paths).unwrap();
-
-    // create a ParentFolder vector to return back to the frontend
-    let mut parent_folders: Vec<ParentFolder> = Vec::new();
-
-    for path in parsed_paths {
-        let uuid = uuid::Uuid::new_v4().to_string();
-        // gets the parent, file name, and extension of the path
-        let split_path = split_path_parts(&path);
-        let cover_panel_path = get_parent_folder_cover_panel_path(&path).unwrap_or_default();
-        //println!("{cover_panel_path}");
-
-        // create a ParentFolder struct to push into the parent_folders vector
-        let folder = ParentFolder {
-            id: uuid.clone(),
-            title: split_path.file_name.clone(),
-            full_path: path.clone(),
-            as_child,
-            is_expanded,
-            cover_panel_path: Some(cover_panel_path.clone()),
-            created_at: "".to_string(),
-            updated_at: "".to_string(),
-        };
-
-        parent_folders.push(folder);
-
-        sqlx::query(
-            "INSERT INTO parent_folder
+/// Registers a series or volume folder, keeping its expanded state and settings if it already exists.
+pub async fn upsert_parent_folder(
+    path: &str,
+    kind: FolderKind,
+    pool: &SqlitePool,
+) -> AppResult<ParentFolder> {
+    let uuid = uuid::Uuid::new_v4().to_string();
+    // gets the parent, file name, and extension of the path
+    let split_path = split_path_parts(path);
+    let folder_path = path.to_string();
+    let cover_panel_path = tokio::task::spawn_blocking(move || {
+        get_parent_folder_cover_panel_path(&folder_path).unwrap_or_default()
+    })
+    .await?;
+    let parent_id = find_parent_folder_id(path, pool).await?;
+    let root_id = find_library_root_id(path, pool).await?;
+
+    sqlx::query(
+        "INSERT INTO parent_folder
         (
             id,
+            parent_id,
+            root_id,
+            kind,
             title,
             full_path,
             as_child,
@@ -106,106 +111,192 @@ pub async fn update_parent_folders(
         )
         VALUES
         (
-            ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?, ?, ?,
             datetime('now', 'localtime'), datetime('now', 'localtime')
         )
         ON CONFLICT (full_path) DO UPDATE SET
-        is_expanded = excluded.is_expanded
+        parent_id = COALESCE(excluded.parent_id, parent_folder.parent_id),
+        root_id = COALESCE(excluded.root_id, parent_folder.root_id),
+        kind = excluded.kind,
+        as_child = excluded.as_child
         ",
-        )
-        .bind(uuid)
-        .bind(split_path.file_name)
+    )
+    .bind(uuid)
+    // only folders inside a series are shown as children, everything else is on the dashboard
+    .bind(&parent_id)
+    .bind(root_id)
+    .bind(kind)
+    .bind(split_path.file_name)
+    .bind(path)
+    .bind(parent_id.is_some())
+    .bind(false)
+    .bind(cover_panel_path)
+    .execute(pool)
+    .await?;
+
+    // read the row back so the caller gets the stored id, not the one generated above
+    let folder: ParentFolder = sqlx::query_as("SELECT * FROM parent_folder WHERE full_path = ?")
         .bind(path)
-        .bind(as_child)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn set_folder_expanded(
+    folder_path: String,
+    is_expanded: bool,
+    handle: AppHandle,
+) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("UPDATE parent_folder SET is_expanded = ? WHERE full_path = ?")
         .bind(is_expanded)
-        .bind(cover_panel_path)
+        .bind(&folder_path)
         .execute(&pool)
-        .await
-        .unwrap();
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&folder_path));
     }
 
-    // return the parent_folders vector back to the frontend
-    parent_folders
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_parent_folders(handle: AppHandle) -> Vec<ParentFolder> {
+pub async fn get_parent_folders(handle: AppHandle) -> AppResult<Vec<ParentFolder>> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     let mut parent_folders: Vec<ParentFolder> = sqlx::query_as("SELECT * FROM parent_folder")
         .fetch_all(&pool)
-        .await
-        .unwrap();
+        .await?;
 
     parent_folders.retain(|folder| !folder.as_child);
     // return the parent_folders vector back to the frontend
-    parent_folders
+    Ok(parent_folders)
 }
 
-#[tauri::command]
-pub async fn update_manga_folders(
-    dir_paths: String,
-    handle: AppHandle,
-    as_child: bool,
-    is_expanded: bool,
-) -> Vec<MangaFolder> {
-    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
-    let parsed_paths = serde_json::from_str::<Vec<String>>(&dir_paths).unwrap();
-
-    // create a MangaFolder vector to return back to the frontend
-    let mut manga_folders: Vec<MangaFolder> = Vec::new();
-
-    for path in parsed_paths {
-        let uuid = uuid::Uuid::new_v4().to_string();
-        // gets the parent, file name, and extension of the path
-        let split_path = split_path_parts(&path);
-        let cover_panel_path = get_manga_folder_cover_panel_path(&path).unwrap_or_default();
-
-        sqlx::query(
-            "INSERT INTO manga_folder
+/// Registers a chapter folder or archive, refreshing its title and ComicInfo if it already exists.
+pub async fn upsert_manga_folder(path: &str, pool: &SqlitePool) -> AppResult<MangaFolder> {
+    let uuid = uuid::Uuid::new_v4().to_string();
+    // gets the parent, file name, and extension of the path
+    let split_path = split_path_parts(path);
+    // opening an archive for its cover and ComicInfo.xml blocks, keep it off the runtime
+    let folder_path = path.to_string();
+    let (cover_panel_path, comic_info) = tokio::task::spawn_blocking(move || {
+        (
+            get_manga_folder_cover_panel_path(&folder_path).unwrap_or_default(),
+            ComicInfo::read(&folder_path),
+        )
+    })
+    .await?;
+    // prefer the ComicInfo.xml title over the folder name when there is one
+    let title = comic_info
+        .as_ref()
+        .and_then(|info| info.display_title())
+        .unwrap_or(split_path.file_name);
+    let parent_id = find_parent_folder_id(path, pool).await?;
+    let root_id = find_library_root_id(path, pool).await?;
+
+    sqlx::query(
+        "INSERT INTO manga_folder
         (
             id,
+            parent_id,
+            root_id,
             title,
             full_path,
             as_child,
             is_expanded,
-            time_spent_reading,
             cover_panel_path,
             created_at,
             updated_at
         )
         VALUES
         (
-            ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?, ?,
             datetime('now', 'localtime'), datetime('now', 'localtime')
         )
         ON CONFLICT (full_path) DO UPDATE SET
+        parent_id = COALESCE(excluded.parent_id, manga_folder.parent_id),
+        root_id = COALESCE(excluded.root_id, manga_folder.root_id),
+        title = excluded.title,
         as_child = excluded.as_child,
-        is_expanded = excluded.is_expanded
+        is_missing = 0
         ",
-        )
-        .bind(uuid)
-        .bind(split_path.file_name)
-        .bind(&path)
-        .bind(as_child)
-        .bind(is_expanded)
-        .bind(0)
-        .bind(cover_panel_path)
-        .execute(&pool)
+    )
+    .bind(uuid)
+    .bind(&parent_id)
+    .bind(root_id)
+    .bind(title)
+    .bind(path)
+    .bind(parent_id.is_some())
+    .bind(false)
+    .bind(cover_panel_path)
+    .execute(pool)
+    .await?;
+
+    let updated_folder: MangaFolder =
+        sqlx::query_as("SELECT * FROM manga_folder WHERE full_path = ?")
+            .bind(path)
+            .fetch_one(pool)
+            .await?;
+
+    if let Some(comic_info) = comic_info {
+        upsert_comic_info(&updated_folder.id, &comic_info, pool).await?;
+    }
+
+    // panels indexed before the chapter was registered, or kept when it was removed, are linked back
+    let panel_prefix = format!("{path}{MAIN_SEPARATOR}");
+    sqlx::query(
+        "UPDATE manga_panel SET folder_id = ?
+        WHERE folder_id IS NULL AND substr(full_path, 1, length(?)) = ?",
+    )
+    .bind(&updated_folder.id)
+    .bind(&panel_prefix)
+    .bind(&panel_prefix)
+    .execute(pool)
+    .await?;
+
+    Ok(updated_folder)
+}
+
+// a parent folder and every parent folder above it, closest first. binds: parent folder id
+pub const PARENT_FOLDER_CHAIN: &str = "WITH RECURSIVE parents AS (
+    SELECT parent_folder.*, 0 AS depth FROM parent_folder WHERE id = ?
+    UNION ALL
+    SELECT parent_folder.*, parents.depth + 1 FROM parent_folder
+    INNER JOIN parents ON parent_folder.id = parents.parent_id
+)";
+
+/// The id of the registered parent folder that directly contains `path`.
+pub async fn find_parent_folder_id(
+    path: &str,
+    pool: &SqlitePool,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM parent_folder WHERE full_path = ?")
+        .bind(split_path_parts(path).parent)
+        .fetch_optional(pool)
         .await
-        .unwrap();
+}
 
-        let updated_folder = sqlx::query_as("SELECT * FROM manga_folder WHERE full_path = ?")
-            .bind(&path)
-            .fetch_one(&pool)
-            .await
-            .unwrap();
+/// The id of the chapter a panel belongs to, either its folder or the archive it is stored in.
+pub async fn find_panel_folder_id(
+    panel_path: &str,
+    pool: &SqlitePool,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM manga_folder WHERE full_path = ?")
+        .bind(panel_folder_path(panel_path))
+        .fetch_optional(pool)
+        .await
+}
 
-        manga_folders.push(updated_folder);
+pub fn panel_folder_path(panel_path: &str) -> String {
+    // entries inside an archive can sit in sub folders of the archive
+    match split_archive_path(panel_path) {
+        Some((archive_path, _)) => archive_path.to_string_lossy().into_owned(),
+        None => split_path_parts(panel_path).parent,
     }
-
-    // return the manga_folders vector back to the frontend
-    manga_folders
 }
 
 pub fn get_parent_folder_cover_panel_path(parent_path: &str) -> Result<String, io::Error> {
@@ -213,11 +304,13 @@ pub fn get_parent_folder_cover_panel_path(parent_path: &str) -> Result<String, i
         let entry = entry?;
         let entry_path = entry.path();
 
-        if entry_path.is_dir() {
-            // First, try to get a cover panel from this subdirectory
+        if entry_path.is_dir() || is_archive(&entry_path) {
+            // First, try to get a cover panel from this subdirectory or archive
             match get_manga_folder_cover_panel_path(&entry_path.to_string_lossy()) {
                 Ok(cover_panel_path) => return Ok(cover_panel_path),
                 Err(e) => match e.kind() {
+                    // archives can't contain other chapters, so move on to the next entry
+                    io::ErrorKind::NotFound if !entry_path.is_dir() => {}
                     // If it's a NotFound error, continue searching other subdirectories
                     io::ErrorKind::NotFound => {
                         // Recursively check subdirectories of this directory
@@ -243,53 +336,72 @@ pub fn get_parent_folder_cover_panel_path(parent_path: &str) -> Result<String, i
 }
 
 pub fn get_manga_folder_cover_panel_path(folder_path: &str) -> Result<String, io::Error> {
-    let file_types = ["jpg", "jpeg", "png", "webp"];
-    let mut manga_panel_paths: Vec<PathBuf> = Vec::new();
+    // works for both plain image folders and .cbz/.zip archives
+    ChapterSource::open(folder_path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Not a manga folder or archive"))?
+        .first_panel_path()
+}
 
-    // Collect valid image file paths as PathBuf
-    for entry in read_dir(folder_path)? {
-        let entry = entry?;
-        let entry_path = entry.path();
+#[tauri::command]
+pub async fn get_manga_panels(folder_path: String) -> AppResult<Vec<PanelEntry>> {
+    let source =
+        ChapterSource::open(&folder_path).ok_or_else(|| AppError::not_found(&folder_path))?;
 
-        if let Some(ext) = entry_path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                if file_types.contains(&ext_str) {
-                    manga_panel_paths.push(entry_path);
-                }
-            }
-        }
-    }
+    source.panels().with_path(&folder_path)
+}
+
+/// The volumes and chapters directly inside a series or volume.
+#[derive(Debug, Serialize)]
+pub struct ChildFolders {
+    pub parent_folders: Vec<ParentFolder>,
+    pub manga_folders: Vec<MangaFolder>,
+}
+
+#[tauri::command]
+pub async fn get_child_folders(parent_path: String, handle: AppHandle) -> AppResult<ChildFolders> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
-    // Sort the paths by the numbers extracted from the file names
-    manga_panel_paths.sort_by_key(|path| {
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .and_then(|name_str| NUMBER_REGEX.captures(name_str))
-            .and_then(|cap| cap.get(1))
-            .and_then(|num| num.as_str().parse::<u32>().ok())
-            .unwrap_or(0) // Default to 0 if no match or parse error
+    let mut parent_folders: Vec<ParentFolder> = sqlx::query_as(
+        "SELECT child.* FROM parent_folder AS child
+        INNER JOIN parent_folder AS parent ON child.parent_id = parent.id
+        WHERE parent.full_path = ?",
+    )
+    .bind(&parent_path)
+    .fetch_all(&pool)
+    .await?;
+
+    let mut manga_folders: Vec<MangaFolder> = sqlx::query_as(
+        "SELECT child.* FROM manga_folder AS child
+        INNER JOIN parent_folder AS parent ON child.parent_id = parent.id
+        WHERE parent.full_path = ?",
+    )
+    .bind(&parent_path)
+    .fetch_all(&pool)
+    .await?;
+
+    parent_folders.sort_by_cached_key(|folder| {
+        ChapterKey::parse(&split_path_parts(&folder.full_path).file_name)
     });
+    sort_chapters(&mut manga_folders, &pool).await?;
 
-    // Return the first panel if available
-    manga_panel_paths
-        .first()
-        .map(|path| path.to_string_lossy().into_owned())
-        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No valid cover panel image found"))
+    Ok(ChildFolders {
+        parent_folders,
+        manga_folders,
+    })
 }
 
 #[tauri::command]
-pub async fn get_manga_folders(handle: AppHandle) -> Vec<MangaFolder> {
+pub async fn get_manga_folders(handle: AppHandle) -> AppResult<Vec<MangaFolder>> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     let mut manga_folders: Vec<MangaFolder> = sqlx::query_as("SELECT * FROM manga_folder")
         .fetch_all(&pool)
-        .await
-        .unwrap();
+        .await?;
 
     manga_folders.retain(|folder| !folder.as_child);
 
     // return the manga_folders vector back to the frontend
-    manga_folders
+    Ok(manga_folders)
 }
 
 #[tauri::command]
@@ -297,8 +409,7 @@ pub async fn update_manga_panel(
     dir_paths: Vec<String>,
     handle: AppHandle,
     is_read: bool,
-    zoom_level: u16,
-) {
+) -> AppResult<()> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     for path in &dir_paths {
@@ -307,11 +418,13 @@ pub async fn update_manga_panel(
         let split_path = split_path_parts(path);
         // get the width and height of the panel image
         let (width, height) = get_panel_image_dimensions(path);
+        let folder_id = find_panel_folder_id(path, &pool).await?;
 
         sqlx::query(
             "INSERT INTO manga_panel
         (
             id,
+            folder_id,
             title,
             full_path,
             is_read,
@@ -323,107 +436,124 @@ pub async fn update_manga_panel(
         )
         VALUES
         (
-            ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?, ?,
             datetime('now', 'localtime'), datetime('now', 'localtime')
         )
         ON CONFLICT (full_path) DO UPDATE SET
+            folder_id = COALESCE(excluded.folder_id, manga_panel.folder_id),
             is_read = excluded.is_read,
             updated_at = datetime('now', 'localtime')",
         )
         .bind(uuid)
+        .bind(folder_id)
         .bind(split_path.file_name)
         .bind(path)
         .bind(is_read)
         .bind(width)
         .bind(height)
-        .bind(zoom_level)
+        .bind(0)
         .execute(&pool)
-        .await
-        .unwrap();
+        .await?;
     }
 
-    // update every panel to match the same zoom level
-    if zoom_level > 0 {
-        sqlx::query("UPDATE manga_panel SET zoom_level = ?")
-            .bind(zoom_level)
-            .execute(&pool)
-            .await
-            .unwrap();
+    if let Some(manga_panel_path) = dir_paths.first() {
+        // update the main manga folder's updated_at
+        sqlx::query(
+            "UPDATE manga_folder SET updated_at = DATETIME('now', 'localtime') WHERE full_path = ?",
+        )
+        .bind(panel_folder_path(manga_panel_path))
+        .execute(&pool)
+        .await?;
     }
 
-    if let Some(manga_folder_path) = dir_paths.first() {
-        if let Some(parent) = Path::new(manga_folder_path).parent() {
-            // update the main manga folder's updated_at
-            sqlx::query(
-                "UPDATE manga_folder SET updated_at = DATETIME('now', 'localtime') WHERE full_path = ?",
-            ).bind(parent.to_string_lossy().to_string())
-            .execute(&pool)
-            .await
-            .unwrap();
-        }
-    }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_manga_panel(path: &str, handle: AppHandle) -> Result<MangaPanel, String> {
+pub async fn get_manga_panel(path: &str, handle: AppHandle) -> AppResult<MangaPanel> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     if path.is_empty() {
-        return Err("`path` is empty #cmd(get_manga_panel)[manga.rs]".to_string());
+        return Err(AppError::InvalidInput("`path` is empty".to_string()));
     }
 
-    let panel: MangaPanel = match sqlx::query_as("SELECT * FROM manga_panel WHERE full_path = ?")
+    let panel: MangaPanel = sqlx::query_as("SELECT * FROM manga_panel WHERE full_path = ?")
         .bind(path)
-        .fetch_one(&pool)
-        .await
-    {
-        Ok(panel) => panel,
-        Err(e) => {
-            return Err(format!(
-                "Error occured querying for Panel `{path}` #cmd(get_manga_panel)[manga.rs]\n{e}"
-            ));
-        }
-    };
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(path))?;
 
     Ok(panel)
 }
 
 #[tauri::command]
-pub async fn delete_folder(_id: String, path: String, all_data: bool, handle: AppHandle) {
+pub async fn delete_folder(
+    id: String,
+    path: String,
+    all_data: bool,
+    handle: AppHandle,
+) -> AppResult<()> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     //println!("Deleting folder: {}", path);
 
-    // delete any folders that contain the main folder module_path!()
-    sqlx::query("DELETE FROM manga_folder WHERE full_path LIKE ? || '%'")
-        .bind(&path)
-        .execute(&pool)
-        .await
-        .unwrap();
+    unwatch_parent_folder(&handle, &path);
 
-    // delete the main folder
-    sqlx::query("DELETE FROM parent_folder where full_path LIKE ? || '%'")
-        .bind(&path)
-        .execute(&pool)
-        .await
-        .unwrap();
+    let mut tx = pool.begin().await?;
 
-    // delete all panels that contain the main folder's module_path!
-    if all_data {
-        sqlx::query("DELETE FROM manga_panel WHERE full_path LIKE ? || '%'")
-            .bind(&path)
-            .execute(&pool)
-            .await
-            .unwrap();
+    if !all_data {
+        // keep the panels and their progress, they are linked again if the folder is re-added
+        sqlx::query(
+            "WITH RECURSIVE parents(id) AS (
+                SELECT id FROM parent_folder WHERE id = ?
+                UNION ALL
+                SELECT parent_folder.id FROM parent_folder
+                INNER JOIN parents ON parent_folder.parent_id = parents.id
+            )
+            UPDATE manga_panel SET folder_id = NULL
+            WHERE folder_id = ?
+            OR folder_id IN (SELECT id FROM manga_folder WHERE parent_id IN parents)",
+        )
+        .bind(&id)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
     }
+
+    // child folders, chapters and their panels go with it through ON DELETE CASCADE
+    sqlx::query("DELETE FROM manga_folder WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM parent_folder WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+    // a folder added on its own is also its library root, otherwise the next rescan brings it back
+    sqlx::query("DELETE FROM library_root WHERE full_path = ?")
+        .bind(&path)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
 }
 
 // helper functions
 pub fn split_path_parts(path: &str) -> PathParts {
     let path = std::path::Path::new(path);
 
-    let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-    let parent = path.parent().unwrap().to_str().unwrap().to_string();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let parent = path
+        .parent()
+        .map(|parent| parent.to_string_lossy().into_owned())
+        .unwrap_or_default();
     let extension = path
         .extension()
         .and_then(|os_string| os_string.to_str())
@@ -437,66 +567,80 @@ pub fn split_path_parts(path: &str) -> PathParts {
 }
 
 pub fn get_panel_image_dimensions(panel_image_path: &str) -> (u16, u16) {
-    match imagesize::size(panel_image_path) {
-        Ok(size) => (size.width as u16, size.height as u16),
-        Err(_) => (0, 0),
+    match panel_image_size(panel_image_path) {
+        Some((width, height)) => (width as u16, height as u16),
+        None => (0, 0),
     }
 }
 
+/// Puts sibling chapters in reading order, the chapter list and next/previous both use it.
+/// ComicInfo volume/number takes priority over the folder names, but only when every chapter
+/// has one, ordering some chapters by ComicInfo and the rest by name isn't a consistent order.
+async fn sort_chapters(manga_folders: &mut [MangaFolder], pool: &SqlitePool) -> AppResult<()> {
+    manga_folders.sort_by_cached_key(|folder| {
+        ChapterKey::parse(&split_path_parts(&folder.full_path).file_name)
+    });
+
+    let mut positions: HashMap<String, (i64, f64)> = HashMap::new();
+    for folder in manga_folders.iter() {
+        if let Some(position) = get_comic_info_by_folder_id(&folder.id, pool)
+            .await?
+            .and_then(|info| info.reading_position())
+        {
+            positions.insert(folder.id.clone(), position);
+        }
+    }
+    if positions.len() == manga_folders.len() {
+        // the sort is stable, so chapters with the same number stay in name order
+        manga_folders.sort_by(|a, b| {
+            let (a_volume, a_number) = positions[&a.id];
+            let (b_volume, b_number) = positions[&b.id];
+            a_volume.cmp(&b_volume).then(a_number.total_cmp(&b_number))
+        });
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_next_or_previous_manga_folder(
     current_folder_path: String,
     is_next: bool,
     handle: AppHandle,
-) -> Option<MangaFolder> {
+) -> AppResult<Option<MangaFolder>> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
-    let parent_path = std::path::Path::new(&current_folder_path)
-        .parent()
-        .unwrap()
-        .to_str()
-        .unwrap();
-
-    let mut manga_folders: Vec<MangaFolder> =
-        sqlx::query_as("SELECT * FROM manga_folder WHERE full_path LIKE ? || '%'")
-            .bind(parent_path)
-            .fetch_all(&pool)
-            .await
-            .unwrap();
+    // chapters in the same parent folder, a folder without a parent only has itself
+    let mut manga_folders: Vec<MangaFolder> = sqlx::query_as(
+        "SELECT sibling.* FROM manga_folder AS current
+        INNER JOIN manga_folder AS sibling
+        ON sibling.parent_id = current.parent_id OR sibling.id = current.id
+        WHERE current.full_path = ?",
+    )
+    .bind(&current_folder_path)
+    .fetch_all(&pool)
+    .await?;
 
-    // sort the manga_folder
-    let re = regex::Regex::new(r"\d+").unwrap();
-
-    manga_folders.sort_by(|a, b| {
-        let a_num: i32 = re
-            .find(&a.title)
-            .and_then(|m| m.as_str().parse().ok())
-            .unwrap_or_default();
-        let b_num: i32 = re
-            .find(&b.title)
-            .and_then(|m| m.as_str().parse().ok())
-            .unwrap_or_default();
-        a_num.cmp(&b_num)
-    });
+    sort_chapters(&mut manga_folders, &pool).await?;
 
     // find the index in the vector where the current folder is
     let index = manga_folders
         .iter()
         .position(|x| x.full_path == current_folder_path)
-        .unwrap();
+        .ok_or_else(|| AppError::not_found(&current_folder_path))?;
 
     // return the next folder in the vector
 
     if is_next {
         if index + 1 >= manga_folders.len() {
-            return None;
+            return Ok(None);
         }
-        Some(manga_folders[index + 1].clone())
+        Ok(Some(manga_folders[index + 1].clone()))
     } else {
         if index == 0 {
-            return None;
+            return Ok(None);
         }
-        Some(manga_folders[index - 1].clone())
+        Ok(Some(manga_folders[index - 1].clone()))
     }
 }
 
@@ -505,85 +649,84 @@ pub async fn update_folder_double_panels(
     folder_path: String,
     double_panels: bool,
     handle: AppHandle,
-) {
+) -> AppResult<()> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     sqlx::query("UPDATE manga_folder SET double_panels = ? WHERE full_path = ?")
         .bind(double_panels)
         .bind(folder_path)
         .execute(&pool)
-        .await
-        .unwrap();
-}
-
-#[tauri::command]
-pub async fn find_last_read_panel(handle: AppHandle, chapter_path: String) -> usize {
-    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+        .await?;
 
-    let panels: Vec<MangaPanel> =
-        sqlx::query_as("SELECT * FROM manga_panel WHERE full_path LIKE ? || '%'")
-            .bind(chapter_path)
-            .fetch_all(&pool)
-            .await
-            .unwrap();
+    Ok(())
+}
 
-    //panels.sort_by(|a, b| a.is_read.cmp(&b.is_read));
+// the furthest read panel of a chapter in reading order, rows come back in scan order
+async fn last_read_panel_path(chapter_path: &str, pool: &SqlitePool) -> AppResult<Option<String>> {
+    let mut panels: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT manga_panel.full_path, manga_panel.is_read FROM manga_panel
+        INNER JOIN manga_folder ON manga_folder.id = manga_panel.folder_id
+        WHERE manga_folder.full_path = ?",
+    )
+    .bind(chapter_path)
+    .fetch_all(pool)
+    .await?;
+
+    panels.sort_by_cached_key(|(path, _)| {
+        (
+            PanelKey::parse(&split_path_parts(path).file_name),
+            path.clone(),
+        )
+    });
 
-    let last = panels.iter().rposition(|x| x.is_read).unwrap_or(0) as usize;
+    Ok(panels
+        .into_iter()
+        .rfind(|(_, is_read)| *is_read)
+        .map(|(path, _)| path))
+}
 
-    //println!("last panel: {:?} at index {}", panels[last].title, last);
+/// The path of the last read panel of a chapter, the reader finds it in `get_manga_panels`.
+#[tauri::command]
+pub async fn find_last_read_panel(
+    handle: AppHandle,
+    chapter_path: String,
+) -> AppResult<Option<String>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
-    last
+    last_read_panel_path(&chapter_path, &pool).await
 }
 
 #[tauri::command]
 pub async fn find_last_read_manga_folder(
     handle: AppHandle,
     paths: Vec<String>,
-) -> Option<(MangaFolder, MangaPanel)> {
+) -> AppResult<Option<(MangaFolder, MangaPanel)>> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
     let folders: Vec<MangaFolder> = query_as("SELECT * FROM manga_folder ORDER BY updated_at DESC")
         .fetch_all(&pool)
-        .await
-        .unwrap();
+        .await?;
 
     //println!("{:#?}", folders);
 
     for folder in folders {
         if paths.contains(&folder.full_path) {
             //println!("last read manga folder: {}", folder.full_path);
-            let last_read_panel: MangaPanel = sqlx::query_as(
-                "SELECT * FROM manga_panel WHERE full_path LIKE ? || '%' ORDER BY updated_at DESC",
+            let last_read_panel: Option<MangaPanel> = sqlx::query_as(
+                "SELECT * FROM manga_panel WHERE folder_id = ? ORDER BY updated_at DESC",
             )
-            .bind(&folder.full_path)
-            .fetch_one(&pool)
-            .await
-            .unwrap();
-            return Some((folder, last_read_panel));
+            .bind(&folder.id)
+            .fetch_optional(&pool)
+            .await?;
+            // a folder that was never opened has no panels yet
+            return Ok(last_read_panel.map(|panel| (folder, panel)));
         }
     }
 
-    None
-}
-
-#[tauri::command]
-pub async fn update_folder_time_spent_reading(
-    folder_path: String,
-    time_spent_reading: u32,
-    handle: AppHandle,
-) {
-    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
-
-    sqlx::query("UPDATE manga_folder SET time_spent_reading = time_spent_reading + ?, updated_at = datetime('now', 'localtime') WHERE full_path = ?")
-        .bind(time_spent_reading)
-        .bind(folder_path)
-        .execute(&pool)
-        .await
-        .unwrap();
+    Ok(None)
 }
 
 #[tauri::command]
-pub async fn set_folder_read(path: String, handle: AppHandle) {
+pub async fn set_folder_read(path: String, handle: AppHandle) -> AppResult<()> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     //println!("setting {} as read", path);
@@ -591,17 +734,81 @@ pub async fn set_folder_read(path: String, handle: AppHandle) {
     sqlx::query("UPDATE manga_folder SET is_read = true WHERE full_path = ?")
         .bind(path)
         .execute(&pool)
-        .await
-        .unwrap();
+        .await?;
+
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn set_folder_unread(path: String, handle: AppHandle) {
+pub async fn set_folder_unread(path: String, handle: AppHandle) -> AppResult<()> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     sqlx::query("UPDATE manga_folder SET is_read = false WHERE full_path = ?")
         .bind(path)
         .execute(&pool)
-        .await
-        .unwrap();
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn last_read_panel_follows_reading_order() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query("INSERT INTO manga_folder (id, title, full_path) VALUES ('chapter', 'ch1', '/manga/ch1')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        // inserted in the order a directory listing might give them
+        let panels = [
+            ("10.png", true),
+            ("2.png", true),
+            ("1.png", true),
+            ("11.png", false),
+        ];
+        for (position, (name, is_read)) in panels.into_iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO manga_panel (id, folder_id, title, full_path, is_read)
+                VALUES (?, 'chapter', ?, ?, ?)",
+            )
+            .bind(position.to_string())
+            .bind(name)
+            .bind(format!("/manga/ch1/{name}"))
+            .bind(is_read)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(
+            last_read_panel_path("/manga/ch1", &pool).await.unwrap(),
+            Some("/manga/ch1/10.png".to_string()),
+            "10 comes after 2 even though it was inserted first"
+        );
+        assert_eq!(
+            last_read_panel_path("/manga/ch2", &pool).await.unwrap(),
+            None,
+            "an unknown chapter has nothing read"
+        );
+
+        sqlx::query("UPDATE manga_panel SET is_read = 0")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            last_read_panel_path("/manga/ch1", &pool).await.unwrap(),
+            None,
+            "an unread chapter starts at the first panel"
+        );
+    }
 }
@@ -1,94 +1,99 @@
+#[cfg(any(target_os = "windows", target_os = "macos"))]
 use std::process::Command;
 
-use std::sync::LazyLock;
-
-use regex::Regex;
-use sysinfo::System;
-
-pub static NUMBER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d+)\D*$").unwrap());
+use crate::error::AppResult;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::error::PathContext;
 
 #[tauri::command]
-pub fn show_in_folder(path: String) {
+pub async fn show_in_folder(path: String) -> AppResult<()> {
     #[cfg(target_os = "windows")]
     {
         Command::new("explorer")
             .args(["/select,", &path]) // The comma after select is not a typo
             .spawn()
-            .unwrap();
+            .with_path(&path)?;
     }
 
-    // #[cfg(target_os = "linux")]
-    // {
-    //     if path.contains(",") {
-    //         // see https://gitlab.freedesktop.org/dbus/dbus/-/issues/76
-    //         let new_path = match metadata(&path).unwrap().is_dir() {
-    //             true => path,
-    //             false => {
-    //                 let mut path2 = PathBuf::from(path);
-    //                 path2.pop();
-    //                 path2.into_os_string().into_string().unwrap()
-    //             }
-    //         };
-    //         Command::new("xdg-open").arg(&new_path).spawn().unwrap();
-    //     } else {
-    //         if let Ok(Fork::Child) = daemon(false, false) {
-    //             Command::new("dbus-send")
-    //                 .args([
-    //                     "--session",
-    //                     "--dest=org.freedesktop.FileManager1",
-    //                     "--type=method_call",
-    //                     "/org/freedesktop/FileManager1",
-    //                     "org.freedesktop.FileManager1.ShowItems",
-    //                     format!("array:string:\"file://{path}\"").as_str(),
-    //                     "string:\"\"",
-    //                 ])
-    //                 .spawn()
-    //                 .unwrap();
-    //         }
-    //     }
-    // }
-    //
+    #[cfg(target_os = "linux")]
+    {
+        // not every file manager implements FileManager1, those that don't just open the folder
+        if let Err(e) = linux::show_items(&path).await {
+            eprintln!("Error Showing In File Manager: {} -> {}", path, e);
+            linux::open_parent(&path).await?;
+        }
+    }
 
     #[cfg(target_os = "macos")]
     {
-        Command::new("open").args(["-R", &path]).spawn().unwrap();
+        Command::new("open")
+            .args(["-R", &path])
+            .spawn()
+            .with_path(&path)?;
     }
+
+    Ok(())
 }
 
-pub fn close_open_instance() -> bool {
-    let mut sys = System::new_all();
-    sys.refresh_all();
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{io, path::Path};
 
-    // Collect and sort processes matching "mpv-shelf"
-    let mut manga_shelf_processes: Vec<_> = sys
-        .processes()
-        .iter()
-        .filter(|(_pid, process)| {
-            process
-                .name()
-                .to_string_lossy()
-                .to_lowercase()
-                .contains("manga")
-        })
-        .collect();
+    use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 
-    manga_shelf_processes.sort_by_key(|(pid, _process)| pid.as_u32());
+    use crate::error::{AppError, AppResult, PathContext};
 
-    // Get the current process ID
-    let this_proc_id = std::process::id();
+    // everything a file uri can't hold as is, `/` stays so the path keeps its shape
+    const URI_PATH: &AsciiSet = &CONTROLS
+        .add(b' ')
+        .add(b'"')
+        .add(b'#')
+        .add(b'%')
+        .add(b'<')
+        .add(b'>')
+        .add(b'?')
+        .add(b'[')
+        .add(b']')
+        .add(b'`')
+        .add(b'{')
+        .add(b'}');
 
-    if manga_shelf_processes.len() > 1 {
-        for (pid, _process) in manga_shelf_processes {
-            // Skip the current process
-            if pid.as_u32() == this_proc_id {
-                continue;
-            }
-            // Attempt to kill the other instances
-            if let Some(proc) = sys.process(*pid) {
-                proc.kill();
-            }
-        }
+    /// Asks the file manager to open the containing folder with `path` selected.
+    pub async fn show_items(path: &str) -> zbus::Result<()> {
+        let uri = format!("file://{}", utf8_percent_encode(path, URI_PATH));
+        let connection = zbus::Connection::session().await?;
+        connection
+            .call_method(
+                Some("org.freedesktop.FileManager1"),
+                "/org/freedesktop/FileManager1",
+                Some("org.freedesktop.FileManager1"),
+                "ShowItems",
+                // the second argument is a startup id, empty lets the file manager pick one
+                &(vec![uri.as_str()], ""),
+            )
+            .await?;
+
+        Ok(())
     }
 
-    true
+    pub async fn open_parent(path: &str) -> AppResult<()> {
+        let parent = Path::new(path)
+            .parent()
+            .ok_or_else(|| AppError::not_found(path))?;
+        let parent = parent.to_string_lossy();
+
+        let status = tokio::process::Command::new("xdg-open")
+            .arg(parent.as_ref())
+            .status()
+            .await
+            .with_path(&parent)?;
+        if !status.success() {
+            return Err(AppError::io(
+                io::Error::other(format!("xdg-open exited with {}", status)),
+                parent,
+            ));
+        }
+
+        Ok(())
+    }
 }
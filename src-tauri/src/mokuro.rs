@@ -0,0 +1,543 @@
+use std::{
+    collections::HashMap,
+    fs::{metadata, read_dir, read_to_string},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppResult, PathContext};
+use crate::source::ChapterSource;
+
+/// OCR output written by mokuro next to a volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sidecar {
+    // `<volume>.mokuro`, every page of the volume in one file
+    Volume(PathBuf),
+    // `_ocr/<volume>/<page>.json`, written by mokuro before 0.2
+    Pages(PathBuf),
+}
+
+// one page of a `.mokuro` file, or a whole `_ocr` page file
+#[derive(Debug, Deserialize)]
+struct MokuroPage {
+    img_width: u32,
+    img_height: u32,
+    #[serde(default)]
+    img_path: Option<String>,
+    #[serde(default)]
+    blocks: Vec<MokuroBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MokuroVolume {
+    pages: Vec<MokuroPage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MokuroBlock {
+    // x1, y1, x2, y2
+    #[serde(rename = "box")]
+    bbox: [f64; 4],
+    #[serde(default)]
+    vertical: bool,
+    #[serde(default)]
+    font_size: Option<f64>,
+    #[serde(default)]
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextBlock {
+    pub id: String,
+    pub panel_id: String,
+    pub position: u32,
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub is_vertical: bool,
+    pub font_size: Option<f64>,
+    pub lines: Vec<String>,
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+// `lines` is stored as a json array
+#[derive(sqlx::FromRow)]
+struct TextBlockRow {
+    id: String,
+    panel_id: String,
+    position: u32,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    is_vertical: bool,
+    font_size: Option<f64>,
+    lines: String,
+    image_width: u32,
+    image_height: u32,
+}
+
+impl From<TextBlockRow> for TextBlock {
+    fn from(row: TextBlockRow) -> Self {
+        Self {
+            id: row.id,
+            panel_id: row.panel_id,
+            position: row.position,
+            x1: row.x1,
+            y1: row.y1,
+            x2: row.x2,
+            y2: row.y2,
+            is_vertical: row.is_vertical,
+            font_size: row.font_size,
+            lines: serde_json::from_str(&row.lines).unwrap_or_default(),
+            image_width: row.image_width,
+            image_height: row.image_height,
+        }
+    }
+}
+
+impl Sidecar {
+    /// Looks for OCR output of the chapter at `folder_path`, a `.mokuro` file wins over `_ocr`.
+    pub fn find(folder_path: &Path) -> Option<Self> {
+        let parent = folder_path.parent()?;
+        let is_dir = folder_path.is_dir();
+        // mokuro names the sidecar of `Vol.1.cbz` after the archive without its extension
+        let volume_name = match is_dir {
+            true => folder_path.file_name()?,
+            false => folder_path.file_stem()?,
+        };
+
+        let mut volume_file = volume_name.to_os_string();
+        volume_file.push(".mokuro");
+        let volume_path = parent.join(volume_file);
+        if volume_path.is_file() {
+            return Some(Self::Volume(volume_path));
+        }
+
+        let pages_path = parent.join("_ocr").join(volume_name);
+        if is_dir && pages_path.is_dir() {
+            return Some(Self::Pages(pages_path));
+        }
+
+        None
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Volume(path) | Self::Pages(path) => path,
+        }
+    }
+
+    /// Seconds since the epoch of the last change, the newest page file for `_ocr` folders.
+    fn modified_at(&self) -> i64 {
+        let modified = |path: &Path| {
+            metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs() as i64)
+        };
+
+        match self {
+            Self::Volume(path) => modified(path),
+            Self::Pages(dir) => read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| modified(&entry.path()))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .unwrap_or(0),
+        }
+    }
+
+    /// Pairs every page of OCR output with the panel it was read from.
+    /// Pages without a matching panel are skipped.
+    fn read_pages(
+        &self,
+        folder_path: &Path,
+        panel_paths: &[String],
+    ) -> AppResult<Vec<(String, MokuroPage)>> {
+        match self {
+            Self::Volume(path) => {
+                let path_str = path.to_string_lossy();
+                let json = read_to_string(path).with_path(&path_str)?;
+                let volume: MokuroVolume = serde_json::from_str(&json)?;
+
+                let by_path: HashMap<PathBuf, &String> = panel_paths
+                    .iter()
+                    .map(|panel_path| (PathBuf::from(panel_path), panel_path))
+                    .collect();
+                let by_name: HashMap<String, &String> = panel_paths
+                    .iter()
+                    .filter_map(|panel_path| {
+                        let name = Path::new(panel_path).file_name()?.to_string_lossy();
+                        Some((name.into_owned(), panel_path))
+                    })
+                    .collect();
+
+                let mut pages = Vec::new();
+                for page in volume.pages {
+                    let Some(img_path) = page.img_path.as_deref() else {
+                        continue;
+                    };
+                    // `img_path` is relative to the volume, fall back to the file name
+                    // for volumes that were flattened or repacked after mokuro ran
+                    let panel_path = by_path.get(&folder_path.join(img_path)).or_else(|| {
+                        let name = Path::new(img_path).file_name()?.to_string_lossy();
+                        by_name.get(name.as_ref())
+                    });
+                    match panel_path {
+                        Some(panel_path) => pages.push((panel_path.to_string(), page)),
+                        None => {
+                            eprintln!("Error Matching Mokuro Page: {} -> {}", path_str, img_path)
+                        }
+                    }
+                }
+                Ok(pages)
+            }
+            Self::Pages(dir) => {
+                let mut pages = Vec::new();
+                for panel_path in panel_paths {
+                    let Ok(relative) = Path::new(panel_path).strip_prefix(folder_path) else {
+                        continue;
+                    };
+                    let page_path = dir.join(relative).with_extension("json");
+                    let Ok(json) = read_to_string(&page_path) else {
+                        continue;
+                    };
+                    // one broken page shouldn't lose the text of the whole volume
+                    match serde_json::from_str(&json) {
+                        Ok(page) => pages.push((panel_path.clone(), page)),
+                        Err(e) => eprintln!(
+                            "Error Parsing Mokuro Page: {} -> {}",
+                            page_path.to_string_lossy(),
+                            e
+                        ),
+                    }
+                }
+                Ok(pages)
+            }
+        }
+    }
+}
+
+/// Imports the mokuro OCR of a registered chapter into `text_block`, replacing what an earlier
+/// import left. Returns `false` when there's nothing new, including when the sidecar is unchanged.
+pub async fn import_text_blocks(folder_path: &str, pool: &SqlitePool) -> AppResult<bool> {
+    let Some(folder_id): Option<String> =
+        sqlx::query_scalar("SELECT id FROM manga_folder WHERE full_path = ?")
+            .bind(folder_path)
+            .fetch_optional(pool)
+            .await?
+    else {
+        return Ok(false);
+    };
+
+    let path = folder_path.to_string();
+    let found = tokio::task::spawn_blocking(move || {
+        Sidecar::find(Path::new(&path)).map(|sidecar| {
+            let modified_at = sidecar.modified_at();
+            (sidecar, modified_at)
+        })
+    })
+    .await?;
+
+    let Some((sidecar, modified_at)) = found else {
+        // the sidecar was deleted, so its text goes with it
+        let removed = sqlx::query("DELETE FROM text_source WHERE manga_folder_id = ?")
+            .bind(&folder_id)
+            .execute(pool)
+            .await?;
+        return Ok(removed.rows_affected() > 0);
+    };
+    let sidecar_path = sidecar.path().to_string_lossy().into_owned();
+
+    let is_unchanged: bool = sqlx::query_scalar(
+        "SELECT EXISTS (
+            SELECT 1 FROM text_source
+            WHERE manga_folder_id = ? AND sidecar_path = ? AND modified_at = ?
+        )",
+    )
+    .bind(&folder_id)
+    .bind(&sidecar_path)
+    .bind(modified_at)
+    .fetch_one(pool)
+    .await?;
+    if is_unchanged {
+        return Ok(false);
+    }
+
+    // listing the panels and parsing the sidecar are blocking io
+    let path = folder_path.to_string();
+    let pages = tokio::task::spawn_blocking(move || {
+        let folder_path = Path::new(&path);
+        let panel_paths = match ChapterSource::open(folder_path) {
+            Some(source) => source.panel_paths().with_path(&path)?,
+            None => Vec::new(),
+        };
+        sidecar.read_pages(folder_path, &panel_paths)
+    })
+    .await??;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO text_source (id, manga_folder_id, sidecar_path, modified_at, imported_at)
+        VALUES (?, ?, ?, ?, datetime('now', 'localtime'))
+        ON CONFLICT (manga_folder_id) DO UPDATE SET
+        sidecar_path = excluded.sidecar_path,
+        modified_at = excluded.modified_at,
+        imported_at = excluded.imported_at",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&folder_id)
+    .bind(&sidecar_path)
+    .bind(modified_at)
+    .execute(&mut *tx)
+    .await?;
+
+    let source_id: String =
+        sqlx::query_scalar("SELECT id FROM text_source WHERE manga_folder_id = ?")
+            .bind(&folder_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    sqlx::query("DELETE FROM text_block WHERE source_id = ?")
+        .bind(&source_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for (panel_path, page) in &pages {
+        for (position, block) in page.blocks.iter().enumerate() {
+            let [x1, y1, x2, y2] = block.bbox;
+
+            // panels that were never indexed are skipped by the select
+            sqlx::query(
+                "INSERT INTO text_block (
+                id,
+                source_id,
+                panel_id,
+                position,
+                x1,
+                y1,
+                x2,
+                y2,
+                is_vertical,
+                font_size,
+                lines,
+                image_width,
+                image_height
+                )
+                SELECT ?, ?, id, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+                FROM manga_panel WHERE full_path = ?",
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(&source_id)
+            .bind(position as u32)
+            .bind(x1)
+            .bind(y1)
+            .bind(x2)
+            .bind(y2)
+            .bind(block.vertical)
+            .bind(block.font_size)
+            .bind(serde_json::to_string(&block.lines)?)
+            .bind(page.img_width)
+            .bind(page.img_height)
+            .bind(panel_path)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// The OCR text of a panel in reading order, empty when the chapter has no mokuro sidecar.
+#[tauri::command]
+pub async fn get_panel_text_blocks(
+    panel_path: String,
+    handle: AppHandle,
+) -> AppResult<Vec<TextBlock>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let rows: Vec<TextBlockRow> = sqlx::query_as(
+        "SELECT text_block.* FROM text_block
+        INNER JOIN manga_panel ON manga_panel.id = text_block.panel_id
+        WHERE manga_panel.full_path = ?
+        ORDER BY text_block.position",
+    )
+    .bind(panel_path)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(rows.into_iter().map(TextBlock::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    // trimmed from mokuro 0.2 output, the fields the import doesn't read are left in
+    const VOLUME_JSON: &str = r#"{
+        "version": "0.2.1",
+        "title": "Series",
+        "volume": "Vol1",
+        "pages": [
+            {
+                "version": "0.2.1",
+                "img_width": 1000,
+                "img_height": 1500,
+                "img_path": "001.jpg",
+                "blocks": [
+                    {
+                        "box": [10, 20, 110, 320],
+                        "vertical": true,
+                        "font_size": 32.0,
+                        "lines_coords": [],
+                        "lines": ["こんにちは", "世界"]
+                    }
+                ]
+            },
+            { "img_width": 1000, "img_height": 1500, "img_path": "sub/002.jpg", "blocks": [] },
+            { "img_width": 1000, "img_height": 1500, "img_path": "old/003.jpg" },
+            { "img_width": 1000, "img_height": 1500, "img_path": "missing.jpg" },
+            { "img_width": 1000, "img_height": 1500 }
+        ]
+    }"#;
+
+    const PAGE_JSON: &str = r#"{
+        "img_width": 800,
+        "img_height": 1200,
+        "blocks": [{ "box": [1, 2, 3, 4], "lines": ["テスト"] }]
+    }"#;
+
+    fn temp_library() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mokuro-{}", uuid::Uuid::new_v4()));
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add_file(library: &Path, relative: &str, contents: &str) -> PathBuf {
+        let path = library.join(relative);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(&path, contents).unwrap();
+        path
+    }
+
+    fn add_dir(library: &Path, relative: &str) -> PathBuf {
+        let path = library.join(relative);
+        create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn panel_paths(folder: &Path, names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|name| folder.join(name).to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn finds_sidecars() {
+        let library = temp_library();
+        // a folder with both, the `.mokuro` file wins
+        let both = add_dir(&library, "Both");
+        add_dir(&library, "_ocr/Both");
+        let both_volume = add_file(&library, "Both.mokuro", VOLUME_JSON);
+        // only the old `_ocr` folder
+        let old = add_dir(&library, "Old");
+        let old_pages = add_dir(&library, "_ocr/Old");
+        // an archive is named by its stem, dots included
+        let archive = add_file(&library, "Vol.1.cbz", "");
+        let archive_volume = add_file(&library, "Vol.1.mokuro", VOLUME_JSON);
+        // `_ocr` was never written for archives
+        let old_archive = add_file(&library, "Old2.cbz", "");
+        add_dir(&library, "_ocr/Old2");
+        // another volume's sidecar doesn't count
+        let bare = add_dir(&library, "Bare");
+        add_file(&library, "Bare2.mokuro", VOLUME_JSON);
+
+        let cases = [
+            (
+                &both,
+                Some(Sidecar::Volume(both_volume)),
+                "mokuro over _ocr",
+            ),
+            (&old, Some(Sidecar::Pages(old_pages)), "_ocr folder"),
+            (
+                &archive,
+                Some(Sidecar::Volume(archive_volume)),
+                "archive stem",
+            ),
+            (&old_archive, None, "archive _ocr"),
+            (&bare, None, "no sidecar"),
+        ];
+        for (folder, expected, message) in cases {
+            assert_eq!(Sidecar::find(folder), expected, "{message}");
+        }
+
+        remove_dir_all(&library).unwrap();
+    }
+
+    #[test]
+    fn matches_volume_pages_by_path_then_name() {
+        let library = temp_library();
+        let folder = add_dir(&library, "Vol1");
+        let sidecar = Sidecar::Volume(add_file(&library, "Vol1.mokuro", VOLUME_JSON));
+        let panels = panel_paths(&folder, &["001.jpg", "sub/002.jpg", "003.jpg", "004.jpg"]);
+
+        let pages = sidecar.read_pages(&folder, &panels).unwrap();
+        let matched: Vec<&String> = pages.iter().map(|(path, _)| path).collect();
+        // `old/003.jpg` only matches by file name, `missing.jpg` and the page without a path
+        // are skipped
+        assert_eq!(matched, [&panels[0], &panels[1], &panels[2]]);
+
+        let (_, first) = &pages[0];
+        assert_eq!((first.img_width, first.img_height), (1000, 1500));
+        assert_eq!(first.blocks.len(), 1);
+        assert_eq!(first.blocks[0].bbox, [10.0, 20.0, 110.0, 320.0]);
+        assert!(first.blocks[0].vertical);
+        assert_eq!(first.blocks[0].font_size, Some(32.0));
+        assert_eq!(first.blocks[0].lines, ["こんにちは", "世界"]);
+        assert!(pages[2].1.blocks.is_empty(), "blocks default to none");
+
+        // a broken volume file fails the whole import
+        let broken = Sidecar::Volume(add_file(&library, "Vol1.mokuro", "{ \"pages\": ["));
+        assert!(broken.read_pages(&folder, &panels).is_err());
+
+        remove_dir_all(&library).unwrap();
+    }
+
+    #[test]
+    fn matches_ocr_pages_by_relative_path() {
+        let library = temp_library();
+        let folder = add_dir(&library, "Vol1");
+        let pages_dir = add_dir(&library, "_ocr/Vol1");
+        add_file(&library, "_ocr/Vol1/001.json", PAGE_JSON);
+        add_file(&library, "_ocr/Vol1/sub/002.json", PAGE_JSON);
+        // one broken page doesn't lose the others
+        add_file(&library, "_ocr/Vol1/003.json", "not json");
+        let sidecar = Sidecar::Pages(pages_dir);
+        let panels = panel_paths(&folder, &["001.png", "sub/002.jpg", "003.jpg", "004.jpg"]);
+
+        let pages = sidecar.read_pages(&folder, &panels).unwrap();
+        let matched: Vec<&String> = pages.iter().map(|(path, _)| path).collect();
+        assert_eq!(matched, [&panels[0], &panels[1]]);
+        assert_eq!((pages[0].1.img_width, pages[0].1.img_height), (800, 1200));
+        assert_eq!(pages[1].1.blocks[0].lines, ["テスト"]);
+
+        remove_dir_all(&library).unwrap();
+    }
+}
@@ -0,0 +1,547 @@
+use std::{cmp::Ordering, iter::Peekable, ops::Range, str::Chars, sync::LazyLock};
+
+use regex::{Match, Regex};
+
+// markers must start a word, so `Revolution 5` has no volume and `010v2` is not volume 2
+static VOLUME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:^|[^a-z0-9])(?:volume|vol|v)\.?\s*(\d+(?:\.\d+)?)").unwrap()
+});
+static CHAPTER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:(?:^|[^a-z0-9])(?:chapter|chap|ch|c|episode|ep)\.?\s*|#\s*)(\d+(?:\.\d+)?)")
+        .unwrap()
+});
+// a release version glued to the number, as in `010v2`
+static VERSION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\dv(\d+)").unwrap());
+static PAGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:^|[^a-z0-9])(?:page|pg|p)\.?\s*(\d+)").unwrap());
+static EXTRA_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:^|[^a-z])(?:extras?|omake|special|bonus|side[\s_-]?story|epilogue|afterword)(?:[^a-z]|$)",
+    )
+    .unwrap()
+});
+static PROLOGUE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:^|[^a-z])(?:prologue|preface)(?:[^a-z]|$)").unwrap());
+// release groups, years and resolutions are tagged in brackets and never hold the chapter
+static BRACKETS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[[^\]]*\]|\([^)]*\)|\{[^}]*\}").unwrap());
+static NUMBER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+(?:\.\d+)?").unwrap());
+static ARCHIVE_EXTENSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\.(?:cbz|zip|cbr|rar|cb7|7z)$").unwrap());
+static IMAGE_EXTENSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\.(?:jpe?g|png|webp|gif|avif)$").unwrap());
+
+/// A volume, chapter or page number such as `10`, `10.5` or `10b`.
+/// Fractions compare as decimals, so `10.15` comes before `10.5`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Number {
+    whole: u64,
+    // digits after the point without trailing zeros, compared as text
+    fraction: String,
+    suffix: Option<char>,
+}
+
+impl Number {
+    /// Parses the number at `found`, a single letter right after it is a sub-chapter like `10a`.
+    fn parse(name: &str, found: Match) -> Self {
+        let (whole, fraction) = found
+            .as_str()
+            .split_once('.')
+            .unwrap_or((found.as_str(), ""));
+
+        let mut rest = name[found.end()..].chars();
+        let suffix = match (rest.next(), rest.next()) {
+            (Some(letter), next)
+                if letter.is_ascii_alphabetic() && !next.is_some_and(|c| c.is_alphanumeric()) =>
+            {
+                Some(letter.to_ascii_lowercase())
+            }
+            _ => None,
+        };
+
+        Self {
+            // names with absurdly long numbers still sort after every real one
+            whole: whole.parse().unwrap_or(u64::MAX),
+            fraction: fraction.trim_end_matches('0').to_string(),
+            suffix,
+        }
+    }
+}
+
+/// Where a name falls when it has no number for a level, unnumbered names sort last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Position {
+    Numbered(Number),
+    Unnumbered,
+}
+
+impl From<Option<Number>> for Position {
+    fn from(number: Option<Number>) -> Self {
+        number.map_or(Self::Unnumbered, Self::Numbered)
+    }
+}
+
+/// The part of a volume a chapter belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+    Prologue,
+    Chapter(Number),
+    Unnumbered,
+    Extra(Option<Number>),
+}
+
+/// The reading order of a chapter folder or archive, parsed from its name.
+/// Chapters without a volume come after every volume, they are usually newer than the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterKey {
+    volume: Position,
+    section: Section,
+    name: String,
+}
+
+impl ChapterKey {
+    pub fn parse(name: &str) -> Self {
+        let stem = ARCHIVE_EXTENSION_REGEX.replace(name, "");
+        let mut parsed = ParsedName::new(&stem);
+
+        parsed.take(&VERSION_REGEX);
+        let volume = parsed.take(&VOLUME_REGEX);
+        let chapter = parsed.take(&CHAPTER_REGEX);
+
+        let section = match chapter {
+            Some(chapter) => Section::Chapter(chapter),
+            None if EXTRA_REGEX.is_match(&stem) => Section::Extra(parsed.last_number()),
+            None if PROLOGUE_REGEX.is_match(&stem) => Section::Prologue,
+            None => parsed
+                .last_number()
+                .map_or(Section::Unnumbered, Section::Chapter),
+        };
+
+        Self {
+            volume: volume.into(),
+            section,
+            name: name.to_string(),
+        }
+    }
+
+    /// Whether the name has a volume number, like `Vol.02` or `v2 Extras`.
+    pub fn has_volume(&self) -> bool {
+        matches!(self.volume, Position::Numbered(_))
+    }
+}
+
+impl Ord for ChapterKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.volume
+            .cmp(&other.volume)
+            .then_with(|| self.section.cmp(&other.section))
+            .then_with(|| natural_cmp(&self.name, &other.name))
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
+impl PartialOrd for ChapterKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The reading order of a panel, parsed from its file name.
+/// Covers come first, then panels by volume, chapter and page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanelKey {
+    is_cover: bool,
+    volume: Position,
+    chapter: Position,
+    page: Position,
+    name: String,
+}
+
+impl PanelKey {
+    pub fn parse(name: &str) -> Self {
+        let stem = IMAGE_EXTENSION_REGEX.replace(name, "");
+        let mut parsed = ParsedName::new(&stem);
+
+        parsed.take(&VERSION_REGEX);
+        let volume = parsed.take(&VOLUME_REGEX);
+        let chapter = parsed.take(&CHAPTER_REGEX);
+        // without a page marker the last number left is the page
+        let page = parsed.take(&PAGE_REGEX).or_else(|| parsed.last_number());
+
+        Self {
+            is_cover: stem.to_lowercase().starts_with("cover"),
+            volume: volume.into(),
+            chapter: chapter.into(),
+            page: page.into(),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Ord for PanelKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .is_cover
+            .cmp(&self.is_cover)
+            .then_with(|| self.volume.cmp(&other.volume))
+            .then_with(|| self.chapter.cmp(&other.chapter))
+            .then_with(|| self.page.cmp(&other.page))
+            .then_with(|| natural_cmp(&self.name, &other.name))
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+
+impl PartialOrd for PanelKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A name with the parts already read blanked out, so a number is only used once.
+struct ParsedName<'a> {
+    name: &'a str,
+    used: Vec<Range<usize>>,
+}
+
+impl<'a> ParsedName<'a> {
+    fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            used: Vec::new(),
+        }
+    }
+
+    /// The number captured by the first match of `marker` that isn't used yet.
+    fn take(&mut self, marker: &Regex) -> Option<Number> {
+        let found = marker
+            .captures_iter(self.name)
+            .filter_map(|captures| captures.get(1))
+            .find(|found| !self.is_used(found.range()))?;
+        self.used.push(found.range());
+        Some(Number::parse(self.name, found))
+    }
+
+    /// The last number no marker claimed, numbers in brackets are only used when there are no others.
+    fn last_number(&self) -> Option<Number> {
+        let brackets: Vec<Range<usize>> = BRACKETS_REGEX
+            .find_iter(self.name)
+            .map(|found| found.range())
+            .collect();
+        let (bracketed, plain): (Vec<Match>, Vec<Match>) = NUMBER_REGEX
+            .find_iter(self.name)
+            .filter(|found| !self.is_used(found.range()))
+            .partition(|found| {
+                brackets
+                    .iter()
+                    .any(|bracket| bracket.contains(&found.start()))
+            });
+
+        plain
+            .last()
+            .or(bracketed.last())
+            .map(|found| Number::parse(self.name, *found))
+    }
+
+    fn is_used(&self, range: Range<usize>) -> bool {
+        self.used
+            .iter()
+            .any(|used| used.start < range.end && range.start < used.end)
+    }
+}
+
+/// Compares two names the way a person would, so `2.jpg` comes before `10.jpg`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let a_num = take_number(&mut a_chars);
+                let b_num = take_number(&mut b_chars);
+                // compare by length first so huge numbers don't overflow
+                let ordering = a_num
+                    .len()
+                    .cmp(&b_num.len())
+                    .then_with(|| a_num.cmp(&b_num));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(a_char), Some(b_char)) => {
+                let ordering = a_char.to_lowercase().cmp(b_char.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        number.push(c);
+    }
+    // leading zeros don't change the value
+    let trimmed = number.trim_start_matches('0');
+    trimmed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every group is listed in reading order
+    const CHAPTER_CORPUS: &[&[&str]] = &[
+        &[
+            "Vol.01 Ch.001",
+            "Vol.01 Ch.002",
+            "Vol.01 Ch.002.5",
+            "Vol.01 Ch.010",
+            "Vol.02 Ch.010.5",
+            "Vol.02 Ch.011",
+            "Vol.10 Ch.100",
+        ],
+        &[
+            "c001 (v01)",
+            "c002 (v01)",
+            "c010 (v02)",
+            "c011 (v02)",
+            "c100 (v10)",
+        ],
+        &[
+            "Berserk v01 c001",
+            "Berserk v01 c002",
+            "Berserk v02 c010",
+            "Berserk v10 c100",
+        ],
+        &[
+            "Chapter 1",
+            "Chapter 2",
+            "Chapter 9",
+            "Chapter 10",
+            "Chapter 100",
+        ],
+        &[
+            "Ch. 1", "Ch. 1.1", "Ch. 1.15", "Ch. 1.2", "Ch. 1.5", "Ch. 2",
+        ],
+        &["Ch.10", "Ch.10a", "Ch.10b", "Ch.10.5", "Ch.11"],
+        &["Episode 1", "Episode 2", "Ep. 12", "Episode 100"],
+        &["Title #1", "Title #2", "Title #10"],
+        &[
+            "20th Century Boys 001",
+            "20th Century Boys 002",
+            "20th Century Boys 010",
+            "20th Century Boys 100",
+        ],
+        &["Kaiji 2 009", "Kaiji 2 010", "Kaiji 2 011"],
+        &[
+            "[Group] Berserk 009 [1080p]",
+            "[Group] Berserk 010 (2019) [1080p]",
+            "[Other Group] Berserk 011 [720p]",
+        ],
+        &["Title 009", "Title 010v2", "Title 011"],
+        &["Prologue", "Chapter 1", "Chapter 2", "Epilogue"],
+        &["Chapter 1", "Chapter 2", "Chapter 10", "Extra", "Extra 2"],
+        &["Chapter 1", "Chapter 2", "Oneshot"],
+        &[
+            "Vol.01 Ch.001",
+            "Vol.01 Ch.002",
+            "Vol.01 Omake",
+            "Vol.02 Ch.003",
+            "Vol.02 Side Story",
+            "Vol.03 Ch.005",
+            "Vol.03 Bonus 1",
+            "Vol.03 Bonus 2",
+        ],
+        &["Vol.01", "Vol.02", "Vol.03 Extra", "Ch.025", "Ch.026"],
+        &[
+            "Title v01.cbz",
+            "Title v02.cbz",
+            "Title v02.5.cbz",
+            "Title v10.cbz",
+        ],
+        &[
+            "Title - Chapter 9.cbz",
+            "Title - Chapter 9.5.cbr",
+            "Title - Chapter 10.zip",
+            "Title - Chapter 11.7z",
+        ],
+        &[
+            "Volume 1 Chapter 1",
+            "Volume 1 Chapter 2",
+            "Volume 2 Chapter 3",
+        ],
+        &["001", "002", "003", "010", "011", "100"],
+        &["1", "2", "10", "20", "100"],
+        &["Revolution 1", "Revolution 2", "Revolution 10"],
+        &["Alpha", "beta", "Gamma"],
+    ];
+
+    const PANEL_CORPUS: &[&[&str]] = &[
+        &["1.jpg", "2.jpg", "10.jpg", "100.jpg"],
+        &["001.jpg", "002.jpg", "010.jpg", "100.jpg"],
+        &["cover.jpg", "001.jpg", "002.jpg", "credits.jpg"],
+        &["Cover.png", "page_1.png", "page_2.png", "page_10.png"],
+        &["p1.jpg", "p2.jpg", "p10.jpg"],
+        &["P01.JPG", "P02.jpeg", "P10.webp"],
+        &["001-002.jpg", "003.jpg", "004-005.jpg", "006.jpg"],
+        &["img (1).jpg", "img (2).jpg", "img (10).jpg"],
+        &[
+            "Berserk v01 c001 p000.jpg",
+            "Berserk v01 c001 p001.jpg",
+            "Berserk v01 c002 p000.jpg",
+            "Berserk v02 c010 p000.jpg",
+        ],
+        &[
+            "20th Century Boys v01 - p003 [Digital].jpg",
+            "20th Century Boys v01 - p004 [Digital].jpg",
+            "20th Century Boys v01 - p010 [Digital].jpg",
+        ],
+        &[
+            "Title - c010 (v02) - p000 [dig] [Group].jpg",
+            "Title - c010 (v02) - p001 [dig] [Group].jpg",
+            "Title - c011 (v02) - p000 [dig] [Group].jpg",
+        ],
+        &["scan_9.png", "scan_10.png", "scan_11.png"],
+        &["a.jpg", "B.jpg", "c.jpg"],
+    ];
+
+    fn number(whole: u64, fraction: &str, suffix: Option<char>) -> Number {
+        Number {
+            whole,
+            fraction: fraction.to_string(),
+            suffix,
+        }
+    }
+
+    // reversed and rotated copies, so sorting has to move every name
+    fn shuffles(group: &[&str]) -> Vec<Vec<String>> {
+        let names: Vec<String> = group.iter().map(|name| name.to_string()).collect();
+        let mut reversed = names.clone();
+        reversed.reverse();
+        let mut rotated = names.clone();
+        rotated.rotate_left(names.len() / 2);
+        vec![reversed, rotated]
+    }
+
+    #[test]
+    fn chapters_sort_in_reading_order() {
+        for group in CHAPTER_CORPUS {
+            for mut names in shuffles(group) {
+                names.sort_by_cached_key(|name| ChapterKey::parse(name));
+                assert_eq!(names, *group);
+            }
+        }
+    }
+
+    #[test]
+    fn panels_sort_in_reading_order() {
+        for group in PANEL_CORPUS {
+            for mut names in shuffles(group) {
+                names.sort_by_cached_key(|name| PanelKey::parse(name));
+                assert_eq!(names, *group);
+            }
+        }
+    }
+
+    #[test]
+    fn chapter_names_are_parsed() {
+        let cases = [
+            (
+                "Vol.02 Ch.10.5",
+                Position::Numbered(number(2, "", None)),
+                Section::Chapter(number(10, "5", None)),
+            ),
+            (
+                "c001 (v01)",
+                Position::Numbered(number(1, "", None)),
+                Section::Chapter(number(1, "", None)),
+            ),
+            (
+                "Ch.10.50",
+                Position::Unnumbered,
+                Section::Chapter(number(10, "5", None)),
+            ),
+            (
+                "Chapter 7b",
+                Position::Unnumbered,
+                Section::Chapter(number(7, "", Some('b'))),
+            ),
+            (
+                "Title 010v2.cbz",
+                Position::Unnumbered,
+                Section::Chapter(number(10, "", None)),
+            ),
+            ("Extra", Position::Unnumbered, Section::Extra(None)),
+            (
+                "Vol.3 Omake 2",
+                Position::Numbered(number(3, "", None)),
+                Section::Extra(Some(number(2, "", None))),
+            ),
+            ("Prologue", Position::Unnumbered, Section::Prologue),
+            ("Oneshot", Position::Unnumbered, Section::Unnumbered),
+        ];
+
+        for (name, volume, section) in cases {
+            let key = ChapterKey::parse(name);
+            assert_eq!((key.volume, key.section), (volume, section), "{name}");
+        }
+    }
+
+    #[test]
+    fn orderings_are_total() {
+        let chapters: Vec<ChapterKey> = CHAPTER_CORPUS
+            .iter()
+            .flat_map(|group| group.iter())
+            .map(|name| ChapterKey::parse(name))
+            .collect();
+        let panels: Vec<PanelKey> = PANEL_CORPUS
+            .iter()
+            .flat_map(|group| group.iter())
+            .map(|name| PanelKey::parse(name))
+            .collect();
+
+        assert_total(&chapters);
+        assert_total(&panels);
+    }
+
+    // a sort given an inconsistent order may panic, so mixing every group must still be consistent
+    fn assert_total<T: Ord + std::fmt::Debug>(keys: &[T]) {
+        for a in keys {
+            for b in keys {
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a:?} {b:?}");
+                for c in keys {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{a:?} {b:?} {c:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn natural_cmp_compares_numbers_by_value() {
+        let cases = [
+            ("2.jpg", "10.jpg", Ordering::Less),
+            ("010", "10", Ordering::Equal),
+            ("a", "B", Ordering::Less),
+            (
+                "page 99999999999999999999999",
+                "page 100000000000000000000000",
+                Ordering::Less,
+            ),
+            ("abc", "abcd", Ordering::Less),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(natural_cmp(a, b), expected, "{a} {b}");
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::comic_info::get_comic_info_by_folder_id;
+use crate::error::{AppError, AppResult};
+use crate::global::get_manga_folder_by_path;
+use crate::manga::PARENT_FOLDER_CHAIN;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ReadingMode {
+    #[default]
+    RightToLeft,
+    LeftToRight,
+    Vertical,
+    Webtoon,
+}
+
+impl ReadingMode {
+    /// Only the paged horizontal modes show two panels side by side.
+    pub fn pairs_panels(self) -> bool {
+        matches!(self, ReadingMode::RightToLeft | ReadingMode::LeftToRight)
+    }
+}
+
+/// Where the effective reading mode of a chapter came from.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadingModeSource {
+    Chapter,
+    Series,
+    ComicInfo,
+    Default,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EffectiveReadingMode {
+    pub mode: ReadingMode,
+    pub source: ReadingModeSource,
+    pub double_panels: bool,
+}
+
+#[tauri::command]
+pub async fn get_reading_mode(
+    folder_path: String,
+    handle: AppHandle,
+) -> AppResult<EffectiveReadingMode> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    resolve_reading_mode(&folder_path, &pool).await
+}
+
+/// Overrides the mode of a single chapter, `None` makes it inherit again.
+#[tauri::command]
+pub async fn set_chapter_reading_mode(
+    folder_path: String,
+    reading_mode: Option<ReadingMode>,
+    handle: AppHandle,
+) -> AppResult<EffectiveReadingMode> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("UPDATE manga_folder SET reading_mode = ? WHERE full_path = ?")
+        .bind(reading_mode)
+        .bind(&folder_path)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&folder_path));
+    }
+
+    resolve_reading_mode(&folder_path, &pool).await
+}
+
+/// Sets the mode every chapter below a parent folder inherits, `None` clears it.
+#[tauri::command]
+pub async fn set_series_reading_mode(
+    series_path: String,
+    reading_mode: Option<ReadingMode>,
+    handle: AppHandle,
+) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("UPDATE parent_folder SET reading_mode = ? WHERE full_path = ?")
+        .bind(reading_mode)
+        .bind(&series_path)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&series_path));
+    }
+
+    Ok(())
+}
+
+/// Picks the chapter's own mode, then the closest parent folder's, then ComicInfo.xml.
+pub async fn resolve_reading_mode(
+    folder_path: &str,
+    pool: &SqlitePool,
+) -> AppResult<EffectiveReadingMode> {
+    let folder = get_manga_folder_by_path(folder_path, pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(folder_path))?;
+
+    let (mode, source) = if let Some(mode) = folder.reading_mode {
+        (mode, ReadingModeSource::Chapter)
+    } else if let Some(mode) = find_series_reading_mode(folder.parent_id.as_deref(), pool).await? {
+        (mode, ReadingModeSource::Series)
+    } else if let Some(info) = get_comic_info_by_folder_id(&folder.id, pool).await? {
+        // ComicInfo.xml only tells manga apart from everything else, untagged chapters keep the default.
+        // `Unknown` is the schema's default value, it says nothing about the direction either
+        match info.manga.as_deref().map(str::trim) {
+            None | Some("") => (ReadingMode::default(), ReadingModeSource::Default),
+            Some(manga) if manga.eq_ignore_ascii_case("unknown") => {
+                (ReadingMode::default(), ReadingModeSource::Default)
+            }
+            Some(_) if info.right_to_left => {
+                (ReadingMode::RightToLeft, ReadingModeSource::ComicInfo)
+            }
+            Some(_) => (ReadingMode::LeftToRight, ReadingModeSource::ComicInfo),
+        }
+    } else {
+        (ReadingMode::default(), ReadingModeSource::Default)
+    };
+
+    Ok(EffectiveReadingMode {
+        mode,
+        source,
+        double_panels: folder.double_panels && mode.pairs_panels(),
+    })
+}
+
+async fn find_series_reading_mode(
+    parent_id: Option<&str>,
+    pool: &SqlitePool,
+) -> Result<Option<ReadingMode>, sqlx::Error> {
+    let Some(parent_id) = parent_id else {
+        return Ok(None);
+    };
+
+    // walks up nested parent folders, the closest one with a mode wins
+    sqlx::query_scalar(&format!(
+        "{PARENT_FOLDER_CHAIN}
+        SELECT reading_mode FROM parents
+        WHERE reading_mode IS NOT NULL
+        ORDER BY depth
+        LIMIT 1"
+    ))
+    .bind(parent_id)
+    .fetch_optional(pool)
+    .await
+}
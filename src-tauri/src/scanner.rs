@@ -0,0 +1,421 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::read_dir,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::{
+    sync::Mutex,
+    task::{JoinError, JoinSet},
+};
+
+use crate::error::AppResult;
+use crate::manga::{get_panel_image_dimensions, panel_folder_path, split_path_parts};
+use crate::mokuro::import_text_blocks;
+use crate::source::{is_archive, is_panel, split_archive_path, ChapterSource};
+use crate::stats::{refresh_global_stats, Stats};
+
+pub const SCAN_PROGRESS_EVENT: &str = "scan://progress";
+pub const SCAN_FINISHED_EVENT: &str = "scan://finished";
+pub const SCAN_ERROR_EVENT: &str = "scan://error";
+
+// panels are probed and inserted about this many at a time, one transaction per batch
+const PANEL_BATCH_SIZE: usize = 64;
+
+/// Managed state so only one scan runs at a time.
+#[derive(Default)]
+pub struct ScannerState {
+    is_scanning: AtomicBool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanProgress {
+    pub root: String,
+    pub roots_scanned: usize,
+    pub total_roots: usize,
+    pub panels_found: usize,
+    pub panels_added: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanFinished {
+    pub total_roots: usize,
+    pub panels_found: usize,
+    pub panels_added: usize,
+    pub stats: Stats,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanError {
+    pub path: String,
+    pub message: String,
+}
+
+// shared between the per-root tasks of a single scan
+struct ScanCounters {
+    total_roots: usize,
+    roots_scanned: AtomicUsize,
+    panels_found: AtomicUsize,
+    panels_added: AtomicUsize,
+}
+
+impl ScanCounters {
+    fn progress(&self, root: &str) -> ScanProgress {
+        ScanProgress {
+            root: root.to_string(),
+            roots_scanned: self.roots_scanned.load(Ordering::Relaxed),
+            total_roots: self.total_roots,
+            panels_found: self.panels_found.load(Ordering::Relaxed),
+            panels_added: self.panels_added.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn start_library_scan(handle: AppHandle) -> AppResult<bool> {
+    Ok(spawn_library_scan(handle))
+}
+
+/// Starts a library scan on a background task.
+/// Returns `false` without doing anything when a scan is already running.
+pub fn spawn_library_scan(handle: AppHandle) -> bool {
+    if handle
+        .state::<ScannerState>()
+        .is_scanning
+        .swap(true, Ordering::SeqCst)
+    {
+        return false;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = scan_library(&handle).await {
+            emit_scan_error(&handle, "", e.to_string());
+        }
+        handle
+            .state::<ScannerState>()
+            .is_scanning
+            .store(false, Ordering::SeqCst);
+    });
+
+    true
+}
+
+async fn scan_library(handle: &AppHandle) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    // every folder lives inside a library root, so walking the roots covers everything
+    let roots: Vec<String> = sqlx::query_scalar("SELECT full_path FROM library_root")
+        .fetch_all(&pool)
+        .await?;
+
+    let known_panels: HashSet<String> = sqlx::query_scalar("SELECT full_path FROM manga_panel")
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .collect();
+    let known_panels = Arc::new(known_panels);
+
+    let counters = Arc::new(ScanCounters {
+        total_roots: roots.len(),
+        roots_scanned: AtomicUsize::new(0),
+        panels_found: AtomicUsize::new(0),
+        panels_added: AtomicUsize::new(0),
+    });
+
+    let mut scans = JoinSet::new();
+    for root in roots {
+        scans.spawn(scan_root(
+            root,
+            handle.clone(),
+            pool.clone(),
+            known_panels.clone(),
+            counters.clone(),
+        ));
+    }
+    while let Some(result) = scans.join_next().await {
+        if let Err(e) = result {
+            eprintln!("Error Joining Scan Task -> {}", e);
+        }
+    }
+
+    let stats = refresh_global_stats(&pool).await?;
+    let finished = ScanFinished {
+        total_roots: counters.total_roots,
+        panels_found: counters.panels_found.load(Ordering::Relaxed),
+        panels_added: counters.panels_added.load(Ordering::Relaxed),
+        stats,
+    };
+    if let Err(e) = handle.emit(SCAN_FINISHED_EVENT, finished) {
+        eprintln!("Error Emitting {} -> {}", SCAN_FINISHED_EVENT, e);
+    }
+
+    Ok(())
+}
+
+async fn scan_root(
+    root: String,
+    handle: AppHandle,
+    pool: SqlitePool,
+    known_panels: Arc<HashSet<String>>,
+    counters: Arc<ScanCounters>,
+) {
+    // walking folders and listing archives is blocking io
+    let walk_root = root.clone();
+    let walked = tokio::task::spawn_blocking(move || {
+        let mut panels = Vec::new();
+        let mut errors = Vec::new();
+        find_panels(Path::new(&walk_root), &mut panels, &mut errors);
+        (panels, errors)
+    })
+    .await;
+
+    let (panels, errors) = match walked {
+        Ok(walked) => walked,
+        Err(e) => {
+            emit_scan_error(&handle, &root, e.to_string());
+            (Vec::new(), Vec::new())
+        }
+    };
+    for error in errors {
+        emit_scan_error(&handle, &error.path, error.message);
+    }
+
+    counters
+        .panels_found
+        .fetch_add(panels.len(), Ordering::Relaxed);
+    let chapter_paths: BTreeSet<String> =
+        panels.iter().map(|path| panel_folder_path(path)).collect();
+    let new_panels: Vec<String> = panels
+        .into_iter()
+        .filter(|path| !known_panels.contains(path))
+        .collect();
+
+    for batch in chapter_batches(new_panels) {
+        match probe_panels(batch).await {
+            Ok(sized) => match insert_panels(&sized, &pool).await {
+                Ok(()) => {
+                    counters
+                        .panels_added
+                        .fetch_add(sized.len(), Ordering::Relaxed);
+                }
+                Err(e) => emit_scan_error(&handle, &root, e.to_string()),
+            },
+            Err(e) => emit_scan_error(&handle, &root, e.to_string()),
+        }
+
+        emit_scan_progress(&handle, &counters, &root);
+    }
+
+    // runs after the panels are inserted, since text blocks are keyed to them
+    for chapter_path in chapter_paths {
+        if let Err(e) = import_text_blocks(&chapter_path, &pool).await {
+            emit_scan_error(&handle, &chapter_path, e.to_string());
+        }
+    }
+
+    counters.roots_scanned.fetch_add(1, Ordering::Relaxed);
+    emit_scan_progress(&handle, &counters, &root);
+}
+
+// panels in batches of about `PANEL_BATCH_SIZE`. a chapter is never split between batches,
+// so `probe_panels` reads each archive once
+fn chapter_batches(panels: Vec<String>) -> Vec<Vec<String>> {
+    let mut chapters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for panel in panels {
+        chapters
+            .entry(panel_folder_path(&panel))
+            .or_default()
+            .push(panel);
+    }
+
+    let mut batches: Vec<Vec<String>> = Vec::new();
+    for chapter in chapters.into_values() {
+        match batches.last_mut() {
+            Some(batch) if batch.len() + chapter.len() <= PANEL_BATCH_SIZE => batch.extend(chapter),
+            _ => batches.push(chapter),
+        }
+    }
+    batches
+}
+
+/// Collects every panel under `path`, descending into sub folders and archives.
+/// Unreadable folders are recorded in `errors` and skipped.
+pub fn find_panels(path: &Path, panels: &mut Vec<String>, errors: &mut Vec<ScanError>) {
+    // a manga folder can itself be an archive
+    if is_archive(path) {
+        let panel_paths = ChapterSource::open(path)
+            .map(|source| source.panel_paths())
+            .unwrap_or_else(|| Ok(Vec::new()));
+        match panel_paths {
+            Ok(panel_paths) => panels.extend(panel_paths),
+            Err(e) => errors.push(ScanError {
+                path: path.to_string_lossy().into_owned(),
+                message: e.to_string(),
+            }),
+        }
+        return;
+    }
+
+    let entries = match read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push(ScanError {
+                path: path.to_string_lossy().into_owned(),
+                message: e.to_string(),
+            });
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if entry_path.is_dir() || is_archive(&entry_path) {
+            find_panels(&entry_path, panels, errors);
+        } else if is_panel(&entry_path) {
+            panels.push(entry_path.to_string_lossy().into_owned());
+        }
+    }
+}
+
+/// Reads the size of every panel on the blocking pool, unreadable panels get `(0, 0)`.
+/// This reads the image header, or the whole entry for panels inside an archive.
+pub async fn probe_panels(paths: Vec<String>) -> Result<Vec<(String, u16, u16)>, JoinError> {
+    tokio::task::spawn_blocking(move || {
+        // the panels of an archive are measured in one pass over it, not one per panel
+        let mut archive_sizes: HashMap<PathBuf, HashMap<String, (usize, usize)>> = HashMap::new();
+        paths
+            .into_iter()
+            .map(|path| {
+                let (width, height) = match split_archive_path(&path) {
+                    Some((archive_path, _)) => archive_sizes
+                        .entry(archive_path)
+                        .or_insert_with_key(|archive_path| {
+                            ChapterSource::open(archive_path)
+                                .and_then(|source| source.panel_sizes().ok())
+                                .unwrap_or_default()
+                        })
+                        .get(&path)
+                        .map(|&(width, height)| (width as u16, height as u16))
+                        .unwrap_or((0, 0)),
+                    None => get_panel_image_dimensions(&path),
+                };
+                (path, width, height)
+            })
+            .collect()
+    })
+    .await
+}
+
+/// Inserts panels with their probed size, panels that reappeared on disk lose their missing flag.
+pub async fn insert_panels(
+    panels: &[(String, u16, u16)],
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for (path, width, height) in panels {
+        // gets the parent, file name, and extension of the path
+        let split_path = split_path_parts(path);
+
+        sqlx::query(
+            "INSERT INTO manga_panel (
+        id,
+        folder_id,
+        title,
+        full_path,
+        is_read,
+        width,
+        height,
+        zoom_level,
+        created_at,
+        updated_at
+        )
+        VALUES (
+        ?, (SELECT id FROM manga_folder WHERE full_path = ?), ?, ?, ?, ?, ?, ?,
+        datetime('now', 'localtime'),
+        datetime('now', 'localtime'))
+        ON CONFLICT (full_path) DO UPDATE SET
+        folder_id = COALESCE(excluded.folder_id, manga_panel.folder_id),
+        is_missing = 0",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(panel_folder_path(path))
+        .bind(split_path.file_name)
+        .bind(path)
+        .bind(false)
+        .bind(width)
+        .bind(height)
+        .bind(0)
+        .execute(&mut *tx)
+        .await?;
+    }
+    crate::bookmarks::relink_bookmarks(&mut tx).await?;
+
+    tx.commit().await
+}
+
+fn emit_scan_progress(handle: &AppHandle, counters: &ScanCounters, root: &str) {
+    if let Err(e) = handle.emit(SCAN_PROGRESS_EVENT, counters.progress(root)) {
+        eprintln!("Error Emitting {} -> {}", SCAN_PROGRESS_EVENT, e);
+    }
+}
+
+fn emit_scan_error(handle: &AppHandle, path: &str, message: String) {
+    eprintln!("Error Scanning: {} -> {}", path, message);
+    let error = ScanError {
+        path: path.to_string(),
+        message,
+    };
+    if let Err(e) = handle.emit(SCAN_ERROR_EVENT, error) {
+        eprintln!("Error Emitting {} -> {}", SCAN_ERROR_EVENT, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_keep_chapters_whole() {
+        let chapter = |name: &str, panels: usize| -> Vec<String> {
+            (1..=panels)
+                .map(|panel| format!("/manga/{name}/{panel:03}.png"))
+                .collect()
+        };
+        // interleaved, the way panels of two archives could come back
+        let mut panels: Vec<String> = chapter("a", 30)
+            .into_iter()
+            .zip(chapter("b", 30))
+            .flat_map(|(a, b)| [a, b])
+            .collect();
+        panels.extend(chapter("c", 100));
+        panels.extend(chapter("d", 10));
+        panels.extend(chapter("e", 20));
+
+        let batches = chapter_batches(panels);
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [60, 100, 30]);
+        for batch in &batches {
+            let chapters: BTreeSet<String> =
+                batch.iter().map(|panel| panel_folder_path(panel)).collect();
+            for chapter in chapters {
+                let in_batches = batches
+                    .iter()
+                    .filter(|batch| {
+                        batch
+                            .iter()
+                            .any(|panel| panel_folder_path(panel) == chapter)
+                    })
+                    .count();
+                assert_eq!(in_batches, 1, "{chapter} is in one batch");
+            }
+        }
+    }
+}
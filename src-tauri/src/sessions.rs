@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+use crate::global::get_manga_folder_by_path;
+use crate::stats::{daily_goal_progress, GoalProgress, GOAL_REACHED_EVENT};
+
+// the reader saves every 30 seconds, a longer gap means the computer slept or the app hung
+// with the chapter open, and that time wasn't spent reading
+const MAX_HEARTBEAT_SECONDS: i64 = 30 + 30;
+
+/// A stretch of time spent reading one chapter.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ReadingSession {
+    pub id: String,
+    pub folder_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    pub pages_turned: u32,
+    pub seconds: u32,
+}
+
+/// Totals summed from the sessions of a chapter, or of every chapter in a series.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, sqlx::FromRow)]
+pub struct ReadingTime {
+    pub seconds: u32,
+    pub pages_turned: u32,
+    pub sessions: u32,
+}
+
+#[tauri::command]
+pub async fn start_reading_session(
+    folder_path: String,
+    handle: AppHandle,
+) -> AppResult<ReadingSession> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let folder = get_manga_folder_by_path(&folder_path, &pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(&folder_path))?;
+    let id = uuid::Uuid::new_v4().to_string();
+
+    sqlx::query(
+        "INSERT INTO reading_session (id, folder_id, started_at, ended_at, pages_turned, seconds)
+        VALUES (?, ?, datetime('now', 'localtime'), datetime('now', 'localtime'), 0, 0)",
+    )
+    .bind(&id)
+    .bind(&folder.id)
+    .execute(&pool)
+    .await?;
+
+    // the daily list on the stats page goes by when a chapter was last touched
+    sqlx::query("UPDATE manga_folder SET updated_at = datetime('now', 'localtime') WHERE id = ?")
+        .bind(&folder.id)
+        .execute(&pool)
+        .await?;
+
+    get_reading_session(&id, &pool).await
+}
+
+/// Extends a session up to now. The reader calls this while the chapter is open and once
+/// more when it's closed, so a crash only loses the time since the last call.
+/// Each call adds at most `MAX_HEARTBEAT_SECONDS`, measured in UTC so a DST change during
+/// the session doesn't add or drop an hour.
+/// Emits `GOAL_REACHED_EVENT` when this update completes the daily goal.
+#[tauri::command]
+pub async fn update_reading_session(
+    session_id: String,
+    pages_turned: u32,
+    handle: AppHandle,
+) -> AppResult<ReadingSession> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    if let Some(progress) = extend_reading_session(&session_id, pages_turned, &pool).await? {
+        if let Err(e) = handle.emit(GOAL_REACHED_EVENT, progress) {
+            eprintln!("Error Emitting Goal: {}", e);
+        }
+    }
+
+    get_reading_session(&session_id, &pool).await
+}
+
+/// Returns today's progress when this update is the one that completed the daily goal.
+async fn extend_reading_session(
+    session_id: &str,
+    pages_turned: u32,
+    pool: &SqlitePool,
+) -> AppResult<Option<GoalProgress>> {
+    let was_complete = daily_goal_progress(pool)
+        .await?
+        .is_some_and(|progress| progress.is_complete);
+
+    let result = sqlx::query(
+        "UPDATE reading_session SET
+        ended_at = datetime('now', 'localtime'),
+        seconds = seconds + MAX(0, MIN(?, strftime('%s', 'now') - strftime('%s', ended_at, 'utc'))),
+        pages_turned = ?
+        WHERE id = ?",
+    )
+    .bind(MAX_HEARTBEAT_SECONDS)
+    .bind(pages_turned)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(session_id));
+    }
+
+    Ok(daily_goal_progress(pool)
+        .await?
+        .filter(|progress| progress.is_complete && !was_complete))
+}
+
+/// Works for a chapter, and for a series or volume by adding up every chapter inside it.
+#[tauri::command]
+pub async fn get_reading_time(folder_path: String, handle: AppHandle) -> AppResult<ReadingTime> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let reading_time = sqlx::query_as(
+        "WITH RECURSIVE folder(id) AS (
+            SELECT id FROM parent_folder WHERE full_path = ?
+            UNION
+            SELECT parent_folder.id FROM parent_folder
+            INNER JOIN folder ON parent_folder.parent_id = folder.id
+        )
+        SELECT
+            COALESCE(SUM(reading_session.seconds), 0) AS seconds,
+            COALESCE(SUM(reading_session.pages_turned), 0) AS pages_turned,
+            COUNT(reading_session.id) AS sessions
+        FROM reading_session
+        INNER JOIN manga_folder ON manga_folder.id = reading_session.folder_id
+        WHERE manga_folder.full_path = ? OR manga_folder.parent_id IN (SELECT id FROM folder)",
+    )
+    .bind(&folder_path)
+    .bind(&folder_path)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(reading_time)
+}
+
+/// Every second spent reading, including chapters that have since been removed.
+pub async fn total_reading_seconds(pool: &SqlitePool) -> Result<u32, sqlx::Error> {
+    sqlx::query_scalar("SELECT COALESCE(SUM(seconds), 0) FROM reading_session")
+        .fetch_one(pool)
+        .await
+}
+
+async fn get_reading_session(session_id: &str, pool: &SqlitePool) -> AppResult<ReadingSession> {
+    sqlx::query_as("SELECT * FROM reading_session WHERE id = ?")
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(session_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn caps_the_time_added_per_update() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        // (name, seconds since the last update, seconds so far, seconds added)
+        let cases = [
+            ("regular heartbeat", 20, 100, 20),
+            (
+                "woke up from sleep",
+                2 * 60 * 60,
+                100,
+                MAX_HEARTBEAT_SECONDS,
+            ),
+            ("clock moved back", -60, 100, 0),
+        ];
+        for (name, since_update, seconds, added) in cases {
+            sqlx::query(
+                "INSERT INTO reading_session (id, folder_id, started_at, ended_at, pages_turned, seconds)
+                VALUES (?, NULL, datetime('now', 'localtime', '-3 hours'),
+                    datetime('now', 'localtime', ? || ' seconds'), 0, ?)",
+            )
+            .bind(name)
+            .bind(-since_update)
+            .bind(seconds)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            extend_reading_session(name, 1, &pool).await.unwrap();
+            let session = get_reading_session(name, &pool).await.unwrap();
+            // allow a second for the clock ticking between the insert and the update
+            let added_now = i64::from(session.seconds) - seconds;
+            assert!(
+                (added..=added + 1).contains(&added_now),
+                "{name}: added {added_now} seconds, expected {added}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reaching_the_daily_goal_is_reported_once() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO reading_goal (period, unit, target, created_at, updated_at)
+            VALUES ('daily', 'pages', 10, '', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO reading_session (id, folder_id, started_at, ended_at, pages_turned, seconds)
+            VALUES ('session', NULL, datetime('now', 'localtime'), datetime('now', 'localtime'), 0, 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let cases = [
+            ("below the goal", 4, false),
+            ("reaches the goal", 10, true),
+            ("already reached", 12, false),
+            ("still reached", 20, false),
+        ];
+        for (name, pages_turned, reached) in cases {
+            let progress = extend_reading_session("session", pages_turned, &pool)
+                .await
+                .unwrap();
+            assert_eq!(progress.is_some(), reached, "{name}");
+        }
+
+        assert!(matches!(
+            extend_reading_session("unknown", 1, &pool).await,
+            Err(AppError::NotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,681 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{read_dir, File},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, OnceLock, PoisonError},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use sevenz_rust::Password;
+use sqlx::SqlitePool;
+use tauri::{
+    http::{header::CONTENT_TYPE, Request, Response, StatusCode},
+    AppHandle, Manager,
+};
+use tokio::sync::Mutex;
+
+use crate::ordering::PanelKey;
+
+pub const PANEL_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// The scheme the webview uses to request panel images, see `panel_protocol`.
+pub const PANEL_PROTOCOL: &str = "panel";
+
+// a chapter is tens of megabytes of images, only the ones being read are kept
+const CACHED_ARCHIVES: usize = 3;
+
+/// Where the panels of a chapter come from.
+/// Panels inside an archive are addressed as `<archive path>/<entry name>`
+/// so every other part of the app can keep treating them as plain paths.
+#[derive(Debug, Clone)]
+pub enum ChapterSource {
+    Directory(PathBuf),
+    Archive(ArchiveKind, PathBuf),
+}
+
+/// The container formats that can be read as a chapter, all of them read-only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Rar,
+    SevenZ,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "cbz" | "zip" => Some(Self::Zip),
+            "cbr" | "rar" => Some(Self::Rar),
+            "cb7" | "7z" => Some(Self::SevenZ),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PanelEntry {
+    pub name: String,
+    pub path: String,
+}
+
+impl ChapterSource {
+    pub fn open(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Some(Self::Directory(path.to_path_buf()));
+        }
+        if path.is_file() {
+            return ArchiveKind::from_path(path)
+                .map(|kind| Self::Archive(kind, path.to_path_buf()));
+        }
+        None
+    }
+
+    /// Lists every panel of the chapter as a full path, in reading order by file name.
+    pub fn panel_paths(&self) -> Result<Vec<String>, io::Error> {
+        let mut entries = match self {
+            Self::Directory(dir) => {
+                let mut paths = Vec::new();
+                for entry in read_dir(dir)? {
+                    let entry_path = entry?.path();
+                    if entry_path.is_file() && is_panel(&entry_path) {
+                        paths.push(entry_path.to_string_lossy().into_owned());
+                    }
+                }
+                paths
+            }
+            Self::Archive(kind, archive_path) => list_archive_entries(*kind, archive_path)?
+                .into_iter()
+                .filter(|name| is_panel(Path::new(name)))
+                .map(|name| archive_path.join(name).to_string_lossy().into_owned())
+                .collect(),
+        };
+
+        entries.sort_by_cached_key(|path| (PanelKey::parse(&file_name(path)), path.clone()));
+        Ok(entries)
+    }
+
+    /// The size of every readable panel keyed by its path, found in one pass over the chapter.
+    /// Rar and 7z entries only decode in order, so reading them one at a time would decode
+    /// the archive again for every panel.
+    pub fn panel_sizes(&self) -> Result<HashMap<String, (usize, usize)>, io::Error> {
+        let mut sizes = HashMap::new();
+        match self {
+            Self::Directory(_) => {
+                for path in self.panel_paths()? {
+                    if let Ok(size) = imagesize::size(&path) {
+                        sizes.insert(path, (size.width, size.height));
+                    }
+                }
+            }
+            Self::Archive(kind, archive_path) => {
+                for_each_archive_panel(*kind, archive_path, |name, bytes| {
+                    if let Ok(size) = imagesize::blob_size(bytes) {
+                        let path = archive_path.join(name).to_string_lossy().into_owned();
+                        sizes.insert(path, (size.width, size.height));
+                    }
+                })?;
+            }
+        }
+        Ok(sizes)
+    }
+
+    pub fn panels(&self) -> Result<Vec<PanelEntry>, io::Error> {
+        Ok(self
+            .panel_paths()?
+            .into_iter()
+            .map(|path| PanelEntry {
+                name: file_name(&path),
+                path,
+            })
+            .collect())
+    }
+
+    /// Reads the chapter's `ComicInfo.xml`, either from inside the archive or next to the images.
+    pub fn comic_info_xml(&self) -> Option<String> {
+        let bytes = match self {
+            Self::Directory(dir) => read_dir(dir)
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .find(|entry| is_comic_info(&entry.file_name().to_string_lossy()))
+                .and_then(|entry| std::fs::read(entry.path()).ok())?,
+            Self::Archive(kind, archive_path) => {
+                let entry_name = list_archive_entries(*kind, archive_path)
+                    .ok()?
+                    .into_iter()
+                    .find(|name| is_comic_info(&file_name(name)))?;
+                read_archive_entry(*kind, archive_path, &entry_name).ok()?
+            }
+        };
+
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub fn first_panel_path(&self) -> Result<String, io::Error> {
+        self.panel_paths()?.into_iter().next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "No valid cover panel image found")
+        })
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveKind::from_path(path).is_some()
+}
+
+pub fn is_panel(path: &Path) -> bool {
+    has_extension(path, &PANEL_EXTENSIONS)
+}
+
+fn is_comic_info(file_name: &str) -> bool {
+    file_name.eq_ignore_ascii_case("ComicInfo.xml")
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn open_zip(archive_path: &Path) -> Result<zip::ZipArchive<File>, io::Error> {
+    zip::ZipArchive::new(File::open(archive_path)?).map_err(io::Error::other)
+}
+
+/// Lists the names of every file inside an archive, using `/` as the separator.
+pub fn list_archive_entries(
+    kind: ArchiveKind,
+    archive_path: &Path,
+) -> Result<Vec<String>, io::Error> {
+    match kind {
+        ArchiveKind::Zip => Ok(open_zip(archive_path)?
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| name.to_string())
+            .collect()),
+        ArchiveKind::Rar => {
+            let archive = unrar::Archive::new(archive_path)
+                .open_for_listing()
+                .map_err(io::Error::other)?;
+            let mut names = Vec::new();
+            for header in archive {
+                let header = header.map_err(io::Error::other)?;
+                if header.is_file() {
+                    names.push(entry_name(&header.filename));
+                }
+            }
+            Ok(names)
+        }
+        ArchiveKind::SevenZ => {
+            let reader = sevenz_rust::SevenZReader::open(archive_path, Password::empty())
+                .map_err(io::Error::other)?;
+            Ok(reader
+                .archive()
+                .files
+                .iter()
+                .filter(|entry| !entry.is_directory() && entry.has_stream())
+                .map(|entry| entry.name().replace('\\', "/"))
+                .collect())
+        }
+    }
+}
+
+pub fn read_archive_entry(
+    kind: ArchiveKind,
+    archive_path: &Path,
+    entry_name: &str,
+) -> Result<Vec<u8>, io::Error> {
+    let not_found = || {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{entry_name}` not found in {}", archive_path.display()),
+        )
+    };
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = open_zip(archive_path)?;
+            let mut entry = archive.by_name(entry_name).map_err(io::Error::other)?;
+            let mut bytes = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+        ArchiveKind::Rar => {
+            let mut archive = unrar::Archive::new(archive_path)
+                .open_for_processing()
+                .map_err(io::Error::other)?;
+            // rar entries can only be read in order, so skip ahead to the one we want
+            while let Some(header) = archive.read_header().map_err(io::Error::other)? {
+                archive = if entry_name == self::entry_name(&header.entry().filename) {
+                    let (bytes, _) = header.read().map_err(io::Error::other)?;
+                    return Ok(bytes);
+                } else {
+                    header.skip().map_err(io::Error::other)?
+                };
+            }
+            Err(not_found())
+        }
+        ArchiveKind::SevenZ => {
+            let mut reader = sevenz_rust::SevenZReader::open(archive_path, Password::empty())
+                .map_err(io::Error::other)?;
+            let mut found: Option<Vec<u8>> = None;
+            reader
+                .for_each_entries(|entry, data| {
+                    if found.is_some() {
+                        return Ok(false);
+                    }
+                    if entry.name().replace('\\', "/") == entry_name {
+                        let mut bytes = Vec::with_capacity(entry.size() as usize);
+                        data.read_to_end(&mut bytes)?;
+                        found = Some(bytes);
+                        return Ok(false);
+                    }
+                    // solid archives decode sequentially, so drain entries we skip
+                    io::copy(data, &mut io::sink())?;
+                    Ok(true)
+                })
+                .map_err(io::Error::other)?;
+            found.ok_or_else(not_found)
+        }
+    }
+}
+
+/// Reads every panel of an archive in the order it's stored, with its entry name.
+fn for_each_archive_panel(
+    kind: ArchiveKind,
+    archive_path: &Path,
+    mut read: impl FnMut(&str, &[u8]),
+) -> Result<(), io::Error> {
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = open_zip(archive_path)?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+                if entry.is_dir() || !is_panel(Path::new(entry.name())) {
+                    continue;
+                }
+                let mut bytes = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut bytes)?;
+                read(entry.name(), &bytes);
+            }
+        }
+        ArchiveKind::Rar => {
+            let mut archive = unrar::Archive::new(archive_path)
+                .open_for_processing()
+                .map_err(io::Error::other)?;
+            while let Some(header) = archive.read_header().map_err(io::Error::other)? {
+                let name = entry_name(&header.entry().filename);
+                archive = if header.entry().is_file() && is_panel(Path::new(&name)) {
+                    let (bytes, archive) = header.read().map_err(io::Error::other)?;
+                    read(&name, &bytes);
+                    archive
+                } else {
+                    header.skip().map_err(io::Error::other)?
+                };
+            }
+        }
+        ArchiveKind::SevenZ => {
+            let mut reader = sevenz_rust::SevenZReader::open(archive_path, Password::empty())
+                .map_err(io::Error::other)?;
+            reader
+                .for_each_entries(|entry, data| {
+                    let name = entry.name().replace('\\', "/");
+                    if entry.is_directory() || !is_panel(Path::new(&name)) {
+                        // solid archives decode sequentially, so drain entries we skip
+                        io::copy(data, &mut io::sink())?;
+                        return Ok(true);
+                    }
+                    let mut bytes = Vec::with_capacity(entry.size() as usize);
+                    data.read_to_end(&mut bytes)?;
+                    read(&name, &bytes);
+                    Ok(true)
+                })
+                .map_err(io::Error::other)?;
+        }
+    }
+    Ok(())
+}
+
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Splits a panel path that points inside an archive into the archive path and the entry name.
+/// Returns `None` for panels that are plain files on disk.
+pub fn split_archive_path(panel_path: &str) -> Option<(PathBuf, String)> {
+    let path = Path::new(panel_path);
+
+    for archive_path in path.ancestors().skip(1) {
+        if archive_path.is_file() && is_archive(archive_path) {
+            let entry = path.strip_prefix(archive_path).ok()?;
+            // archive entries always use forward slashes
+            return Some((archive_path.to_path_buf(), entry_name(entry)));
+        }
+    }
+
+    None
+}
+
+pub fn read_panel_bytes(panel_path: &str) -> Result<Vec<u8>, io::Error> {
+    match split_archive_path(panel_path) {
+        Some((archive_path, entry_name)) => {
+            // split_archive_path only returns paths with a known archive extension
+            let kind = ArchiveKind::from_path(&archive_path).unwrap();
+            read_archive_entry(kind, &archive_path, &entry_name)
+        }
+        None => std::fs::read(panel_path),
+    }
+}
+
+/// Managed state holding the panels of the last few rar and 7z archives the reader opened.
+/// Their entries only decode in order, so reading a chapter a page at a time would decode
+/// every earlier page again for each one. Zip entries are read on their own instead.
+#[derive(Default)]
+pub struct ArchiveCache {
+    // the most recently read last
+    archives: std::sync::Mutex<VecDeque<CachedArchive>>,
+}
+
+// filled by the first read, later reads of the archive wait for it instead of decoding too
+type ArchivePanels = Arc<OnceLock<Result<HashMap<String, Vec<u8>>, String>>>;
+
+struct CachedArchive {
+    path: PathBuf,
+    modified: SystemTime,
+    panels: ArchivePanels,
+}
+
+impl ArchiveCache {
+    /// Reads a panel like `read_panel_bytes`, decoding a rar or 7z archive whole the first time.
+    pub fn read_panel(&self, panel_path: &str) -> Result<Vec<u8>, io::Error> {
+        let Some((archive_path, entry_name)) = split_archive_path(panel_path) else {
+            return std::fs::read(panel_path);
+        };
+        // split_archive_path only returns paths with a known archive extension
+        let kind = ArchiveKind::from_path(&archive_path).unwrap();
+        if kind == ArchiveKind::Zip {
+            return read_archive_entry(kind, &archive_path, &entry_name);
+        }
+
+        let modified = std::fs::metadata(&archive_path)?.modified()?;
+        let panels = self.archive(&archive_path, modified);
+        let panels = panels.get_or_init(|| {
+            let mut panels = HashMap::new();
+            for_each_archive_panel(kind, &archive_path, |name, bytes| {
+                panels.insert(name.to_string(), bytes.to_vec());
+            })
+            .map(|()| panels)
+            .map_err(|e| e.to_string())
+        });
+
+        match panels {
+            Ok(panels) => panels.get(&entry_name).cloned().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("`{entry_name}` not found in {}", archive_path.display()),
+                )
+            }),
+            Err(e) => {
+                // read it again next time, the archive may have been mid copy
+                self.archives
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|archive| archive.path != archive_path);
+                Err(io::Error::other(e.clone()))
+            }
+        }
+    }
+
+    // the cached panels of an archive, a changed archive starts over
+    fn archive(&self, path: &Path, modified: SystemTime) -> ArchivePanels {
+        let mut archives = self.archives.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let cached = archives
+            .iter()
+            .position(|archive| archive.path == path)
+            .and_then(|index| archives.remove(index))
+            .filter(|archive| archive.modified == modified);
+        let archive = cached.unwrap_or_else(|| CachedArchive {
+            path: path.to_path_buf(),
+            modified,
+            panels: Arc::default(),
+        });
+        let panels = archive.panels.clone();
+
+        archives.push_back(archive);
+        while archives.len() > CACHED_ARCHIVES {
+            archives.pop_front();
+        }
+
+        panels
+    }
+}
+
+pub fn panel_image_size(panel_path: &str) -> Option<(usize, usize)> {
+    let size = match split_archive_path(panel_path) {
+        Some(_) => imagesize::blob_size(&read_panel_bytes(panel_path).ok()?).ok()?,
+        None => imagesize::size(panel_path).ok()?,
+    };
+    Some((size.width, size.height))
+}
+
+fn panel_mime_type(panel_path: &str) -> &'static str {
+    match Path::new(panel_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// The panel path a protocol request was built for with `convertFileSrc`.
+pub fn request_panel_path(request: &Request<Vec<u8>>) -> String {
+    let encoded = request.uri().path().trim_start_matches('/');
+    percent_encoding::percent_decode_str(encoded)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Whether the webview may read `panel_path`, an image that is a registered panel or sits
+/// below a library root. Keeps the protocols from serving any other file on disk.
+pub async fn is_library_panel(panel_path: &str, pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let path = Path::new(panel_path);
+    if !is_panel(path) || path.components().any(|part| part == Component::ParentDir) {
+        return Ok(false);
+    }
+
+    sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM manga_panel WHERE full_path = ?)
+        OR EXISTS (
+            SELECT 1 FROM library_root
+            WHERE substr(?, 1, length(full_path) + 1) IN (full_path || '/', full_path || '\\')
+        )",
+    )
+    .bind(panel_path)
+    .bind(panel_path)
+    .fetch_one(pool)
+    .await
+}
+
+/// Whether a protocol may answer a request for `panel_path`, logs why when it may not.
+pub async fn may_serve_panel(handle: &AppHandle, panel_path: &str) -> bool {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    match is_library_panel(panel_path, &pool).await {
+        Ok(true) => true,
+        Ok(false) => {
+            eprintln!("Error Serving Panel: {} -> not in the library", panel_path);
+            false
+        }
+        Err(e) => {
+            eprintln!("Error Serving Panel: {} -> {}", panel_path, e);
+            false
+        }
+    }
+}
+
+pub fn forbidden_response() -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(Vec::new())
+        .unwrap()
+}
+
+/// Serves panel images to the webview for both plain files and archive entries.
+/// The frontend builds the url with `convertFileSrc(panel.full_path, "panel")`.
+pub async fn panel_protocol(handle: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let panel_path = request_panel_path(request);
+    if !may_serve_panel(handle, &panel_path).await {
+        return forbidden_response();
+    }
+
+    let path = panel_path.clone();
+    let handle = handle.clone();
+    let read = tauri::async_runtime::spawn_blocking(move || {
+        handle.state::<ArchiveCache>().read_panel(&path)
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::other(e)));
+    match read {
+        Ok(bytes) => Response::builder()
+            .header(CONTENT_TYPE, panel_mime_type(&panel_path))
+            .body(bytes)
+            .unwrap(),
+        Err(e) => {
+            eprintln!("Error Reading Panel: {} -> {}", panel_path, e);
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Vec::new())
+                .unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = io::Cursor::new(Vec::new());
+        image::RgbImage::new(width, height)
+            .write_to(&mut bytes, image::ImageFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn archive_panel_sizes_are_read_in_one_pass() {
+        let dir = std::env::temp_dir().join(format!("source-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("Chapter 1.cbz");
+
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, bytes) in [
+            ("002.png", png(4, 3)),
+            ("001.png", png(2, 5)),
+            ("ComicInfo.xml", b"<ComicInfo/>".to_vec()),
+            ("broken.png", b"not a png".to_vec()),
+        ] {
+            writer.start_file(name, options).unwrap();
+            std::io::Write::write_all(&mut writer, &bytes).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let sizes = ChapterSource::open(&archive_path)
+            .unwrap()
+            .panel_sizes()
+            .unwrap();
+        let path = |name: &str| archive_path.join(name).to_string_lossy().into_owned();
+        assert_eq!(
+            sizes,
+            HashMap::from([(path("001.png"), (2, 5)), (path("002.png"), (4, 3))])
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_most_recently_read_archives() {
+        let cache = ArchiveCache::default();
+        let path = |name: &str| PathBuf::from(format!("/manga/{name}.cbr"));
+        let time = |seconds: u64| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+
+        let first = cache.archive(&path("1"), time(1));
+        assert!(
+            Arc::ptr_eq(&first, &cache.archive(&path("1"), time(1))),
+            "read again"
+        );
+        assert!(
+            !Arc::ptr_eq(&first, &cache.archive(&path("1"), time(2))),
+            "a changed archive is decoded again"
+        );
+
+        let second = cache.archive(&path("2"), time(1));
+        cache.archive(&path("3"), time(1));
+        // reading 2 again makes 4 push out 1 instead
+        cache.archive(&path("2"), time(1));
+        cache.archive(&path("4"), time(1));
+        let cached: Vec<PathBuf> = cache
+            .archives
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|archive| archive.path.clone())
+            .collect();
+        assert_eq!(cached, [path("3"), path("2"), path("4")]);
+        assert!(Arc::ptr_eq(&second, &cache.archive(&path("2"), time(1))));
+    }
+
+    #[tokio::test]
+    async fn only_library_images_are_served() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+        sqlx::query("INSERT INTO library_root (id, full_path) VALUES ('root', '/library')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO manga_panel (id, title, full_path) VALUES ('panel', '001.png', '/elsewhere/001.png')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for (path, allowed) in [
+            ("/library/Series/Chapter 1/001.png", true),
+            ("/library/Series/Chapter 1.cbz/001.jpg", true),
+            ("/elsewhere/001.png", true),
+            ("/elsewhere/002.png", false),
+            ("/library-other/001.png", false),
+            ("/etc/passwd", false),
+            ("/library/Series/notes.txt", false),
+            ("/library/../home/user/.ssh/id.png", false),
+        ] {
+            assert_eq!(
+                is_library_panel(path, &pool).await.unwrap(),
+                allowed,
+                "{path}"
+            );
+        }
+    }
+}
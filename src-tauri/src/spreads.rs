@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult, PathContext};
+use crate::global::get_manga_folder_by_path;
+use crate::scanner::{insert_panels, probe_panels};
+use crate::source::{ChapterSource, PanelEntry};
+
+// panels more than this much wider than tall are two pages scanned as one image,
+// square pages and portrait scans with their margins cropped stay below it
+const SPREAD_ASPECT_RATIO: f32 = 1.2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PanelKind {
+    Single,
+    Spread,
+    Cover,
+}
+
+impl PanelKind {
+    /// Panels of unknown size count as single pages.
+    pub fn classify(index: usize, width: u16, height: u16) -> Self {
+        if width == 0 || height == 0 {
+            return PanelKind::Single;
+        }
+
+        if width as f32 / height as f32 > SPREAD_ASPECT_RATIO {
+            PanelKind::Spread
+        } else if index == 0 {
+            PanelKind::Cover
+        } else {
+            PanelKind::Single
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LayoutPanel {
+    pub index: usize,
+    pub name: String,
+    pub path: String,
+    pub width: u16,
+    pub height: u16,
+    pub kind: PanelKind,
+}
+
+/// One or two panels shown side by side, in reading order.
+#[derive(Debug, Serialize, Clone)]
+pub struct Spread {
+    pub panels: Vec<LayoutPanel>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PanelLayout {
+    pub spread_offset: bool,
+    pub spreads: Vec<Spread>,
+}
+
+#[tauri::command]
+pub async fn get_panel_layout(folder_path: String, handle: AppHandle) -> AppResult<PanelLayout> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    build_panel_layout(&folder_path, &pool).await
+}
+
+/// Shifts the pairing by one page, for chapters where the cover detection lands on the wrong side.
+#[tauri::command]
+pub async fn set_spread_offset(
+    folder_path: String,
+    spread_offset: bool,
+    handle: AppHandle,
+) -> AppResult<PanelLayout> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result = sqlx::query("UPDATE manga_folder SET spread_offset = ? WHERE full_path = ?")
+        .bind(spread_offset)
+        .bind(&folder_path)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&folder_path));
+    }
+
+    build_panel_layout(&folder_path, &pool).await
+}
+
+async fn build_panel_layout(folder_path: &str, pool: &SqlitePool) -> AppResult<PanelLayout> {
+    let folder = get_manga_folder_by_path(folder_path, pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(folder_path))?;
+
+    let source =
+        ChapterSource::open(folder_path).ok_or_else(|| AppError::not_found(folder_path))?;
+    let entries = source.panels().with_path(folder_path)?;
+
+    let mut sizes: HashMap<String, (u16, u16)> = sqlx::query_as::<_, (String, u16, u16)>(
+        "SELECT full_path, width, height FROM manga_panel WHERE folder_id = ?",
+    )
+    .bind(&folder.id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(path, width, height)| (path, (width, height)))
+    .collect();
+
+    // panels the scanner hasn't reached yet are probed now and kept for next time
+    let unknown: Vec<String> = entries
+        .iter()
+        .filter(|entry| !sizes.contains_key(&entry.path))
+        .map(|entry| entry.path.clone())
+        .collect();
+    if !unknown.is_empty() {
+        let probed = probe_panels(unknown).await?;
+        insert_panels(&probed, pool).await?;
+        sizes.extend(
+            probed
+                .into_iter()
+                .map(|(path, width, height)| (path, (width, height))),
+        );
+    }
+
+    let panels = classify_panels(entries, &sizes);
+
+    Ok(PanelLayout {
+        spread_offset: folder.spread_offset,
+        spreads: pair_panels(panels, folder.spread_offset),
+    })
+}
+
+fn classify_panels(
+    entries: Vec<PanelEntry>,
+    sizes: &HashMap<String, (u16, u16)>,
+) -> Vec<LayoutPanel> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (width, height) = sizes.get(&entry.path).copied().unwrap_or((0, 0));
+            LayoutPanel {
+                index,
+                name: entry.name,
+                path: entry.path,
+                width,
+                height,
+                kind: PanelKind::classify(index, width, height),
+            }
+        })
+        .collect()
+}
+
+/// Pairs consecutive single pages, covers and wide spreads always stand alone.
+/// A single page left without a partner before a spread is shown alone as well,
+/// and `offset` shows the first single page alone so every pair after it shifts by one.
+pub fn pair_panels(panels: Vec<LayoutPanel>, offset: bool) -> Vec<Spread> {
+    let mut spreads = Vec::new();
+    let mut pending: Option<LayoutPanel> = None;
+    let mut shift = offset;
+
+    for panel in panels {
+        match panel.kind {
+            PanelKind::Cover | PanelKind::Spread => {
+                if let Some(single) = pending.take() {
+                    spreads.push(Spread {
+                        panels: vec![single],
+                    });
+                }
+                spreads.push(Spread {
+                    panels: vec![panel],
+                });
+            }
+            PanelKind::Single if shift => {
+                shift = false;
+                spreads.push(Spread {
+                    panels: vec![panel],
+                });
+            }
+            PanelKind::Single => match pending.take() {
+                Some(single) => spreads.push(Spread {
+                    panels: vec![single, panel],
+                }),
+                None => pending = Some(panel),
+            },
+        }
+    }
+
+    if let Some(single) = pending {
+        spreads.push(Spread {
+            panels: vec![single],
+        });
+    }
+
+    spreads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a chapter from its page kinds, `C` cover, `S` single and `W` wide spread
+    fn chapter(kinds: &str) -> Vec<LayoutPanel> {
+        kinds
+            .chars()
+            .enumerate()
+            .map(|(index, kind)| {
+                let (width, height) = match kind {
+                    'W' => (1600, 1100),
+                    _ => (800, 1100),
+                };
+                let panel_kind = PanelKind::classify(index, width, height);
+                assert_eq!(
+                    panel_kind,
+                    match kind {
+                        'C' => PanelKind::Cover,
+                        'W' => PanelKind::Spread,
+                        _ => PanelKind::Single,
+                    },
+                    "{kinds} at {index}"
+                );
+                LayoutPanel {
+                    index,
+                    name: format!("{index:03}.png"),
+                    path: format!("/chapter/{index:03}.png"),
+                    width,
+                    height,
+                    kind: panel_kind,
+                }
+            })
+            .collect()
+    }
+
+    fn indices(spreads: &[Spread]) -> Vec<Vec<usize>> {
+        spreads
+            .iter()
+            .map(|spread| spread.panels.iter().map(|panel| panel.index).collect())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_panels() {
+        for (index, width, height, kind) in [
+            (0, 800, 1100, PanelKind::Cover),
+            (1, 800, 1100, PanelKind::Single),
+            (1, 1600, 1100, PanelKind::Spread),
+            (0, 1600, 1100, PanelKind::Spread),
+            // square pages and slightly cropped scans aren't spreads
+            (1, 1000, 1000, PanelKind::Single),
+            (1, 1200, 1000, PanelKind::Single),
+            (1, 1201, 1000, PanelKind::Spread),
+            (1, 0, 0, PanelKind::Single),
+            (0, 0, 1100, PanelKind::Single),
+        ] {
+            assert_eq!(
+                PanelKind::classify(index, width, height),
+                kind,
+                "{index} {width}x{height}"
+            );
+        }
+    }
+
+    #[test]
+    fn pairs_panels() {
+        for (kinds, offset, expected) in [
+            ("CSSSS", false, vec![vec![0], vec![1, 2], vec![3, 4]]),
+            // an odd count leaves the last page alone
+            ("CSSS", false, vec![vec![0], vec![1, 2], vec![3]]),
+            ("CWSSS", false, vec![vec![0], vec![1], vec![2, 3], vec![4]]),
+            // a spread in the middle, the page before it has no partner
+            (
+                "CSSSWSS",
+                false,
+                vec![vec![0], vec![1, 2], vec![3], vec![4], vec![5, 6]],
+            ),
+            (
+                "CSSWSS",
+                false,
+                vec![vec![0], vec![1, 2], vec![3], vec![4, 5]],
+            ),
+            ("CWW", false, vec![vec![0], vec![1], vec![2]]),
+            // the offset shows the first single page alone
+            ("CSSSS", true, vec![vec![0], vec![1], vec![2, 3], vec![4]]),
+            (
+                "CSSWSS",
+                true,
+                vec![vec![0], vec![1], vec![2], vec![3], vec![4, 5]],
+            ),
+            ("", false, vec![]),
+        ] {
+            assert_eq!(
+                indices(&pair_panels(chapter(kinds), offset)),
+                expected,
+                "{kinds} offset {offset}"
+            );
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use crate::manga::{get_panel_image_dimensions, split_path_parts, MangaFolder, MangaPanel};
-use chrono::Datelike;
-use chrono::{Local, NaiveDateTime};
+use std::collections::BTreeMap;
+
+use crate::error::{AppError, AppResult};
+use crate::manga::{MangaFolder, MangaPanel};
+use crate::sessions::total_reading_seconds;
+use crate::source::ChapterSource;
+use chrono::{Local, NaiveDate, NaiveDateTime, Weekday};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use tauri::{AppHandle, Manager};
 use tokio::sync::Mutex;
 
-#[derive(Debug, Serialize, Deserialize, Default, sqlx::FromRow)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, sqlx::FromRow)]
 pub struct Stats {
     pub total_manga: u32,
     pub total_panels: u32,
@@ -22,14 +26,8 @@ pub struct MangaStats {
     pub total_panels_remaining: u32,
 }
 
-#[derive(Serialize, Deserialize, Default, sqlx::FromRow)]
-pub struct Chart {
-    watchtime: u32,
-    updated_at: String,
-}
-
 #[tauri::command]
-pub async fn fetch_daily_manga_folders(handle: AppHandle) -> Vec<MangaFolder> {
+pub async fn fetch_daily_manga_folders(handle: AppHandle) -> AppResult<Vec<MangaFolder>> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     // fetch all manga folders created + updated today
@@ -40,171 +38,103 @@ pub async fn fetch_daily_manga_folders(handle: AppHandle) -> Vec<MangaFolder> {
 ";
 
     let mut daily_manga: Vec<MangaFolder> = Vec::new();
-    let manga_folders: Vec<MangaFolder> = sqlx::query_as(query).fetch_all(&pool).await.unwrap();
+    let manga_folders: Vec<MangaFolder> = sqlx::query_as(query).fetch_all(&pool).await?;
 
     for folder in manga_folders {
         // parse created_at and updated_at from sqlite 'localtime'
 
-        let updated_at =
-            NaiveDateTime::parse_from_str(&folder.updated_at, "%Y-%m-%d %H:%M:%S").unwrap();
-        let created_at =
-            NaiveDateTime::parse_from_str(&folder.created_at, "%Y-%m-%d %H:%M:%S").unwrap();
+        // a malformed timestamp is treated as "not today" instead of failing the whole list
+        let updated_at = NaiveDateTime::parse_from_str(&folder.updated_at, "%Y-%m-%d %H:%M:%S");
+        let created_at = NaiveDateTime::parse_from_str(&folder.created_at, "%Y-%m-%d %H:%M:%S");
 
         // check if created_at and updated_at are today
 
         let today = Local::now().naive_local().date();
 
-        if updated_at.date() == today || created_at.date() == today {
-            daily_manga.push(folder);
-        } else {
-            println!("Not today: {}", folder.title);
-        }
-    }
-
-    daily_manga
-}
+        let is_today = |date: Result<NaiveDateTime, chrono::ParseError>| {
+            date.is_ok_and(|date| date.date() == today)
+        };
 
-async fn read_manga_folder_dirs(folder_path: &String, pool: SqlitePool) {
-    let image_formats = [
-        "jpg", "jpeg", "png", "gif", "bmp", "ico", "tif", "tiff", "webp", "svg", "pdf",
-    ];
-
-    match std::fs::read_dir(folder_path) {
-        Ok(parent) => {
-            for file in parent {
-                let entry = file.unwrap();
-                let file_name = entry.file_name();
-                let file_path = entry.path();
-
-                if file_path.is_dir() {
-                    let path_str = file_path.to_str().unwrap().to_string();
-                    Box::pin(read_manga_folder_dirs(&path_str, pool.clone())).await;
-                } else {
-                    for format in &image_formats {
-                        if file_name.to_str().unwrap().to_lowercase().contains(format) {
-                            let path_str = file_path.to_str().unwrap();
-                            insert_or_ignore_panel(path_str, pool.clone()).await;
-                        }
-                    }
-                }
-            }
-        }
-        Err(err) => {
-            eprintln!("Error Reading Dir: {} -> {}", folder_path, err);
+        if is_today(updated_at) || is_today(created_at) {
+            daily_manga.push(folder);
         }
     }
-}
-
-async fn insert_or_ignore_panel(path: &str, pool: SqlitePool) {
-    let uuid = uuid::Uuid::new_v4().to_string();
-    // gets the parent, file name, and extension of the path
-    let split_path = split_path_parts(path);
-    // get the width and height of the panel image
-    let (width, height) = get_panel_image_dimensions(path);
 
-    sqlx::query(
-        "INSERT OR IGNORE INTO manga_panel (
-    id,
-    title,
-    full_path,
-    is_read,
-    width,
-    height,
-    zoom_level,
-    created_at,
-    updated_at
-    )
-    VALUES (
-    ?, ?, ?, ?, ?, ?, ?,
-    datetime('now', 'localtime'),
-    datetime('now', 'localtime'))",
-    )
-    .bind(uuid)
-    .bind(split_path.file_name)
-    .bind(path)
-    .bind(false)
-    .bind(width)
-    .bind(height)
-    .bind(0)
-    .execute(&pool)
-    .await
-    .unwrap();
+    Ok(daily_manga)
 }
 
 #[tauri::command]
-pub async fn create_manga_stats(handle: AppHandle, folder_path: String) -> MangaStats {
+pub async fn create_manga_stats(handle: AppHandle, folder_path: String) -> AppResult<MangaStats> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
     // fetch all manga panels
-    let manga_panels: Vec<MangaPanel> =
-        sqlx::query_as("SELECT * FROM manga_panel WHERE full_path LIKE ? || '%'")
-            .bind(&folder_path)
-            .fetch_all(&pool)
-            .await
-            .unwrap();
+    let manga_panels: Vec<MangaPanel> = sqlx::query_as(
+        "SELECT manga_panel.* FROM manga_panel
+        INNER JOIN manga_folder ON manga_folder.id = manga_panel.folder_id
+        WHERE manga_folder.full_path = ?",
+    )
+    .bind(&folder_path)
+    .fetch_all(&pool)
+    .await?;
 
     //println!("Folder path: {}", manga_panels.len());
 
     let (total, total_read, total_remaining) = count_manga_panels(&folder_path, &manga_panels);
 
-    MangaStats {
+    Ok(MangaStats {
         total_panels: total,
         total_panels_read: total_read,
         total_panels_remaining: total_remaining,
-    }
+    })
 }
 
-pub async fn create_global_stats(pool: &SqlitePool) -> Stats {
+pub async fn create_global_stats(pool: &SqlitePool) -> AppResult<Stats> {
     // fetch all manga folders
     let manga_folders: Vec<MangaFolder> = sqlx::query_as("SELECT * FROM manga_folder")
         .fetch_all(pool)
-        .await
-        .unwrap();
-
-    let total_manga = manga_folders.len() as u32;
-    let mut total_time_spent_reading: u32 = 0;
+        .await?;
 
-    // count total panels, total panels read, total panels remaining
-    for folder in &manga_folders {
-        read_manga_folder_dirs(&folder.full_path, pool.clone()).await;
-        total_time_spent_reading += folder.time_spent_reading;
-    }
+    // folders the watcher marked as missing still hold reading time, but aren't counted
+    let total_manga = manga_folders
+        .iter()
+        .filter(|folder| !folder.is_missing)
+        .count() as u32;
+    let total_time_spent_reading = total_reading_seconds(pool).await?;
 
-    // fetch all manga panels
-    let manga_panels: Vec<MangaPanel> = sqlx::query_as("SELECT * FROM manga_panel")
-        .fetch_all(pool)
-        .await
-        .unwrap();
+    // the library scanner keeps manga_panel up to date, so the totals are counted from there
+    let manga_panels: Vec<MangaPanel> =
+        sqlx::query_as("SELECT * FROM manga_panel WHERE is_missing = 0")
+            .fetch_all(pool)
+            .await?;
 
     let (total_panels, total_panels_read, total_panels_remaining) =
         count_global_manga_panels(&manga_panels);
 
-    // reset time spent reading for every folder after counting
-    sqlx::query("UPDATE manga_folder SET time_spent_reading = 0")
-        .execute(pool)
-        .await
-        .unwrap();
-
-    Stats {
+    Ok(Stats {
         total_manga,
         total_panels,
         total_panels_read,
         total_panels_remaining,
         total_time_spent_reading,
-    }
+    })
 }
 
 #[tauri::command]
-pub async fn update_global_stats(handle: AppHandle) -> Stats {
+pub async fn update_global_stats(handle: AppHandle) -> AppResult<Stats> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+    refresh_global_stats(&pool).await
+}
+
+/// Recounts the totals from the database and caches them in the `stats` table.
+pub async fn refresh_global_stats(pool: &SqlitePool) -> AppResult<Stats> {
     let mut is_stale: bool = false;
 
     let old_stats: Stats = sqlx::query_as("SELECT * FROM stats")
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or_default();
 
-    let mut new_stats = create_global_stats(&pool).await;
+    let new_stats = create_global_stats(pool).await?;
 
     let mut old_stats_vec: Vec<u32> = Vec::new();
     let mut new_stats_vec: Vec<u32> = Vec::new();
@@ -219,9 +149,7 @@ pub async fn update_global_stats(handle: AppHandle) -> Stats {
     new_stats_vec.push(new_stats.total_panels);
     new_stats_vec.push(new_stats.total_panels_read);
     new_stats_vec.push(new_stats.total_panels_remaining);
-
-    new_stats_vec.push(new_stats.total_time_spent_reading + old_stats.total_time_spent_reading);
-    new_stats.total_time_spent_reading += old_stats.total_time_spent_reading;
+    new_stats_vec.push(new_stats.total_time_spent_reading);
 
     for i in 0..old_stats_vec.len() {
         if old_stats_vec[i] != new_stats_vec[i] {
@@ -250,14 +178,13 @@ pub async fn update_global_stats(handle: AppHandle) -> Stats {
         .bind(new_stats.total_panels_read)
         .bind(new_stats.total_panels_remaining)
         .bind(new_stats.total_time_spent_reading)
-        .execute(&pool)
-        .await
-        .unwrap();
+        .execute(pool)
+        .await?;
 
-        return new_stats;
+        return Ok(new_stats);
     }
 
-    old_stats
+    Ok(old_stats)
 }
 
 fn count_global_manga_panels(manga_panels: &Vec<MangaPanel>) -> (u32, u32, u32) {
@@ -282,20 +209,11 @@ fn count_global_manga_panels(manga_panels: &Vec<MangaPanel>) -> (u32, u32, u32)
 }
 
 fn count_manga_panels(manga_folder_dir: &str, manga_panels: &Vec<MangaPanel>) -> (u32, u32, u32) {
-    let file_types = ["jpg", "jpeg", "png", "webp"];
-    let manga_dir = std::fs::read_dir(manga_folder_dir).unwrap();
-    let mut total_panels: Vec<String> = Vec::new();
+    let total_panels: Vec<String> = ChapterSource::open(manga_folder_dir)
+        .and_then(|source| source.panel_paths().ok())
+        .unwrap_or_default();
     let mut total_panels_read: Vec<String> = Vec::new();
 
-    for entry in manga_dir {
-        let panel_path = entry.unwrap().path();
-        let file_type = panel_path.extension().unwrap().to_str().unwrap();
-
-        if file_types.contains(&file_type) {
-            total_panels.push(panel_path.to_str().unwrap().to_string());
-        }
-    }
-
     for panel in manga_panels {
         if panel.is_read && total_panels.contains(&panel.full_path) {
             //println!("Panel is read: {}", panel.full_path);
@@ -322,127 +240,443 @@ fn count_manga_panels(manga_folder_dir: &str, manga_panels: &Vec<MangaPanel>) ->
     )
 }
 
-#[tauri::command]
-pub async fn create_chart_stats(
-    range: String,
-    days_in_month: Option<u8>,
-    handle: AppHandle,
-) -> Vec<f32> {
-    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+/// Emitted with the daily `GoalProgress` when a reading session reaches the daily goal.
+pub const GOAL_REACHED_EVENT: &str = "goal://reached";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum GoalPeriod {
+    Daily,
+    // weeks start on monday, like the week buckets of the chart
+    Weekly,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum GoalUnit {
+    Minutes,
+    Pages,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ReadingGoal {
+    pub period: GoalPeriod,
+    pub unit: GoalUnit,
+    pub target: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GoalProgress {
+    pub goal: ReadingGoal,
+    pub progress: u32,
+    pub is_complete: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streaks {
+    pub current: u32,
+    pub longest: u32,
+}
 
-    //let mut updated_this_week: Vec<Chart> = Vec::new();
-    let mut final_data: Vec<f32> = Vec::new();
+#[derive(Debug, Serialize, Clone)]
+pub struct GoalsOverview {
+    pub goals: Vec<GoalProgress>,
+    pub streaks: Streaks,
+}
 
-    let today = chrono::Local::now().naive_local().date();
-    let current_year = chrono::Local::now().year();
-    let current_month = today.month0();
+/// Reading time and pages turned, summed from the sessions started on a day.
+#[derive(Debug, Clone, Copy, Default)]
+struct DayTotals {
+    seconds: u32,
+    pages_turned: u32,
+}
 
-    if range == "daily" {
-        final_data = vec![0.0; 7];
-    } else if range == "weekly" {
-        final_data = vec![0.0; days_in_month.unwrap() as usize - 1];
-    } else if range == "monthly" {
-        final_data = vec![0.0; 11];
+impl DayTotals {
+    fn add(self, other: Self) -> Self {
+        Self {
+            seconds: self.seconds + other.seconds,
+            pages_turned: self.pages_turned + other.pages_turned,
+        }
     }
 
-    {
-        let data: Vec<Chart> = sqlx::query_as("SELECT * FROM chart ORDER BY updated_at DESC")
-            .fetch_all(&pool)
-            .await
-            .unwrap();
+    fn amount(&self, unit: GoalUnit) -> u32 {
+        match unit {
+            GoalUnit::Minutes => self.seconds / 60,
+            GoalUnit::Pages => self.pages_turned,
+        }
+    }
+}
 
-        for entry in &data {
-            let last_watched_at =
-                chrono::NaiveDate::parse_from_str(&entry.updated_at, "%Y-%m-%d").unwrap();
-
-            if range == "daily" {
-                let week = today.week(chrono::Weekday::Mon);
-                let days = week.days();
-
-                if days.contains(&last_watched_at) {
-                    let weekday_index = last_watched_at.weekday().num_days_from_sunday();
-                    //println!("{}", weekday_index);
-                    final_data[weekday_index as usize] =
-                        ((entry.watchtime as f32 / 3600.0) * 100.0).round() / 100.0;
-                }
-            }
-
-            if range == "weekly" {
-                let split_date: Vec<&str> = entry.updated_at.split('-').collect();
-                let mut _month: u8 = 0;
-
-                {
-                    let split_month: Vec<&str> = split_date[1]
-                        .split("")
-                        .filter(|str| !str.is_empty())
-                        .collect();
-                    if split_month[0] == "0" {
-                        _month = split_month[1].parse().unwrap();
-                    } else {
-                        _month = split_date[1].parse().unwrap();
-                    }
-                }
-
-                if current_month as u8 + 1 == _month {
-                    let mut _day: usize = 0;
-
-                    let split_day: Vec<&str> = split_date[2]
-                        .split("")
-                        .filter(|str| !str.is_empty())
-                        .collect();
-                    if split_day[0] == "0" {
-                        _day = split_day[1].parse().unwrap();
-                    } else {
-                        _day = split_date[2].parse().unwrap();
-                    }
-
-                    final_data[_day - 1] =
-                        ((entry.watchtime as f32 / 3600.0) * 100.0).round() / 100.0;
-                }
-            }
-
-            if range == "monthly" && last_watched_at.year() == current_year {
-                let split_date: Vec<&str> = entry.updated_at.split('-').collect();
-                let mut _month: u8 = 0;
-
-                {
-                    let split_month: Vec<&str> = split_date[1]
-                        .split("")
-                        .filter(|str| !str.is_empty())
-                        .collect();
-                    if split_month[0] == "0" {
-                        _month = split_month[1].parse().unwrap();
-                    } else {
-                        _month = split_date[1].parse().unwrap();
-                    }
-                }
-
-                final_data[_month as usize - 1] +=
-                    ((entry.watchtime as f32 / 3600.0) * 100.0).round() / 100.0
-            }
+impl GoalProgress {
+    fn new(goal: ReadingGoal, days: &BTreeMap<NaiveDate, DayTotals>, today: NaiveDate) -> Self {
+        let first_day = match goal.period {
+            GoalPeriod::Daily => today,
+            GoalPeriod::Weekly => today.week(Weekday::Mon).first_day(),
+        };
+        // summed before converting, so minutes split over several days aren't rounded away
+        let progress = days
+            .range(first_day..=today)
+            .fold(DayTotals::default(), |total, (_, day)| total.add(*day))
+            .amount(goal.unit);
+
+        Self {
+            is_complete: progress >= goal.target,
+            progress,
+            goal,
         }
     }
+}
+
+impl Streaks {
+    /// A day counts once the daily goal is met, or once anything is read when there's no daily
+    /// goal. Today not being done yet doesn't break the current streak.
+    fn count(
+        days: &BTreeMap<NaiveDate, DayTotals>,
+        daily_goal: Option<&ReadingGoal>,
+        today: NaiveDate,
+    ) -> Self {
+        let counts = |day: &DayTotals| match daily_goal {
+            Some(goal) => day.amount(goal.unit) >= goal.target,
+            None => day.seconds > 0 || day.pages_turned > 0,
+        };
+
+        let mut longest = 0;
+        let mut run = 0;
+        let mut last_day: Option<NaiveDate> = None;
+        for (day, _) in days.iter().filter(|(_, totals)| counts(totals)) {
+            run = match last_day {
+                Some(last_day) if last_day.succ_opt() == Some(*day) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            last_day = Some(*day);
+        }
 
-    final_data
+        let current = match last_day {
+            Some(last_day) if last_day == today || last_day.succ_opt() == Some(today) => run,
+            _ => 0,
+        };
+
+        Self { current, longest }
+    }
 }
 
 #[tauri::command]
-pub async fn update_chart_watchtime(watch_time: u32, handle: tauri::AppHandle) {
+pub async fn set_reading_goal(
+    period: GoalPeriod,
+    unit: GoalUnit,
+    target: u32,
+    handle: AppHandle,
+) -> AppResult<ReadingGoal> {
     let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
-    let today = chrono::Local::now().naive_local().date();
+
+    if target == 0 {
+        return Err(AppError::InvalidInput(
+            "a reading goal needs a target above zero".into(),
+        ));
+    }
 
     sqlx::query(
-        "INSERT OR IGNORE INTO chart (watchtime, updated_at) VALUES (0, date('now', 'localtime'))",
+        "INSERT INTO reading_goal (period, unit, target, created_at, updated_at)
+        VALUES (?, ?, ?, datetime('now', 'localtime'), datetime('now', 'localtime'))
+        ON CONFLICT (period) DO UPDATE SET
+        unit = excluded.unit,
+        target = excluded.target,
+        updated_at = excluded.updated_at",
     )
+    .bind(period)
+    .bind(unit)
+    .bind(target)
     .execute(&pool)
-    .await
-    .unwrap();
+    .await?;
+
+    let goal = sqlx::query_as("SELECT * FROM reading_goal WHERE period = ?")
+        .bind(period)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(goal)
+}
+
+#[tauri::command]
+pub async fn remove_reading_goal(period: GoalPeriod, handle: AppHandle) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
 
-    // Then, increment the watchtime.
-    sqlx::query("UPDATE chart SET watchtime = watchtime + ? WHERE updated_at = ?")
-        .bind(watch_time)
-        .bind(today.to_string())
+    sqlx::query("DELETE FROM reading_goal WHERE period = ?")
+        .bind(period)
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Progress towards every goal that is set, along with the reading streaks.
+#[tauri::command]
+pub async fn get_goal_progress(handle: AppHandle) -> AppResult<GoalsOverview> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let goals: Vec<ReadingGoal> = sqlx::query_as("SELECT * FROM reading_goal ORDER BY period")
+        .fetch_all(&pool)
+        .await?;
+    let days = daily_totals(None, &pool).await?;
+    let today = Local::now().date_naive();
+
+    let daily_goal = goals.iter().find(|goal| goal.period == GoalPeriod::Daily);
+    let streaks = Streaks::count(&days, daily_goal, today);
+    let goals = goals
+        .into_iter()
+        .map(|goal| GoalProgress::new(goal, &days, today))
+        .collect();
+
+    Ok(GoalsOverview { goals, streaks })
+}
+
+/// Today's progress, `None` when no daily goal is set.
+pub async fn daily_goal_progress(pool: &SqlitePool) -> AppResult<Option<GoalProgress>> {
+    let goal: Option<ReadingGoal> = sqlx::query_as("SELECT * FROM reading_goal WHERE period = ?")
+        .bind(GoalPeriod::Daily)
+        .fetch_optional(pool)
+        .await?;
+    let Some(goal) = goal else {
+        return Ok(None);
+    };
+
+    let today = Local::now().date_naive();
+    let days = daily_totals(Some(today), pool).await?;
+
+    Ok(Some(GoalProgress::new(goal, &days, today)))
+}
+
+/// Totals for every day with a reading session, starting at `since` if given.
+async fn daily_totals(
+    since: Option<NaiveDate>,
+    pool: &SqlitePool,
+) -> AppResult<BTreeMap<NaiveDate, DayTotals>> {
+    let since = since.map(|day| day.to_string());
+    let rows: Vec<(String, u32, u32)> = sqlx::query_as(
+        "SELECT date(started_at) AS day, SUM(seconds), SUM(pages_turned)
+        FROM reading_session
+        WHERE ? IS NULL OR date(started_at) >= ?
+        GROUP BY day",
+    )
+    .bind(&since)
+    .bind(&since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut days = BTreeMap::new();
+    for (day, seconds, pages_turned) in rows {
+        // skip rows with a malformed date rather than failing every goal
+        let Ok(day) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+            eprintln!("Error Parsing Session Date: {}", day);
+            continue;
+        };
+        days.insert(
+            day,
+            DayTotals {
+                seconds,
+                pages_turned,
+            },
+        );
+    }
+
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn day(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    fn goal(period: GoalPeriod, unit: GoalUnit, target: u32) -> ReadingGoal {
+        ReadingGoal {
+            period,
+            unit,
+            target,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn days(minutes: &[(&str, u32)]) -> BTreeMap<NaiveDate, DayTotals> {
+        minutes
+            .iter()
+            .map(|(date, minutes)| {
+                (
+                    day(date),
+                    DayTotals {
+                        seconds: minutes * 60,
+                        pages_turned: 0,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_streaks() {
+        let daily = goal(GoalPeriod::Daily, GoalUnit::Minutes, 10);
+        let cases = [
+            (
+                "read every day up to today",
+                days(&[("2024-03-01", 10), ("2024-03-02", 15), ("2024-03-03", 10)]),
+                Some(&daily),
+                Streaks {
+                    current: 3,
+                    longest: 3,
+                },
+            ),
+            (
+                "today not met yet keeps the streak",
+                days(&[("2024-03-01", 10), ("2024-03-02", 10), ("2024-03-03", 5)]),
+                Some(&daily),
+                Streaks {
+                    current: 2,
+                    longest: 2,
+                },
+            ),
+            (
+                "a missed day starts over",
+                days(&[
+                    ("2024-02-28", 10),
+                    ("2024-02-29", 10),
+                    ("2024-03-02", 10),
+                    ("2024-03-03", 10),
+                ]),
+                Some(&daily),
+                Streaks {
+                    current: 2,
+                    longest: 2,
+                },
+            ),
+            (
+                "a day below the goal is missed",
+                days(&[
+                    ("2024-02-29", 10),
+                    ("2024-03-01", 10),
+                    ("2024-03-02", 9),
+                    ("2024-03-03", 10),
+                ]),
+                Some(&daily),
+                Streaks {
+                    current: 1,
+                    longest: 2,
+                },
+            ),
+            (
+                "nothing since the day before yesterday",
+                days(&[("2024-02-29", 10), ("2024-03-01", 10)]),
+                Some(&daily),
+                Streaks {
+                    current: 0,
+                    longest: 2,
+                },
+            ),
+            (
+                "without a daily goal any reading counts",
+                days(&[("2024-03-01", 1), ("2024-03-02", 1)]),
+                None,
+                Streaks {
+                    current: 2,
+                    longest: 2,
+                },
+            ),
+        ];
+
+        for (name, days, daily_goal, expected) in cases {
+            let streaks = Streaks::count(&days, daily_goal, day("2024-03-03"));
+            assert_eq!(streaks, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn sums_goal_progress_over_the_period() {
+        // 2024-03-04 is a monday
+        let read = days(&[("2024-03-03", 30), ("2024-03-04", 20), ("2024-03-06", 15)]);
+        let cases = [
+            (
+                "daily goal only counts today",
+                GoalPeriod::Daily,
+                "2024-03-06",
+                15,
+                false,
+            ),
+            (
+                "daily goal on a day without reading",
+                GoalPeriod::Daily,
+                "2024-03-05",
+                0,
+                false,
+            ),
+            (
+                "weekly goal starts on monday",
+                GoalPeriod::Weekly,
+                "2024-03-06",
+                35,
+                true,
+            ),
+            (
+                "sunday ends the previous week",
+                GoalPeriod::Weekly,
+                "2024-03-03",
+                30,
+                true,
+            ),
+        ];
+
+        for (name, period, today, progress, is_complete) in cases {
+            let goal = GoalProgress::new(goal(period, GoalUnit::Minutes, 30), &read, day(today));
+            assert_eq!(
+                (goal.progress, goal.is_complete),
+                (progress, is_complete),
+                "{name}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_count_for_the_day_they_started() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO reading_session (id, folder_id, started_at, ended_at, pages_turned, seconds)
+            VALUES
+            ('late', NULL, '2024-03-01 23:50:00', '2024-03-02 00:20:00', 20, 1800),
+            ('early', NULL, '2024-03-02 00:00:00', '2024-03-02 00:05:00', 3, 300),
+            ('broken', NULL, 'not a date', 'not a date', 1, 60)",
+        )
         .execute(&pool)
         .await
         .unwrap();
+
+        let totals: Vec<(NaiveDate, u32, u32)> = daily_totals(None, &pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(day, totals)| (day, totals.seconds, totals.pages_turned))
+            .collect();
+        assert_eq!(
+            totals,
+            [(day("2024-03-01"), 1800, 20), (day("2024-03-02"), 300, 3)]
+        );
+
+        let since = daily_totals(Some(day("2024-03-02")), &pool).await.unwrap();
+        assert_eq!(
+            since.keys().copied().collect::<Vec<_>>(),
+            [day("2024-03-02")]
+        );
+    }
 }
@@ -0,0 +1,274 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::available_parallelism,
+    time::SystemTime,
+};
+
+use image::{codecs::jpeg::JpegEncoder, ImageEncoder};
+use sqlx::SqlitePool;
+use tauri::{
+    http::{header::CONTENT_TYPE, Request, Response, StatusCode},
+    AppHandle, Manager,
+};
+use tokio::{
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+};
+
+use crate::covers::CoverStore;
+use crate::error::{AppError, AppResult, PathContext};
+use crate::source::{
+    forbidden_response, may_serve_panel, read_panel_bytes, request_panel_path, split_archive_path,
+};
+
+/// The scheme the webview uses to request thumbnails, see `thumbnail_protocol`.
+/// Strip sized thumbnails are requested with `?size=strip`.
+pub const THUMBNAIL_PROTOCOL: &str = "thumbnail";
+
+// thumbnails are jpeg, the image crate only encodes lossless webp which is larger than the original
+const THUMBNAIL_QUALITY: u8 = 80;
+
+// least recently used thumbnails are removed once the cache grows past this
+const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThumbnailSize {
+    Cover,
+    Strip,
+}
+
+impl ThumbnailSize {
+    fn from_query(query: Option<&str>) -> Self {
+        match query {
+            Some(query) if query.split('&').any(|pair| pair == "size=strip") => Self::Strip,
+            _ => Self::Cover,
+        }
+    }
+
+    /// The box the thumbnail is scaled down to fit, aspect ratio is kept.
+    fn bounds(self) -> (u32, u32) {
+        match self {
+            Self::Cover => (320, 480),
+            Self::Strip => (120, 180),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cover => "cover",
+            Self::Strip => "strip",
+        }
+    }
+}
+
+/// Managed state pointing at the thumbnail directory in the app data dir.
+/// Decoding is limited to one thumbnail per core so a full dashboard doesn't starve the reader.
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    workers: Semaphore,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf) -> Self {
+        let workers = available_parallelism().map(|n| n.get()).unwrap_or(2);
+        Self {
+            dir,
+            workers: Semaphore::new(workers),
+        }
+    }
+
+    /// Returns the cached thumbnail of a panel, creating it first if the panel changed.
+    pub async fn get_or_create(&self, panel_path: &str, size: ThumbnailSize) -> AppResult<Vec<u8>> {
+        let panel = panel_path.to_string();
+        let cache_path = tokio::task::spawn_blocking({
+            let dir = self.dir.clone();
+            move || cache_path(&dir, &panel, size)
+        })
+        .await?
+        .with_path(panel_path)?;
+
+        if let Ok(bytes) = tokio::fs::read(&cache_path).await {
+            // the modified time doubles as the last use for eviction
+            if let Ok(file) = File::options().write(true).open(&cache_path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            return Ok(bytes);
+        }
+
+        // the semaphore is never closed, so acquiring only waits for a free worker
+        let _permit = self.workers.acquire().await.ok();
+        let panel = panel_path.to_string();
+        tokio::task::spawn_blocking(move || create_thumbnail(&panel, size, &cache_path)).await?
+    }
+}
+
+#[tauri::command]
+pub async fn rebuild_thumbnails(handle: AppHandle) -> AppResult<usize> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+    let cache = handle.state::<Arc<ThumbnailCache>>().inner().clone();
+
+    let dir = cache.dir.clone();
+    tokio::task::spawn_blocking(move || {
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)
+    })
+    .await?
+    .with_path(&cache.dir.to_string_lossy())?;
+
+    let covers: Vec<String> = sqlx::query_scalar(
+        "SELECT COALESCE(custom_cover_path, cover_panel_path) AS cover FROM parent_folder
+        WHERE cover IS NOT NULL
+        UNION
+        SELECT COALESCE(custom_cover_path, cover_panel_path) AS cover FROM manga_folder
+        WHERE cover IS NOT NULL AND is_missing = 0",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut thumbnails = JoinSet::new();
+    for cover in covers {
+        let cache = cache.clone();
+        thumbnails.spawn(async move {
+            let result = cache.get_or_create(&cover, ThumbnailSize::Cover).await;
+            (cover, result)
+        });
+    }
+
+    let mut rebuilt = 0;
+    while let Some(joined) = thumbnails.join_next().await {
+        match joined? {
+            (_, Ok(_)) => rebuilt += 1,
+            (cover, Err(e)) => eprintln!("Error Creating Thumbnail: {} -> {}", cover, e),
+        }
+    }
+
+    Ok(rebuilt)
+}
+
+/// Trims the cache back under its size limit on the blocking pool.
+pub fn spawn_thumbnail_eviction(handle: AppHandle) {
+    let dir = handle.state::<Arc<ThumbnailCache>>().dir.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = evict_thumbnails(&dir, MAX_CACHE_BYTES) {
+            eprintln!("Error Evicting Thumbnails: {} -> {}", dir.display(), e);
+        }
+    });
+}
+
+/// Serves thumbnails to the webview, the frontend builds the url with
+/// `convertFileSrc(panel.full_path, "thumbnail")`.
+pub async fn thumbnail_protocol(
+    handle: &AppHandle,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let panel_path = request_panel_path(request);
+    // custom covers are copied into the app data dir, outside of every library root
+    let is_custom_cover = handle.state::<CoverStore>().holds(&panel_path);
+    if !is_custom_cover && !may_serve_panel(handle, &panel_path).await {
+        return forbidden_response();
+    }
+    let size = ThumbnailSize::from_query(request.uri().query());
+    let cache = handle.state::<Arc<ThumbnailCache>>().inner().clone();
+
+    match cache.get_or_create(&panel_path, size).await {
+        Ok(bytes) => Response::builder()
+            .header(CONTENT_TYPE, "image/jpeg")
+            .body(bytes)
+            .unwrap(),
+        Err(e) => {
+            eprintln!("Error Creating Thumbnail: {} -> {}", panel_path, e);
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Vec::new())
+                .unwrap()
+        }
+    }
+}
+
+/// Thumbnails are keyed by the panel path and the size and modified time of the file
+/// holding it, so a replaced panel or archive gets a new thumbnail.
+fn cache_path(dir: &Path, panel_path: &str, size: ThumbnailSize) -> Result<PathBuf, io::Error> {
+    let file_path = match split_archive_path(panel_path) {
+        Some((archive_path, _)) => archive_path,
+        None => PathBuf::from(panel_path),
+    };
+    let metadata = fs::metadata(&file_path)?;
+
+    // the key only has to be stable for one build, a new std hasher just rebuilds the cache
+    let mut hasher = DefaultHasher::new();
+    panel_path.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified()?.hash(&mut hasher);
+
+    Ok(dir.join(format!("{:016x}-{}.jpg", hasher.finish(), size.name())))
+}
+
+fn create_thumbnail(
+    panel_path: &str,
+    size: ThumbnailSize,
+    cache_path: &Path,
+) -> AppResult<Vec<u8>> {
+    let bytes = read_panel_bytes(panel_path).with_path(panel_path)?;
+    let (width, height) = size.bounds();
+    let thumbnail = image::load_from_memory(&bytes)?
+        .thumbnail(width, height)
+        .into_rgb8();
+
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, THUMBNAIL_QUALITY).write_image(
+        &thumbnail,
+        thumbnail.width(),
+        thumbnail.height(),
+        image::ExtendedColorType::Rgb8,
+    )?;
+
+    // written under a temporary name first so a half written file is never served
+    let cache_dir = cache_path
+        .parent()
+        .ok_or_else(|| AppError::not_found(cache_path.to_string_lossy()))?;
+    fs::create_dir_all(cache_dir).with_path(&cache_dir.to_string_lossy())?;
+    let partial_path = cache_path.with_extension("part");
+    fs::write(&partial_path, &encoded).with_path(&partial_path.to_string_lossy())?;
+    fs::rename(&partial_path, cache_path).with_path(&cache_path.to_string_lossy())?;
+
+    Ok(encoded)
+}
+
+/// Removes the least recently used thumbnails until the cache fits in `max_bytes`.
+/// Returns how many were removed.
+fn evict_thumbnails(dir: &Path, max_bytes: u64) -> Result<usize, io::Error> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut thumbnails = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            thumbnails.push((used, metadata.len(), entry.path()));
+        }
+    }
+
+    let mut total: u64 = thumbnails.iter().map(|(_, len, _)| len).sum();
+    thumbnails.sort_by_key(|(used, _, _)| *used);
+
+    let mut removed = 0;
+    for (_, len, path) in thumbnails {
+        if total <= max_bytes {
+            break;
+        }
+        fs::remove_file(&path)?;
+        total -= len;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
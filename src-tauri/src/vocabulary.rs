@@ -0,0 +1,1035 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use regex::Regex;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::dictionary::WordFinder;
+use crate::error::{AppError, AppResult, PathContext};
+use crate::global::{get_manga_folder_by_path, get_parent_folder_by_path};
+
+pub const VOCABULARY_PROGRESS_EVENT: &str = "vocabulary://progress";
+
+// frequency ranks each level roughly covers, none of the dictionaries carry the levels
+const JLPT_RANKS: [(JlptLevel, i64); 5] = [
+    (JlptLevel::N5, 800),
+    (JlptLevel::N4, 1500),
+    (JlptLevel::N3, 3750),
+    (JlptLevel::N2, 6000),
+    (JlptLevel::N1, 10000),
+];
+// the highest rank of every frequency band, ranks past the last one share a band
+const FREQUENCY_BANDS: [i64; 6] = [1000, 2000, 5000, 10000, 20000, 50000];
+
+static HTML_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+// furigana the way anki's japanese support writes it, `日本[にほん]`
+static FURIGANA_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[[^\]]*\]").unwrap());
+
+/// A JLPT level estimated from a word's frequency rank.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JlptLevel {
+    N5,
+    N4,
+    N3,
+    N2,
+    N1,
+    BeyondN1,
+    // no frequency dictionary ranks the word
+    Unranked,
+}
+
+impl JlptLevel {
+    const ALL: [Self; 7] = [
+        Self::N5,
+        Self::N4,
+        Self::N3,
+        Self::N2,
+        Self::N1,
+        Self::BeyondN1,
+        Self::Unranked,
+    ];
+
+    fn estimate(rank: Option<i64>) -> Self {
+        let Some(rank) = rank else {
+            return Self::Unranked;
+        };
+        JLPT_RANKS
+            .iter()
+            .find(|(_, max_rank)| rank <= *max_rank)
+            .map_or(Self::BeyondN1, |(level, _)| *level)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LevelCount {
+    pub level: JlptLevel,
+    pub words: u32,
+}
+
+/// The words ranked from `min_rank` to `max_rank`, the last band has no upper bound.
+#[derive(Debug, Serialize, Clone)]
+pub struct FrequencyBand {
+    pub min_rank: i64,
+    pub max_rank: Option<i64>,
+    pub words: u32,
+}
+
+/// What a chapter, or every chapter below a folder, asks of a learner. Counts are of
+/// dictionary words, text no dictionary has a word for is left out.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct VocabularyStats {
+    // chapters with text that went into the counts
+    pub chapters: u32,
+    pub token_count: u32,
+    pub unique_words: u32,
+    pub unique_kanji: u32,
+    pub known_words: u32,
+    pub known_tokens: u32,
+    // the share of tokens that are known words, from 0 to 1
+    pub comprehension: f64,
+    pub jlpt: Vec<LevelCount>,
+    pub frequency_bands: Vec<FrequencyBand>,
+    pub unranked_words: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SeriesComprehension {
+    pub id: String,
+    pub title: String,
+    pub full_path: String,
+    pub stats: VocabularyStats,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VocabularyProgress {
+    pub analyzed: usize,
+    pub total: usize,
+}
+
+// one word of the text, `Unknown` holds a run no dictionary has a word for
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word { expression: String, reading: String },
+    Unknown(String),
+}
+
+#[derive(sqlx::FromRow)]
+struct WordRow {
+    occurrences: u32,
+    frequency_rank: Option<i64>,
+    is_known: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct SeriesWordRow {
+    series_id: String,
+    #[sqlx(flatten)]
+    word: WordRow,
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}' | '々')
+}
+
+// kana, the katakana long vowel mark included, and kanji
+fn is_japanese(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309f}' | '\u{30a0}'..='\u{30ff}') || is_kanji(c)
+}
+
+/// Splits text into words by taking the longest dictionary word at every position.
+/// Punctuation and latin text end a run and aren't part of any token.
+async fn tokenize(text: &str, pool: &SqlitePool) -> AppResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+
+    let chars: Vec<char> = text.chars().collect();
+    for run in chars
+        .split(|c| !is_japanese(*c))
+        .filter(|run| !run.is_empty())
+    {
+        let finder = WordFinder::new(run, pool).await?;
+        let mut unknown = String::new();
+        let mut position = 0;
+        while position < run.len() {
+            match finder.longest_word(position) {
+                Some(word) => {
+                    if !unknown.is_empty() {
+                        tokens.push(Token::Unknown(std::mem::take(&mut unknown)));
+                    }
+                    tokens.push(Token::Word {
+                        expression: word.expression,
+                        reading: word.reading,
+                    });
+                    position += word.length;
+                }
+                None => {
+                    unknown.push(run[position]);
+                    position += 1;
+                }
+            }
+        }
+        if !unknown.is_empty() {
+            tokens.push(Token::Unknown(unknown));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// changes whenever a dictionary is imported, reimported or removed
+async fn dictionaries_signature(pool: &SqlitePool) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(group_concat(id || '@' || updated_at, ','), '')
+        FROM (SELECT id, updated_at FROM dictionary WHERE is_importing = 0 ORDER BY id)",
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Splits a chapter's text into words and caches them, unless the cached words are still
+/// current. Returns `false` when nothing changed, the analysis of a chapter that lost its
+/// text is removed.
+pub async fn analyze_chapter(folder_id: &str, pool: &SqlitePool) -> AppResult<bool> {
+    let Some(text_imported_at): Option<Option<String>> =
+        sqlx::query_scalar("SELECT imported_at FROM text_source WHERE manga_folder_id = ?")
+            .bind(folder_id)
+            .fetch_optional(pool)
+            .await?
+    else {
+        let removed = sqlx::query("DELETE FROM vocabulary_analysis WHERE manga_folder_id = ?")
+            .bind(folder_id)
+            .execute(pool)
+            .await?;
+        return Ok(removed.rows_affected() > 0);
+    };
+    let dictionaries = dictionaries_signature(pool).await?;
+
+    let is_current: bool = sqlx::query_scalar(
+        "SELECT EXISTS (
+            SELECT 1 FROM vocabulary_analysis
+            WHERE manga_folder_id = ? AND text_imported_at IS ? AND dictionaries = ?
+        )",
+    )
+    .bind(folder_id)
+    .bind(&text_imported_at)
+    .bind(&dictionaries)
+    .fetch_one(pool)
+    .await?;
+    if is_current {
+        return Ok(false);
+    }
+
+    let blocks: Vec<String> = sqlx::query_scalar(
+        "SELECT text_block.lines FROM text_block
+        INNER JOIN text_source ON text_source.id = text_block.source_id
+        WHERE text_source.manga_folder_id = ?",
+    )
+    .bind(folder_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut token_count: u32 = 0;
+    // word to its reading, whether a dictionary has it and how often it occurs
+    let mut words: HashMap<String, (Option<String>, bool, u32)> = HashMap::new();
+    let mut kanji: HashMap<char, u32> = HashMap::new();
+    for lines in blocks {
+        let text = serde_json::from_str::<Vec<String>>(&lines)
+            .unwrap_or_default()
+            .concat();
+
+        for c in text.chars().filter(|c| is_kanji(*c)) {
+            *kanji.entry(c).or_default() += 1;
+        }
+        for token in tokenize(&text, pool).await? {
+            let (word, reading, in_dictionary) = match token {
+                Token::Word {
+                    expression,
+                    reading,
+                } => (expression, Some(reading), true),
+                Token::Unknown(text) => (text, None, false),
+            };
+            if in_dictionary {
+                token_count += 1;
+            }
+            words.entry(word).or_insert((reading, in_dictionary, 0)).2 += 1;
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM vocabulary_analysis WHERE manga_folder_id = ?")
+        .bind(folder_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO vocabulary_analysis
+        (manga_folder_id, text_imported_at, dictionaries, token_count, analyzed_at)
+        VALUES (?, ?, ?, ?, datetime('now', 'localtime'))",
+    )
+    .bind(folder_id)
+    .bind(&text_imported_at)
+    .bind(&dictionaries)
+    .bind(token_count)
+    .execute(&mut *tx)
+    .await?;
+
+    for (word, (reading, in_dictionary, occurrences)) in &words {
+        // ranks are ascending, the best rank is the lowest. occurrence counts can't be
+        // compared with ranks, so those dictionaries are left out
+        sqlx::query(
+            "INSERT INTO vocabulary_word
+            (manga_folder_id, word, reading, in_dictionary, occurrences, frequency_rank)
+            VALUES (?, ?, ?, ?, ?, CASE WHEN ? THEN (
+                SELECT MIN(value) FROM dictionary_frequency
+                INNER JOIN dictionary ON dictionary.id = dictionary_frequency.dictionary_id
+                WHERE dictionary.frequency_mode = 'rank-based' AND dictionary.is_importing = 0
+                AND kind = 'term' AND expression = ?
+            ) END)",
+        )
+        .bind(folder_id)
+        .bind(word)
+        .bind(reading)
+        .bind(in_dictionary)
+        .bind(occurrences)
+        .bind(in_dictionary)
+        .bind(word)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for (character, occurrences) in &kanji {
+        sqlx::query(
+            "INSERT INTO vocabulary_kanji (manga_folder_id, character, occurrences)
+            VALUES (?, ?, ?)",
+        )
+        .bind(folder_id)
+        .bind(character.to_string())
+        .bind(occurrences)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// The chapter at `folder_path`, or every chapter below the series or volume at it.
+async fn chapter_ids(folder_path: &str, pool: &SqlitePool) -> AppResult<Vec<String>> {
+    if let Some(chapter) = get_manga_folder_by_path(folder_path, pool).await? {
+        return Ok(vec![chapter.id]);
+    }
+    let parent = get_parent_folder_by_path(folder_path, pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(folder_path))?;
+
+    Ok(series_chapter_ids(&parent.id, pool).await?)
+}
+
+async fn series_chapter_ids(
+    parent_id: &str,
+    pool: &SqlitePool,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "WITH RECURSIVE folder(id) AS (
+            SELECT id FROM parent_folder WHERE id = ?
+            UNION
+            SELECT parent_folder.id FROM parent_folder
+            INNER JOIN folder ON parent_folder.parent_id = folder.id
+        )
+        SELECT manga_folder.id FROM manga_folder
+        INNER JOIN folder ON manga_folder.parent_id = folder.id",
+    )
+    .bind(parent_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Sums up the cached analyses of the chapters, chapters that weren't analyzed are left out.
+async fn vocabulary_stats(folder_ids: &[String], pool: &SqlitePool) -> AppResult<VocabularyStats> {
+    // the ids are passed as one json array, a series can have more chapters than
+    // sqlite allows bound parameters
+    let ids = serde_json::to_string(folder_ids)?;
+
+    let chapters: u32 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM vocabulary_analysis
+        WHERE manga_folder_id IN (SELECT value FROM json_each(?))",
+    )
+    .bind(&ids)
+    .fetch_one(pool)
+    .await?;
+
+    let unique_kanji: u32 = sqlx::query_scalar(
+        "SELECT COUNT(DISTINCT character) FROM vocabulary_kanji
+        WHERE manga_folder_id IN (SELECT value FROM json_each(?))",
+    )
+    .bind(&ids)
+    .fetch_one(pool)
+    .await?;
+
+    // a word is known by its dictionary form or, for lists kept in kana, its reading
+    let words: Vec<WordRow> = sqlx::query_as(
+        "SELECT
+            SUM(vocabulary_word.occurrences) AS occurrences,
+            MIN(vocabulary_word.frequency_rank) AS frequency_rank,
+            MAX(by_word.word IS NOT NULL OR by_reading.word IS NOT NULL) AS is_known
+        FROM vocabulary_word
+        LEFT JOIN known_word AS by_word ON by_word.word = vocabulary_word.word
+        LEFT JOIN known_word AS by_reading ON by_reading.word = vocabulary_word.reading
+        WHERE vocabulary_word.in_dictionary
+        AND vocabulary_word.manga_folder_id IN (SELECT value FROM json_each(?))
+        GROUP BY vocabulary_word.word",
+    )
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(summarize(chapters, unique_kanji, &words))
+}
+
+fn summarize(chapters: u32, unique_kanji: u32, words: &[WordRow]) -> VocabularyStats {
+    let mut stats = VocabularyStats {
+        chapters,
+        unique_kanji,
+        unique_words: words.len() as u32,
+        ..VocabularyStats::default()
+    };
+
+    let mut levels: HashMap<JlptLevel, u32> = HashMap::new();
+    let mut bands = vec![0; FREQUENCY_BANDS.len() + 1];
+    for word in words {
+        stats.token_count += word.occurrences;
+        if word.is_known {
+            stats.known_words += 1;
+            stats.known_tokens += word.occurrences;
+        }
+
+        *levels
+            .entry(JlptLevel::estimate(word.frequency_rank))
+            .or_default() += 1;
+        match word.frequency_rank {
+            Some(rank) => {
+                let band = FREQUENCY_BANDS
+                    .iter()
+                    .position(|max_rank| rank <= *max_rank)
+                    .unwrap_or(FREQUENCY_BANDS.len());
+                bands[band] += 1;
+            }
+            None => stats.unranked_words += 1,
+        }
+    }
+
+    if stats.token_count > 0 {
+        stats.comprehension = stats.known_tokens as f64 / stats.token_count as f64;
+    }
+    stats.jlpt = JlptLevel::ALL
+        .iter()
+        .map(|level| LevelCount {
+            level: *level,
+            words: levels.get(level).copied().unwrap_or(0),
+        })
+        .collect();
+    stats.frequency_bands = bands
+        .into_iter()
+        .enumerate()
+        .map(|(band, words)| FrequencyBand {
+            min_rank: band
+                .checked_sub(1)
+                .map_or(1, |previous| FREQUENCY_BANDS[previous] + 1),
+            max_rank: FREQUENCY_BANDS.get(band).copied(),
+            words,
+        })
+        .collect();
+
+    stats
+}
+
+/// The word in the first column of a word list or of anki's "Notes in Plain Text" export,
+/// with the markup and furigana anki leaves in fields removed.
+fn parse_known_words(text: &str) -> Vec<String> {
+    let mut separator = None;
+    // columns anki puts before the fields when the export includes them, counted from 1
+    let mut skipped_columns = HashSet::new();
+    let mut words = Vec::new();
+
+    for line in text.lines() {
+        if let Some(header) = line.strip_prefix('#') {
+            if let Some(value) = header.strip_prefix("separator:") {
+                separator = match value.trim().to_lowercase().as_str() {
+                    "tab" => Some('\t'),
+                    "comma" => Some(','),
+                    "semicolon" => Some(';'),
+                    "space" => Some(' '),
+                    "pipe" => Some('|'),
+                    "colon" => Some(':'),
+                    other => other.chars().next(),
+                };
+            } else if let Some((_, column)) = header.split_once(" column:") {
+                if let Ok(column) = column.trim().parse::<usize>() {
+                    skipped_columns.insert(column);
+                }
+            }
+            continue;
+        }
+
+        let separator = separator.unwrap_or('\t');
+        let Some(field) = line
+            .split(separator)
+            .enumerate()
+            .find(|(index, _)| !skipped_columns.contains(&(index + 1)))
+            .map(|(_, field)| field)
+        else {
+            continue;
+        };
+
+        let field = field
+            .trim()
+            .strip_prefix('"')
+            .and_then(|field| field.strip_suffix('"'))
+            .map_or_else(|| field.to_string(), |field| field.replace("\"\"", "\""));
+        let field = HTML_TAG_REGEX.replace_all(&field, "");
+        let field = FURIGANA_REGEX.replace_all(&field, "");
+        let word = field
+            .replace("&nbsp;", "")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+            .replace(' ', "");
+
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+
+    words
+}
+
+/// Analyzes the text of every chapter whose cached words are missing or outdated.
+/// Returns how many chapters were analyzed.
+#[tauri::command]
+pub async fn analyze_library_vocabulary(handle: AppHandle) -> AppResult<usize> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let folder_ids: Vec<String> = sqlx::query_scalar(
+        "SELECT manga_folder_id FROM text_source
+        UNION
+        SELECT manga_folder_id FROM vocabulary_analysis",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let total = folder_ids.len();
+    let mut analyzed = 0;
+    for (index, folder_id) in folder_ids.iter().enumerate() {
+        if analyze_chapter(folder_id, &pool).await? {
+            analyzed += 1;
+        }
+        let progress = VocabularyProgress {
+            analyzed: index + 1,
+            total,
+        };
+        if let Err(e) = handle.emit(VOCABULARY_PROGRESS_EVENT, progress) {
+            eprintln!("Error Emitting {} -> {}", VOCABULARY_PROGRESS_EVENT, e);
+        }
+    }
+
+    Ok(analyzed)
+}
+
+/// The vocabulary of the chapter, series or volume at `folder_path`, analyzing the chapters
+/// whose cached words are missing or outdated first.
+#[tauri::command]
+pub async fn get_vocabulary_stats(
+    folder_path: String,
+    handle: AppHandle,
+) -> AppResult<VocabularyStats> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let folder_ids = chapter_ids(&folder_path, &pool).await?;
+    for folder_id in &folder_ids {
+        analyze_chapter(folder_id, &pool).await?;
+    }
+
+    vocabulary_stats(&folder_ids, &pool).await
+}
+
+/// Every series with analyzed text, the one with the most known words first, to pick what to
+/// read next. Only cached analyses count, `analyze_library_vocabulary` brings them up to date.
+#[tauri::command]
+pub async fn rank_series_by_comprehension(
+    handle: AppHandle,
+) -> AppResult<Vec<SeriesComprehension>> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let mut ranking = series_comprehension(&pool).await?;
+    // ties go to the series with fewer words left to learn
+    ranking.sort_by(|a, b| {
+        b.stats
+            .comprehension
+            .total_cmp(&a.stats.comprehension)
+            .then_with(|| {
+                let unknown = |stats: &VocabularyStats| stats.unique_words - stats.known_words;
+                unknown(&a.stats).cmp(&unknown(&b.stats))
+            })
+    });
+
+    Ok(ranking)
+}
+
+// every chapter with the series it's in, through any volumes in between
+const SERIES_CHAPTERS: &str = "WITH RECURSIVE series_folder(series_id, folder_id) AS (
+        SELECT id, id FROM parent_folder WHERE kind = 'series'
+        UNION
+        SELECT series_folder.series_id, parent_folder.id FROM parent_folder
+        INNER JOIN series_folder ON parent_folder.parent_id = series_folder.folder_id
+    ),
+    series_chapter(series_id, chapter_id) AS (
+        SELECT series_folder.series_id, manga_folder.id FROM manga_folder
+        INNER JOIN series_folder ON manga_folder.parent_id = series_folder.folder_id
+    )";
+
+/// `vocabulary_stats` of every series with analyzed text, grouped in sql instead of
+/// queried series by series.
+async fn series_comprehension(pool: &SqlitePool) -> AppResult<Vec<SeriesComprehension>> {
+    let series: Vec<(String, String, String, u32, u32)> = sqlx::query_as(&format!(
+        "{SERIES_CHAPTERS}
+        SELECT
+            parent_folder.id,
+            parent_folder.title,
+            parent_folder.full_path,
+            COUNT(DISTINCT vocabulary_analysis.manga_folder_id),
+            COUNT(DISTINCT vocabulary_kanji.character)
+        FROM parent_folder
+        INNER JOIN series_chapter ON series_chapter.series_id = parent_folder.id
+        INNER JOIN vocabulary_analysis
+            ON vocabulary_analysis.manga_folder_id = series_chapter.chapter_id
+        LEFT JOIN vocabulary_kanji
+            ON vocabulary_kanji.manga_folder_id = series_chapter.chapter_id
+        GROUP BY parent_folder.id
+        ORDER BY parent_folder.title"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let rows: Vec<SeriesWordRow> = sqlx::query_as(&format!(
+        "{SERIES_CHAPTERS}
+        SELECT
+            series_chapter.series_id,
+            SUM(vocabulary_word.occurrences) AS occurrences,
+            MIN(vocabulary_word.frequency_rank) AS frequency_rank,
+            MAX(by_word.word IS NOT NULL OR by_reading.word IS NOT NULL) AS is_known
+        FROM vocabulary_word
+        INNER JOIN series_chapter ON series_chapter.chapter_id = vocabulary_word.manga_folder_id
+        LEFT JOIN known_word AS by_word ON by_word.word = vocabulary_word.word
+        LEFT JOIN known_word AS by_reading ON by_reading.word = vocabulary_word.reading
+        WHERE vocabulary_word.in_dictionary
+        GROUP BY series_chapter.series_id, vocabulary_word.word"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let mut words: HashMap<String, Vec<WordRow>> = HashMap::new();
+    for row in rows {
+        words.entry(row.series_id).or_default().push(row.word);
+    }
+
+    Ok(series
+        .into_iter()
+        .map(|(id, title, full_path, chapters, unique_kanji)| {
+            let words = words.remove(&id).unwrap_or_default();
+            SeriesComprehension {
+                stats: summarize(chapters, unique_kanji, &words),
+                id,
+                title,
+                full_path,
+            }
+        })
+        .filter(|series| series.stats.token_count > 0)
+        .collect())
+}
+
+/// Adds the words of a word list, one per line, or of an anki deck exported as
+/// "Notes in Plain Text". `replace` forgets the words imported before. Returns how many
+/// words are known afterwards.
+#[tauri::command]
+pub async fn import_known_words(path: String, replace: bool, handle: AppHandle) -> AppResult<u32> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let bytes = tokio::fs::read(&path).await.with_path(&path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let words = parse_known_words(text.trim_start_matches('\u{feff}'));
+
+    let mut tx = pool.begin().await?;
+
+    if replace {
+        sqlx::query("DELETE FROM known_word")
+            .execute(&mut *tx)
+            .await?;
+    }
+    for word in &words {
+        sqlx::query(
+            "INSERT OR IGNORE INTO known_word (word, imported_at)
+            VALUES (?, datetime('now', 'localtime'))",
+        )
+        .bind(word)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM known_word")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn get_known_word_count(handle: AppHandle) -> AppResult<u32> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM known_word")
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn clear_known_words(handle: AppHandle) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    sqlx::query("DELETE FROM known_word").execute(&pool).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn word(occurrences: u32, frequency_rank: Option<i64>, is_known: bool) -> WordRow {
+        WordRow {
+            occurrences,
+            frequency_rank,
+            is_known,
+        }
+    }
+
+    #[test]
+    fn estimates_jlpt_levels() {
+        for (rank, level) in [
+            (Some(1), JlptLevel::N5),
+            (Some(800), JlptLevel::N5),
+            (Some(801), JlptLevel::N4),
+            (Some(1500), JlptLevel::N4),
+            (Some(3750), JlptLevel::N3),
+            (Some(6000), JlptLevel::N2),
+            (Some(6001), JlptLevel::N1),
+            (Some(10000), JlptLevel::N1),
+            (Some(10001), JlptLevel::BeyondN1),
+            (None, JlptLevel::Unranked),
+        ] {
+            assert_eq!(JlptLevel::estimate(rank), level, "{rank:?}");
+        }
+    }
+
+    #[test]
+    fn summarizes_words() {
+        let stats = summarize(
+            2,
+            5,
+            &[
+                word(6, Some(10), true),
+                word(2, Some(1500), false),
+                word(1, Some(60000), false),
+                word(1, None, true),
+            ],
+        );
+
+        assert_eq!(stats.chapters, 2);
+        assert_eq!(stats.unique_kanji, 5);
+        assert_eq!(stats.unique_words, 4);
+        assert_eq!(stats.token_count, 10);
+        assert_eq!(stats.known_words, 2);
+        assert_eq!(stats.known_tokens, 7);
+        assert_eq!(stats.comprehension, 0.7);
+        assert_eq!(stats.unranked_words, 1);
+
+        let levels: Vec<(JlptLevel, u32)> = stats
+            .jlpt
+            .iter()
+            .map(|count| (count.level, count.words))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                (JlptLevel::N5, 1),
+                (JlptLevel::N4, 1),
+                (JlptLevel::N3, 0),
+                (JlptLevel::N2, 0),
+                (JlptLevel::N1, 0),
+                (JlptLevel::BeyondN1, 1),
+                (JlptLevel::Unranked, 1),
+            ]
+        );
+
+        let bands: Vec<(i64, Option<i64>, u32)> = stats
+            .frequency_bands
+            .iter()
+            .map(|band| (band.min_rank, band.max_rank, band.words))
+            .collect();
+        assert_eq!(
+            bands,
+            [
+                (1, Some(1000), 1),
+                (1001, Some(2000), 1),
+                (2001, Some(5000), 0),
+                (5001, Some(10000), 0),
+                (10001, Some(20000), 0),
+                (20001, Some(50000), 0),
+                (50001, None, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn summarizes_nothing() {
+        let stats = summarize(0, 0, &[]);
+
+        assert_eq!(stats.token_count, 0);
+        assert_eq!(stats.comprehension, 0.0);
+        assert!(stats.jlpt.iter().all(|count| count.words == 0));
+        assert_eq!(stats.frequency_bands.len(), FREQUENCY_BANDS.len() + 1);
+    }
+
+    #[test]
+    fn parses_known_words() {
+        for (text, words) in [
+            // a plain word list
+            ("食べる\n 見る \n\n", vec!["食べる", "見る"]),
+            // anki's export with the note's guid, notetype and deck before the fields
+            (
+                "#separator:tab\n#html:true\n#guid column:1\n#notetype column:2\n#deck column:3\n\
+                abc\tJapanese\tMining\t<b>日本</b>\tJapan\n\
+                def\tJapanese\tMining\t食べる\teat",
+                vec!["日本", "食べる"],
+            ),
+            // only some of the columns exported
+            (
+                "#separator:tab\n#deck column:1\nMining\t見る\tsee",
+                vec!["見る"],
+            ),
+            // a named separator in any case, and quoted fields
+            (
+                "#separator:Comma\n\"食べる\",eat\n\"a\"\"b\",quote",
+                vec!["食べる", "a\"b"],
+            ),
+            ("#separator:semicolon\n見る;see", vec!["見る"]),
+            ("#separator:|\n見る|see", vec!["見る"]),
+            // furigana and markup left in fields
+            (
+                "日本[にほん] 語[ご]\n<div>本&nbsp;</div>\n&lt;tag&gt;",
+                vec!["日本語", "本", "<tag>"],
+            ),
+            // lines without a word are skipped
+            ("#separator:tab\n\t\n<br>", vec![]),
+        ] {
+            assert_eq!(parse_known_words(text), words, "{text}");
+        }
+    }
+
+    #[tokio::test]
+    async fn tokenizes_by_longest_word() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO dictionary (id, title, format, priority) VALUES ('jmdict', 'JMdict', 3, 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        for (expression, reading, rules) in [
+            ("日本", "にほん", ""),
+            ("本", "ほん", ""),
+            ("歯", "は", ""),
+            ("は", "は", ""),
+            ("食べる", "たべる", "v1"),
+        ] {
+            sqlx::query(
+                "INSERT INTO dictionary_term (dictionary_id, expression, reading, rules, glossary)
+                VALUES ('jmdict', ?, ?, ?, '[]')",
+            )
+            .bind(expression)
+            .bind(reading)
+            .bind(rules)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let word = |expression: &str, reading: &str| Token::Word {
+            expression: expression.to_string(),
+            reading: reading.to_string(),
+        };
+        assert_eq!(
+            tokenize("日本は本を食べなかった。abcを", &pool)
+                .await
+                .unwrap(),
+            [
+                word("日本", "にほん"),
+                word("は", "は"),
+                word("本", "ほん"),
+                Token::Unknown("を".to_string()),
+                word("食べる", "たべる"),
+                Token::Unknown("を".to_string()),
+            ]
+        );
+    }
+
+    async fn migrated_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+        pool
+    }
+
+    async fn execute(sql: &str, pool: &SqlitePool) {
+        sqlx::query(sql).execute(pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn ranks_words_with_rank_based_dictionaries_only() {
+        let pool = migrated_pool().await;
+
+        execute(
+            "INSERT INTO dictionary (id, title, format, priority, frequency_mode, is_importing) VALUES
+            ('jmdict', 'JMdict', 3, 0, 'rank-based', 0),
+            ('ranks', 'Ranks', 3, 1, 'rank-based', 0),
+            ('counts', 'Counts', 3, 2, 'occurrence-based', 0),
+            ('partial', 'Partial', 3, 3, 'rank-based', 1)",
+            &pool,
+        )
+        .await;
+        execute(
+            "INSERT INTO dictionary_term (dictionary_id, expression, reading, glossary)
+            VALUES ('jmdict', '本', 'ほん', '[]')",
+            &pool,
+        )
+        .await;
+        // the occurrence count is lower than the rank, and so is the half imported rank
+        execute(
+            "INSERT INTO dictionary_frequency (dictionary_id, kind, expression, value) VALUES
+            ('ranks', 'term', '本', 300),
+            ('counts', 'term', '本', 90),
+            ('partial', 'term', '本', 1)",
+            &pool,
+        )
+        .await;
+        execute(
+            "INSERT INTO manga_folder (id, title, full_path) VALUES ('chapter', 'ch1', '/manga/ch1');
+            INSERT INTO manga_panel (id, folder_id, title, full_path)
+            VALUES ('panel', 'chapter', '1.png', '/manga/ch1/1.png');
+            INSERT INTO text_source (id, manga_folder_id, sidecar_path, modified_at, imported_at)
+            VALUES ('source', 'chapter', '/manga/ch1.mokuro', 0, '2024-01-01');
+            INSERT INTO text_block
+            (id, source_id, panel_id, position, x1, y1, x2, y2, lines, image_width, image_height)
+            VALUES ('block', 'source', 'panel', 0, 0, 0, 1, 1, '[\"本\"]', 1, 1)",
+            &pool,
+        )
+        .await;
+
+        assert!(analyze_chapter("chapter", &pool).await.unwrap());
+        let rank: Option<i64> =
+            sqlx::query_scalar("SELECT frequency_rank FROM vocabulary_word WHERE word = '本'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rank, Some(300));
+    }
+
+    fn stats_summary(stats: &VocabularyStats) -> (u32, u32, u32, u32, u32, u32, u32) {
+        (
+            stats.chapters,
+            stats.token_count,
+            stats.unique_words,
+            stats.unique_kanji,
+            stats.known_words,
+            stats.known_tokens,
+            stats.unranked_words,
+        )
+    }
+
+    #[tokio::test]
+    async fn ranks_series_from_grouped_analyses() {
+        let pool = migrated_pool().await;
+
+        // a series of chapters, a series of volumes and a series without text
+        execute(
+            "INSERT INTO parent_folder (id, parent_id, kind, title, full_path) VALUES
+            ('a', NULL, 'series', 'A', '/manga/A'),
+            ('b', NULL, 'series', 'B', '/manga/B'),
+            ('b1', 'b', 'volume', 'B 1', '/manga/B/1'),
+            ('c', NULL, 'series', 'C', '/manga/C');
+            INSERT INTO manga_folder (id, parent_id, title, full_path) VALUES
+            ('a1', 'a', 'a1', '/manga/A/1'),
+            ('a2', 'a', 'a2', '/manga/A/2'),
+            ('b11', 'b1', 'b11', '/manga/B/1/1'),
+            ('c1', 'c', 'c1', '/manga/C/1');
+            INSERT INTO vocabulary_analysis (manga_folder_id, dictionaries, token_count) VALUES
+            ('a1', '', 5), ('a2', '', 6), ('b11', '', 2);
+            INSERT INTO vocabulary_word
+            (manga_folder_id, word, reading, in_dictionary, occurrences, frequency_rank) VALUES
+            ('a1', '本', 'ほん', 1, 3, 300),
+            ('a1', '日本', 'にほん', 1, 1, NULL),
+            ('a1', 'ゞ', NULL, 0, 7, NULL),
+            ('a2', '本', 'ほん', 1, 2, 300),
+            ('a2', '食べる', 'たべる', 1, 4, 2000),
+            ('b11', '日本', 'にほん', 1, 2, NULL);
+            INSERT INTO vocabulary_kanji (manga_folder_id, character, occurrences) VALUES
+            ('a1', '本', 3), ('a1', '日', 1), ('a2', '本', 2), ('a2', '食', 4),
+            ('b11', '日', 2), ('b11', '本', 2);
+            INSERT INTO known_word (word) VALUES ('本'), ('たべる')",
+            &pool,
+        )
+        .await;
+
+        let ranking = series_comprehension(&pool).await.unwrap();
+        let titles: Vec<&str> = ranking.iter().map(|series| series.title.as_str()).collect();
+        assert_eq!(titles, ["A", "B"], "a series without text is left out");
+
+        // the same numbers as asking for each series on its own
+        for series in &ranking {
+            let chapter_ids = series_chapter_ids(&series.id, &pool).await.unwrap();
+            let alone = vocabulary_stats(&chapter_ids, &pool).await.unwrap();
+            assert_eq!(
+                stats_summary(&series.stats),
+                stats_summary(&alone),
+                "{}",
+                series.title
+            );
+            assert_eq!(
+                series.stats.comprehension, alone.comprehension,
+                "{}",
+                series.title
+            );
+        }
+        assert_eq!(stats_summary(&ranking[0].stats), (2, 10, 3, 3, 2, 9, 1));
+        assert_eq!(stats_summary(&ranking[1].stats), (1, 2, 1, 2, 0, 0, 1));
+    }
+}
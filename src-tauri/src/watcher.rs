@@ -0,0 +1,595 @@
+use std::{
+    path::{Path, MAIN_SEPARATOR},
+    time::Duration,
+};
+
+use notify_debouncer_full::{
+    new_debouncer,
+    notify::{
+        event::{ModifyKind, RenameMode},
+        Event, EventKind, RecommendedWatcher, RecursiveMode,
+    },
+    DebounceEventResult, Debouncer, RecommendedCache,
+};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, Mutex};
+
+use crate::error::AppResult;
+use crate::library::{is_chapter_path, register_new_path};
+use crate::manga::{split_path_parts, upsert_manga_folder};
+use crate::scanner::{find_panels, insert_panels, probe_panels};
+use crate::source::{is_archive, is_panel};
+
+pub const LIBRARY_CHANGED_EVENT: &str = "library://changed";
+
+// events for the same path within this window are merged into one
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+// matches a path and everything below it, binds: path, path + separator, path + separator
+const UNDER_PATH: &str = "(full_path = ? OR substr(full_path, 1, length(?)) = ?)";
+
+/// Managed state holding the filesystem watcher, dropping it stops watching.
+#[derive(Default)]
+pub struct LibraryWatcher {
+    debouncer: std::sync::Mutex<Option<Debouncer<RecommendedWatcher, RecommendedCache>>>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryChangeKind {
+    Added,
+    Removed,
+    Renamed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LibraryChange {
+    pub kind: LibraryChangeKind,
+    pub path: String,
+    pub new_path: Option<String>,
+}
+
+/// Watches every library root and applies changes on disk to the library.
+pub fn start_library_watcher(handle: AppHandle) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<DebounceEventResult>();
+
+    // the debouncer calls back on its own thread, hand the events over to the async runtime
+    let debouncer = new_debouncer(WATCH_DEBOUNCE, None, move |result| {
+        let _ = sender.send(result);
+    });
+    match debouncer {
+        Ok(debouncer) => {
+            let state = handle.state::<LibraryWatcher>();
+            let mut guard = state.debouncer.lock().unwrap_or_else(|e| e.into_inner());
+            *guard = Some(debouncer);
+        }
+        Err(e) => {
+            eprintln!("Error Starting Library Watcher -> {}", e);
+            return;
+        }
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+        let roots: Result<Vec<String>, sqlx::Error> =
+            sqlx::query_scalar("SELECT full_path FROM library_root")
+                .fetch_all(&pool)
+                .await;
+        match roots {
+            Ok(roots) => {
+                for root in roots {
+                    watch_parent_folder(&handle, &root);
+                }
+            }
+            Err(e) => eprintln!("Error Loading Watched Folders -> {}", e),
+        }
+
+        while let Some(result) = receiver.recv().await {
+            let events = match result {
+                Ok(events) => events,
+                Err(errors) => {
+                    for e in errors {
+                        eprintln!("Error Watching Library -> {}", e);
+                    }
+                    continue;
+                }
+            };
+
+            for event in events {
+                match apply_event(&event, &handle, &pool).await {
+                    Ok(changes) => {
+                        for change in changes {
+                            if let Err(e) = handle.emit(LIBRARY_CHANGED_EVENT, change) {
+                                eprintln!("Error Emitting {} -> {}", LIBRARY_CHANGED_EVENT, e);
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Error Applying {:?} -> {}", event.paths, e),
+                }
+            }
+        }
+    });
+}
+
+pub fn watch_parent_folder(handle: &AppHandle, path: &str) {
+    with_debouncer(handle, |debouncer| {
+        if let Err(e) = debouncer.watch(path, RecursiveMode::Recursive) {
+            eprintln!("Error Watching: {} -> {}", path, e);
+        }
+    });
+}
+
+pub fn unwatch_parent_folder(handle: &AppHandle, path: &str) {
+    // not every deleted folder is a watched root, so a failed unwatch is expected
+    with_debouncer(handle, |debouncer| {
+        let _ = debouncer.unwatch(path);
+    });
+}
+
+fn with_debouncer(
+    handle: &AppHandle,
+    f: impl FnOnce(&mut Debouncer<RecommendedWatcher, RecommendedCache>),
+) {
+    let state = handle.state::<LibraryWatcher>();
+    let mut guard = state.debouncer.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(debouncer) = guard.as_mut() {
+        f(debouncer);
+    }
+}
+
+async fn apply_event(
+    event: &Event,
+    handle: &AppHandle,
+    pool: &SqlitePool,
+) -> AppResult<Vec<LibraryChange>> {
+    let mut changes = Vec::new();
+
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let from = event.paths[0].to_string_lossy().into_owned();
+            let to = event.paths[1].to_string_lossy().into_owned();
+            let moved_roots = roots_under(&from, pool).await?;
+            if rename_path(&from, &to, pool).await? {
+                // watches are registered by path, follow the roots to where they are now
+                for root in moved_roots {
+                    unwatch_parent_folder(handle, &root);
+                    watch_parent_folder(handle, &format!("{to}{}", &root[from.len()..]));
+                }
+                changes.push(LibraryChange {
+                    kind: LibraryChangeKind::Renamed,
+                    path: from,
+                    new_path: Some(to),
+                });
+            }
+        }
+        // a rename that couldn't be paired arrives as a lone half, so go by what is on disk now
+        EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => {
+            for path in &event.paths {
+                let path_str = path.to_string_lossy().into_owned();
+                let (changed, kind) = if path.exists() {
+                    (add_path(path, pool).await?, LibraryChangeKind::Added)
+                } else {
+                    (
+                        mark_missing(&path_str, pool).await?,
+                        LibraryChangeKind::Removed,
+                    )
+                };
+
+                if changed {
+                    changes.push(LibraryChange {
+                        kind,
+                        path: path_str,
+                        new_path: None,
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(changes)
+}
+
+/// Registers new chapters and panels found at `path`, returns `false` if nothing was added.
+async fn add_path(path: &Path, pool: &SqlitePool) -> AppResult<bool> {
+    if path.is_file() && is_panel(path) {
+        // a panel copied into a chapter, the chapter itself may be new as well
+        if let Some(parent) = path.parent() {
+            ensure_manga_folder(&parent.to_string_lossy(), pool).await?;
+        }
+        let sized = probe_panels(vec![path.to_string_lossy().into_owned()]).await?;
+        insert_panels(&sized, pool).await?;
+        return Ok(true);
+    }
+
+    if !path.is_dir() && !is_archive(path) {
+        return Ok(false);
+    }
+
+    let added = register_new_path(path, pool).await?;
+
+    let root = path.to_path_buf();
+    let panels = tokio::task::spawn_blocking(move || {
+        let mut panels = Vec::new();
+        let mut errors = Vec::new();
+        find_panels(&root, &mut panels, &mut errors);
+        for error in errors {
+            eprintln!("Error Reading: {} -> {}", error.path, error.message);
+        }
+        panels
+    })
+    .await?;
+
+    let sized = probe_panels(panels).await?;
+    insert_panels(&sized, pool).await?;
+
+    Ok(added)
+}
+
+async fn ensure_manga_folder(path: &str, pool: &SqlitePool) -> AppResult<()> {
+    // a cover dropped next to chapters doesn't make the series a chapter, same as a scan
+    if !is_chapter_path(Path::new(path)).await? {
+        return Ok(());
+    }
+
+    // chapters we already know keep their flags and progress, they just stop being missing
+    let result = sqlx::query("UPDATE manga_folder SET is_missing = 0 WHERE full_path = ?")
+        .bind(path)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        upsert_manga_folder(path, pool).await?;
+    }
+
+    Ok(())
+}
+
+/// Flags the folders and panels at or below `path` as missing, returns `false` if none matched.
+pub async fn mark_missing(path: &str, pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let prefix = format!("{path}{MAIN_SEPARATOR}");
+    let mut tx = pool.begin().await?;
+    let mut rows_affected = 0;
+
+    for table in ["manga_folder", "manga_panel"] {
+        rows_affected += sqlx::query(&format!(
+            "UPDATE {table} SET is_missing = 1 WHERE is_missing = 0 AND {UNDER_PATH}"
+        ))
+        .bind(path)
+        .bind(&prefix)
+        .bind(&prefix)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(rows_affected > 0)
+}
+
+/// The library roots at or below `path`.
+async fn roots_under(path: &str, pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let prefix = format!("{path}{MAIN_SEPARATOR}");
+    sqlx::query_scalar(&format!(
+        "SELECT full_path FROM library_root WHERE {UNDER_PATH}"
+    ))
+    .bind(path)
+    .bind(&prefix)
+    .bind(&prefix)
+    .fetch_all(pool)
+    .await
+}
+
+/// Moves every row at or below `from` to `to`, so reading progress follows a renamed folder.
+async fn rename_path(from: &str, to: &str, pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let from_prefix = format!("{from}{MAIN_SEPARATOR}");
+    let to_prefix = format!("{to}{MAIN_SEPARATOR}");
+    let mut tx = pool.begin().await?;
+    let mut rows_affected = 0;
+
+    // OR REPLACE drops rows a scan already created for the new path, the moved rows hold the progress
+    for table in [
+        "library_root",
+        "parent_folder",
+        "manga_folder",
+        "manga_panel",
+        "global_manga",
+        "global_parent",
+    ] {
+        rows_affected += sqlx::query(&format!(
+            "UPDATE OR REPLACE {table} SET full_path = ? || substr(full_path, length(?) + 1)
+            WHERE {UNDER_PATH}"
+        ))
+        .bind(to)
+        .bind(from)
+        .bind(from)
+        .bind(&from_prefix)
+        .bind(&from_prefix)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    for table in [
+        "parent_folder",
+        "manga_folder",
+        "global_manga",
+        "global_parent",
+    ] {
+        for column in ["cover_panel_path", "custom_cover_path"] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET {column} = ? || substr({column}, length(?) + 1)
+                WHERE substr({column}, 1, length(?)) = ?"
+            ))
+            .bind(to)
+            .bind(from)
+            .bind(&from_prefix)
+            .bind(&from_prefix)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // folders titled after their old name follow the new one
+        sqlx::query(&format!(
+            "UPDATE {table} SET title = ? WHERE full_path = ? AND title = ?"
+        ))
+        .bind(split_path_parts(to).file_name)
+        .bind(to)
+        .bind(split_path_parts(from).file_name)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // bookmarks keep the path to relink by after their panel is removed
+    sqlx::query(
+        "UPDATE bookmark SET panel_path = ? || substr(panel_path, length(?) + 1)
+        WHERE panel_path = ? OR substr(panel_path, 1, length(?)) = ?",
+    )
+    .bind(to)
+    .bind(from)
+    .bind(from)
+    .bind(&from_prefix)
+    .bind(&from_prefix)
+    .execute(&mut *tx)
+    .await?;
+
+    for table in ["manga_folder", "manga_panel"] {
+        sqlx::query(&format!(
+            "UPDATE {table} SET is_missing = 0 WHERE {UNDER_PATH}"
+        ))
+        .bind(to)
+        .bind(&to_prefix)
+        .bind(&to_prefix)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // a folder moved into another parent folder is re-linked to it
+    for table in ["parent_folder", "manga_folder"] {
+        sqlx::query(&format!(
+            "UPDATE {table} SET parent_id = COALESCE(
+                (SELECT id FROM parent_folder WHERE full_path = ?),
+                parent_id
+            )
+            WHERE full_path = ?"
+        ))
+        .bind(split_path_parts(to).parent)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(rows_affected > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn path(parts: &[&str]) -> String {
+        parts.join(std::path::MAIN_SEPARATOR_STR)
+    }
+
+    // a migrated database with a root holding one series, one chapter and one panel
+    async fn library() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrate_database(&pool).await.unwrap();
+
+        let root = path(&["", "library"]);
+        let series = path(&["", "library", "Series"]);
+        let chapter = path(&["", "library", "Series", "Chapter 1"]);
+        let panel = path(&["", "library", "Series", "Chapter 1", "001.png"]);
+        sqlx::query("INSERT INTO library_root (id, full_path) VALUES ('root', ?)")
+            .bind(&root)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO parent_folder (id, title, full_path, root_id, cover_panel_path)
+            VALUES ('series', 'Series', ?, 'root', ?)",
+        )
+        .bind(&series)
+        .bind(&panel)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO manga_folder (id, title, full_path, root_id, parent_id, cover_panel_path)
+            VALUES ('chapter', 'Chapter 1', ?, 'root', 'series', ?)",
+        )
+        .bind(&chapter)
+        .bind(&panel)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO manga_panel (id, folder_id, title, full_path, is_read)
+            VALUES ('panel', 'chapter', '001.png', ?, 1)",
+        )
+        .bind(&panel)
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO bookmark (id, panel_id, panel_path) VALUES ('bookmark', 'panel', ?)",
+        )
+        .bind(&panel)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    async fn full_path(table: &str, id: &str, pool: &SqlitePool) -> String {
+        sqlx::query_scalar(&format!("SELECT full_path FROM {table} WHERE id = ?"))
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn renamed_series_keeps_its_rows() {
+        let pool = library().await;
+
+        let renamed = rename_path(
+            &path(&["", "library", "Series"]),
+            &path(&["", "library", "Renamed"]),
+            &pool,
+        )
+        .await
+        .unwrap();
+        assert!(renamed);
+
+        assert_eq!(
+            full_path("parent_folder", "series", &pool).await,
+            path(&["", "library", "Renamed"])
+        );
+        assert_eq!(
+            full_path("manga_folder", "chapter", &pool).await,
+            path(&["", "library", "Renamed", "Chapter 1"])
+        );
+        assert_eq!(
+            full_path("manga_panel", "panel", &pool).await,
+            path(&["", "library", "Renamed", "Chapter 1", "001.png"])
+        );
+
+        let (title, cover): (String, String) =
+            sqlx::query_as("SELECT title, cover_panel_path FROM parent_folder WHERE id = 'series'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(title, "Renamed");
+        assert_eq!(
+            cover,
+            path(&["", "library", "Renamed", "Chapter 1", "001.png"])
+        );
+
+        let is_read: bool =
+            sqlx::query_scalar("SELECT is_read FROM manga_panel WHERE id = 'panel'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(is_read);
+
+        let bookmarked: String =
+            sqlx::query_scalar("SELECT panel_path FROM bookmark WHERE id = 'bookmark'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            bookmarked,
+            path(&["", "library", "Renamed", "Chapter 1", "001.png"])
+        );
+    }
+
+    #[tokio::test]
+    async fn renamed_root_moves_the_root() {
+        let pool = library().await;
+
+        let renamed = rename_path(&path(&["", "library"]), &path(&["", "manga"]), &pool)
+            .await
+            .unwrap();
+        assert!(renamed);
+
+        assert_eq!(
+            full_path("library_root", "root", &pool).await,
+            path(&["", "manga"])
+        );
+        assert_eq!(
+            full_path("manga_panel", "panel", &pool).await,
+            path(&["", "manga", "Series", "Chapter 1", "001.png"])
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_path_renames_nothing() {
+        let pool = library().await;
+
+        let renamed = rename_path(&path(&["", "other"]), &path(&["", "moved"]), &pool)
+            .await
+            .unwrap();
+        assert!(!renamed);
+    }
+
+    #[tokio::test]
+    async fn finds_the_roots_below_a_renamed_path() {
+        let pool = library().await;
+        sqlx::query(
+            "INSERT INTO library_root (id, full_path) VALUES ('nested', ?), ('sibling', ?)",
+        )
+        .bind(path(&["", "library", "Seinen"]))
+        .bind(path(&["", "library two"]))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut roots = roots_under(&path(&["", "library"]), &pool).await.unwrap();
+        roots.sort();
+        assert_eq!(
+            roots,
+            [path(&["", "library"]), path(&["", "library", "Seinen"])]
+        );
+    }
+
+    #[tokio::test]
+    async fn loose_images_only_register_chapters() {
+        let pool = library().await;
+        let dir = std::env::temp_dir().join(format!("watcher-{}", uuid::Uuid::new_v4()));
+        let series = dir.join("Series");
+        for file in ["cover.jpg", "Chapter 1/001.png", "Chapter 2/001.png"] {
+            let file = series.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, b"").unwrap();
+        }
+
+        let cases = [
+            ("a cover next to chapters", series.clone(), false),
+            ("a chapter of images", series.join("Chapter 2"), true),
+        ];
+        for (name, folder, registered) in cases {
+            let folder = folder.to_string_lossy().into_owned();
+            ensure_manga_folder(&folder, &pool).await.unwrap();
+
+            let exists: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM manga_folder WHERE full_path = ?)",
+            )
+            .bind(&folder)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(exists, registered, "{name}");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
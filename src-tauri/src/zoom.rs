@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::error::{AppError, AppResult};
+use crate::global::get_manga_folder_by_path;
+use crate::manga::PARENT_FOLDER_CHAIN;
+
+// the panel height in pixels the reader used before zoom was stored per series
+pub const DEFAULT_ZOOM_LEVEL: u16 = 490;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum FitMode {
+    FitWidth,
+    #[default]
+    FitHeight,
+    Original,
+    Custom,
+}
+
+/// Where the effective zoom of a chapter came from.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoomSource {
+    Chapter,
+    Series,
+    Default,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EffectiveZoom {
+    pub fit_mode: FitMode,
+    pub zoom_level: u16,
+    pub source: ZoomSource,
+}
+
+#[derive(sqlx::FromRow)]
+struct ZoomRow {
+    fit_mode: FitMode,
+    zoom_level: Option<u16>,
+}
+
+#[tauri::command]
+pub async fn get_zoom(folder_path: String, handle: AppHandle) -> AppResult<EffectiveZoom> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    resolve_zoom(&folder_path, &pool).await
+}
+
+/// Zooming from the reader, the change goes to the chapter if it overrides the zoom
+/// and to its series otherwise, so the next chapters open the same way.
+#[tauri::command]
+pub async fn update_zoom(
+    folder_path: String,
+    fit_mode: FitMode,
+    zoom_level: u16,
+    handle: AppHandle,
+) -> AppResult<EffectiveZoom> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let folder = get_manga_folder_by_path(&folder_path, &pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(&folder_path))?;
+
+    match folder.parent_id {
+        Some(parent_id) if folder.fit_mode.is_none() => {
+            sqlx::query("UPDATE parent_folder SET fit_mode = ?, zoom_level = ? WHERE id = ?")
+                .bind(fit_mode)
+                .bind(zoom_level)
+                .bind(parent_id)
+                .execute(&pool)
+                .await?;
+        }
+        _ => {
+            sqlx::query("UPDATE manga_folder SET fit_mode = ?, zoom_level = ? WHERE id = ?")
+                .bind(fit_mode)
+                .bind(zoom_level)
+                .bind(folder.id)
+                .execute(&pool)
+                .await?;
+        }
+    }
+
+    resolve_zoom(&folder_path, &pool).await
+}
+
+/// Overrides the zoom of a single chapter, a `None` fit mode makes it inherit again.
+#[tauri::command]
+pub async fn set_chapter_zoom(
+    folder_path: String,
+    fit_mode: Option<FitMode>,
+    zoom_level: Option<u16>,
+    handle: AppHandle,
+) -> AppResult<EffectiveZoom> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result =
+        sqlx::query("UPDATE manga_folder SET fit_mode = ?, zoom_level = ? WHERE full_path = ?")
+            .bind(fit_mode)
+            .bind(fit_mode.and(zoom_level))
+            .bind(&folder_path)
+            .execute(&pool)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&folder_path));
+    }
+
+    resolve_zoom(&folder_path, &pool).await
+}
+
+/// Sets the zoom every chapter below a parent folder inherits, a `None` fit mode clears it.
+#[tauri::command]
+pub async fn set_series_zoom(
+    series_path: String,
+    fit_mode: Option<FitMode>,
+    zoom_level: Option<u16>,
+    handle: AppHandle,
+) -> AppResult<()> {
+    let pool = handle.state::<Mutex<SqlitePool>>().lock().await.clone();
+
+    let result =
+        sqlx::query("UPDATE parent_folder SET fit_mode = ?, zoom_level = ? WHERE full_path = ?")
+            .bind(fit_mode)
+            .bind(fit_mode.and(zoom_level))
+            .bind(&series_path)
+            .execute(&pool)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found(&series_path));
+    }
+
+    Ok(())
+}
+
+/// Picks the chapter's own zoom, then the closest parent folder's, then the default.
+pub async fn resolve_zoom(folder_path: &str, pool: &SqlitePool) -> AppResult<EffectiveZoom> {
+    let folder = get_manga_folder_by_path(folder_path, pool)
+        .await?
+        .ok_or_else(|| AppError::not_found(folder_path))?;
+
+    if let Some(fit_mode) = folder.fit_mode {
+        return Ok(EffectiveZoom {
+            fit_mode,
+            zoom_level: folder.zoom_level.unwrap_or(DEFAULT_ZOOM_LEVEL),
+            source: ZoomSource::Chapter,
+        });
+    }
+
+    let series: Option<ZoomRow> = match folder.parent_id {
+        Some(parent_id) => {
+            sqlx::query_as(&format!(
+                "{PARENT_FOLDER_CHAIN}
+                SELECT fit_mode, zoom_level FROM parents
+                WHERE fit_mode IS NOT NULL
+                ORDER BY depth
+                LIMIT 1"
+            ))
+            .bind(parent_id)
+            .fetch_optional(pool)
+            .await?
+        }
+        None => None,
+    };
+
+    Ok(match series {
+        Some(series) => EffectiveZoom {
+            fit_mode: series.fit_mode,
+            zoom_level: series.zoom_level.unwrap_or(DEFAULT_ZOOM_LEVEL),
+            source: ZoomSource::Series,
+        },
+        None => EffectiveZoom {
+            fit_mode: FitMode::default(),
+            zoom_level: DEFAULT_ZOOM_LEVEL,
+            source: ZoomSource::Default,
+        },
+    })
+}
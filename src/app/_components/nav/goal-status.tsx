@@ -0,0 +1,36 @@
+"use client";
+
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+import { GoalProgressType } from "@/app/stats/_components/reading-goals";
+
+// how long the notice stays in the nav bar
+const NOTICE_MS = 8000;
+
+export default function GoalStatus() {
+  const [reached, setReached] = useState<GoalProgressType | null>(null);
+
+  useEffect(() => {
+    let timeout: ReturnType<typeof setTimeout>;
+    const unlisten = listen<GoalProgressType>("goal://reached", ({ payload }) => {
+      setReached(payload);
+      clearTimeout(timeout);
+      timeout = setTimeout(() => setReached(null), NOTICE_MS);
+    });
+
+    return () => {
+      clearTimeout(timeout);
+      unlisten.then((dispose) => dispose());
+    };
+  }, []);
+
+  if (!reached) {
+    return null;
+  }
+
+  return (
+    <li className="text-xs text-accent bg-gray-700 rounded-sm px-1.5 select-none">
+      Daily goal reached · {reached.progress} {reached.goal.unit}
+    </li>
+  );
+}
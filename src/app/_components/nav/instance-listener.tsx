@@ -0,0 +1,42 @@
+"use client";
+
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { ask } from "@tauri-apps/plugin-dialog";
+import { useRouter } from "next/navigation";
+import { useEffect } from "react";
+
+export type InstanceOpenedType = {
+  args: string[];
+  path: string | null;
+  kind: "chapter" | "series" | "unknown" | null;
+};
+
+// launching the app again focuses this window and hands over the path it was launched with
+export default function InstanceListener() {
+  const router = useRouter();
+
+  useEffect(() => {
+    const unlisten = listen<InstanceOpenedType>("instance://opened", async ({ payload }) => {
+      const { path, kind } = payload;
+      if (!path) return;
+
+      if (kind === "chapter") {
+        await invoke("set_global_manga_folder", { fullPath: path });
+        router.push("/manga");
+      } else if (kind === "series") {
+        await invoke("set_global_parent_folder", { fullPath: path });
+        router.push("/manga-chapters");
+      } else if (await ask(`Add ${path} to the library?`, { title: "Manga Shelf" })) {
+        await invoke("add_library_root", { rootPath: path });
+        router.push("/dashboard");
+      }
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
+  }, [router]);
+
+  return null;
+}
@@ -3,41 +3,22 @@
 import Link from "next/link";
 import { ArrowLeftStartOnRectangleIcon, ArrowUturnLeftIcon } from "@heroicons/react/16/solid";
 import { usePathname, useRouter } from "next/navigation";
-import { useEffect, useState } from "react";
+import { useEffect } from "react";
 import { BarChart3 } from "lucide-react";
-import { invoke } from "@tauri-apps/api/core";
-import { MangaFolderType } from "@/app/dashboard/page";
+import ScanStatus from "./scan-status";
+import InstanceListener from "./instance-listener";
+import GoalStatus from "./goal-status";
 
 export default function NavBar() {
   const router = useRouter();
   const pathname = usePathname();
-  const [startTime, setStartTime] = useState<number>(0);
-  const [wasPreviousPathManga, setWasPreviousPathManga] = useState<boolean>(false);
 
+  // reading time is recorded by the reader itself, see the sessions in the manga page
   useEffect(() => {
     if (pathname == "/") {
       router.push("/dashboard");
     }
-
-    if (pathname == "/manga") {
-      setStartTime(Date.now());
-      setWasPreviousPathManga(true);
-    } else {
-      if (wasPreviousPathManga) {
-        const elapsedTime = Math.floor((Date.now() - startTime) / 1000);
-        invoke("get_global_manga").then((result: unknown) => {
-          invoke("update_folder_time_spent_reading", {
-            folderPath: (result as MangaFolderType).full_path,
-            timeSpentReading: elapsedTime,
-          });
-          invoke("update_chart_watchtime", { watchTime: elapsedTime });
-        });
-        setStartTime(0);
-      }
-
-      setWasPreviousPathManga(false);
-    }
-  }, [pathname, router, startTime, wasPreviousPathManga]);
+  }, [pathname, router]);
 
   const handleGoBack = () => {
     if (pathname === "/dashboard") {
@@ -54,7 +35,10 @@ export default function NavBar() {
             <ArrowUturnLeftIcon className="w-5 md:w-7 h-auto  transition-all" />
           </Link>
         </li>
-        <ul className="flex flex-row gap-1">
+        <ul className="flex flex-row gap-1 items-center">
+          <InstanceListener />
+          <GoalStatus />
+          <ScanStatus />
           <li className="transition-all pl-0.5 bg-gray-700 text-accent flex flex-row justify-center items-center cursor-pointer hover:opacity-80 rounded-sm">
             <Link href="/stats" className="focus-visible:outline-none focus-visible:ring-1 focus-visible:ring-white ring-opacity-50 rounded-sm">
               <BarChart3 className="w-5 md:w-6 h-auto  transition-all" strokeWidth={2.4} />
@@ -0,0 +1,49 @@
+"use client";
+
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+export type ScanProgressType = {
+  root: string;
+  roots_scanned: number;
+  total_roots: number;
+  panels_found: number;
+  panels_added: number;
+};
+
+export type ScanErrorType = {
+  path: string;
+  message: string;
+};
+
+export default function ScanStatus() {
+  const [progress, setProgress] = useState<ScanProgressType | null>(null);
+
+  useEffect(() => {
+    const unlistenProgress = listen<ScanProgressType>("scan://progress", ({ payload }) => {
+      setProgress(payload);
+    });
+    const unlistenFinished = listen("scan://finished", () => {
+      setProgress(null);
+    });
+    const unlistenError = listen<ScanErrorType>("scan://error", ({ payload }) => {
+      console.error(`Error scanning ${payload.path}: ${payload.message}`);
+    });
+
+    return () => {
+      unlistenProgress.then((dispose) => dispose());
+      unlistenFinished.then((dispose) => dispose());
+      unlistenError.then((dispose) => dispose());
+    };
+  }, []);
+
+  if (!progress) {
+    return null;
+  }
+
+  return (
+    <li className="text-xs text-accent bg-gray-700 rounded-sm px-1.5 select-none" title={progress.root}>
+      Scanning {progress.roots_scanned}/{progress.total_roots} · {progress.panels_added} panels
+    </li>
+  );
+}
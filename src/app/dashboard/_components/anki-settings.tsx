@@ -0,0 +1,175 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { ChevronDownIcon, ChevronUpIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+
+export type CardFieldType = "word" | "reading" | "sentence" | "definition" | "picture" | "source";
+
+export type AnkiSettingsType = {
+  endpoint: string;
+  deck_name: string;
+  model_name: string;
+  fields: Record<string, CardFieldType>;
+  tags: string[];
+  duplicate_scope: "deck" | "collection";
+  allow_duplicates: boolean;
+};
+
+type AnkiCatalogType = {
+  decks: string[];
+  models: string[];
+};
+
+const cardFields: CardFieldType[] = ["word", "reading", "sentence", "definition", "picture", "source"];
+
+const selectClassName = "h-7 rounded-md border border-input bg-transparent px-1";
+
+// where words mined from the reader's lookups go, through the AnkiConnect add-on
+export default function AnkiSettings() {
+  const [isOpen, setIsOpen] = useState(false);
+  const [settings, setSettings] = useState<AnkiSettingsType | null>(null);
+  const [catalog, setCatalog] = useState<AnkiCatalogType | null>(null);
+  const [modelFields, setModelFields] = useState<string[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!isOpen) return;
+    invoke("get_anki_settings").then((result: unknown) => {
+      setSettings(result as AnkiSettingsType);
+    });
+  }, [isOpen]);
+
+  const update = (changes: Partial<AnkiSettingsType>) => {
+    setSettings((prev) => (prev ? { ...prev, ...changes } : prev));
+  };
+
+  const handleError = (e: unknown) => {
+    setError((e as { message?: string }).message ?? String(e));
+  };
+
+  const fetchModelFields = (endpoint: string, modelName: string) => {
+    invoke<string[]>("get_anki_model_fields", { endpoint, modelName })
+      .then((result) => {
+        setModelFields(result);
+        setError(null);
+      })
+      .catch(handleError);
+  };
+
+  const invokeConnect = () => {
+    if (!settings) return;
+    invoke<AnkiCatalogType>("get_anki_catalog", { endpoint: settings.endpoint })
+      .then((result) => {
+        setCatalog(result);
+        fetchModelFields(settings.endpoint, settings.model_name);
+      })
+      .catch(handleError);
+  };
+
+  const handleModelChange = (modelName: string) => {
+    if (!settings) return;
+    // the old mapping names fields of the other note type
+    update({ model_name: modelName, fields: {} });
+    fetchModelFields(settings.endpoint, modelName);
+  };
+
+  const handleFieldChange = (field: string, cardField: CardFieldType | "") => {
+    if (!settings) return;
+    const fields = { ...settings.fields };
+    if (cardField) {
+      fields[field] = cardField;
+    } else {
+      delete fields[field];
+    }
+    update({ fields });
+  };
+
+  const invokeSave = () => {
+    invoke<AnkiSettingsType>("set_anki_settings", { settings })
+      .then((result) => {
+        setSettings(result);
+        setError(null);
+      })
+      .catch(handleError);
+  };
+
+  // fields of the saved mapping are listed until the note type was fetched from anki
+  const fieldNames = modelFields.length > 0 ? modelFields : Object.keys(settings?.fields ?? {});
+
+  return (
+    <div className="mt-2 flex flex-col gap-2 text-xs">
+      <Button className="h-7 w-fit flex flex-row items-center gap-0.5 text-xs shadow-sm" variant="secondary" onClick={() => setIsOpen(!isOpen)}>
+        Anki Settings
+        {isOpen ? <ChevronUpIcon className="h-3 w-auto" /> : <ChevronDownIcon className="h-3 w-auto" />}
+      </Button>
+      {isOpen && settings && (
+        <div className="w-fit flex flex-col gap-2 bg-card rounded-md shadow-md outline outline-border p-2">
+          <div className="flex flex-row items-center gap-1">
+            <Input className="h-7 w-56 text-xs" value={settings.endpoint} onChange={(e) => update({ endpoint: e.target.value })} />
+            <Button className="h-7 px-2 text-xs" variant="secondary" onClick={invokeConnect}>
+              Connect
+            </Button>
+          </div>
+          <div className="flex flex-row items-center gap-1">
+            <select className={selectClassName} value={settings.deck_name} onChange={(e) => update({ deck_name: e.target.value })}>
+              {(catalog?.decks ?? [settings.deck_name]).map((deck) => (
+                <option key={deck} value={deck}>
+                  {deck}
+                </option>
+              ))}
+            </select>
+            <select className={selectClassName} value={settings.model_name} onChange={(e) => handleModelChange(e.target.value)}>
+              {(catalog?.models ?? [settings.model_name]).map((model) => (
+                <option key={model} value={model}>
+                  {model}
+                </option>
+              ))}
+            </select>
+          </div>
+          <ul className="flex flex-col gap-1">
+            {fieldNames.map((field) => (
+              <li key={field} className="flex flex-row items-center justify-between gap-2">
+                <span className="font-semibold">{field}</span>
+                <select className={selectClassName} value={settings.fields[field] ?? ""} onChange={(e) => handleFieldChange(field, e.target.value as CardFieldType | "")}>
+                  <option value="">Empty</option>
+                  {cardFields.map((cardField) => (
+                    <option key={cardField} value={cardField} className="capitalize">
+                      {cardField}
+                    </option>
+                  ))}
+                </select>
+              </li>
+            ))}
+          </ul>
+          <Input
+            className="h-7 text-xs"
+            placeholder="Tags"
+            value={settings.tags.join(" ")}
+            onChange={(e) => update({ tags: e.target.value.split(" ").filter(Boolean) })}
+          />
+          <div className="flex flex-row items-center gap-2">
+            <select
+              className={selectClassName}
+              value={settings.duplicate_scope}
+              onChange={(e) => update({ duplicate_scope: e.target.value as AnkiSettingsType["duplicate_scope"] })}
+            >
+              <option value="deck">Duplicates in Deck</option>
+              <option value="collection">Duplicates in Collection</option>
+            </select>
+            <label className="flex flex-row items-center gap-1">
+              <input type="checkbox" checked={settings.allow_duplicates} onChange={(e) => update({ allow_duplicates: e.target.checked })} />
+              Allow Duplicates
+            </label>
+          </div>
+          {error && <p className="text-red-500">{error}</p>}
+          <Button className="h-7 w-fit px-2 text-xs" onClick={invokeSave}>
+            Save
+          </Button>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,103 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { BookOpenIcon, XMarkIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+
+export type DictionaryType = {
+  id: string;
+  title: string;
+  revision: string | null;
+  format: number;
+  priority: number;
+  frequency_mode: "rank-based" | "occurrence-based";
+  term_count: number;
+  kanji_count: number;
+  created_at: string;
+  updated_at: string;
+};
+
+export type DictionaryProgressType = {
+  title: string;
+  banks_imported: number;
+  total_banks: number;
+};
+
+// yomitan dictionary zips, imported once and looked up offline from the reader
+export default function Dictionaries() {
+  const [dictionaries, setDictionaries] = useState<DictionaryType[]>([]);
+  const [progress, setProgress] = useState<DictionaryProgressType | null>(null);
+
+  const fetchDictionaries = () => {
+    invoke("get_dictionaries").then((result: unknown) => {
+      setDictionaries(result as DictionaryType[]);
+    });
+  };
+
+  useEffect(() => {
+    fetchDictionaries();
+
+    const unlisten = listen<DictionaryProgressType>("dictionary://progress", ({ payload }) => {
+      setProgress(payload);
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
+  }, []);
+
+  const handleImportDictionary = () => {
+    open({
+      title: "Select Yomitan Dictionary",
+      multiple: true,
+      filters: [{ name: "Yomitan Dictionary", extensions: ["zip"] }],
+    }).then(async (result: string[] | null) => {
+      if (!result) return;
+      // one at a time, every import is a single large transaction
+      for (const path of result) {
+        await invoke("import_dictionary", { path }).catch((error) => {
+          console.error("Error Importing Dictionary:", path, error);
+        });
+      }
+      setProgress(null);
+      fetchDictionaries();
+    });
+  };
+
+  const invokeRemoveDictionary = (id: string) => {
+    invoke("remove_dictionary", { id }).then(() => {
+      setDictionaries((prev) => prev.filter((dictionary) => dictionary.id !== id));
+    });
+  };
+
+  return (
+    <ul className="flex flex-row flex-wrap items-center gap-2 mt-2">
+      <li>
+        <Button
+          className="h-7 flex flex-row justify-center items-center gap-0.5 text-xs shadow-sm"
+          variant="secondary"
+          disabled={progress !== null}
+          onClick={handleImportDictionary}
+        >
+          <span className="text-center">
+            {progress ? `Importing ${progress.title} ${progress.banks_imported}/${progress.total_banks}` : "Add Dictionary"}
+          </span>
+          <BookOpenIcon className="h-3 w-auto" />
+        </Button>
+      </li>
+      {dictionaries.map((dictionary) => (
+        <li key={dictionary.id} className="flex flex-row items-center gap-1 rounded-sm bg-secondary px-2 py-1 text-xs shadow-sm">
+          <span className="max-w-64 truncate" title={`${dictionary.term_count} terms, ${dictionary.kanji_count} kanji`}>
+            {dictionary.title}
+          </span>
+          <Button variant="ghost" className="h-5 w-5 p-0" title="Remove" onClick={() => invokeRemoveDictionary(dictionary.id)}>
+            <XMarkIcon className="h-3 w-auto" />
+          </Button>
+        </li>
+      ))}
+    </ul>
+  );
+}
@@ -0,0 +1,62 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { ArrowPathIcon, XMarkIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import { LibraryRootType } from "../page";
+
+// the folders added with "Add Manga", rescanning picks up changes made while the app was closed
+export default function LibraryRoots({ onChange, refreshKey }: { onChange: () => void; refreshKey: number }) {
+  const [roots, setRoots] = useState<LibraryRootType[]>([]);
+  const [scanningRoot, setScanningRoot] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke("get_library_roots").then((result: unknown) => {
+      if (result) {
+        setRoots(result as LibraryRootType[]);
+      }
+    });
+  }, [refreshKey]);
+
+  const invokeRescanRoot = (rootPath: string) => {
+    setScanningRoot(rootPath);
+    invoke("rescan_library_root", { rootPath })
+      .then(() => onChange())
+      .finally(() => setScanningRoot(null));
+  };
+
+  const invokeRemoveRoot = (rootPath: string) => {
+    // progress is kept so the chapters pick it back up if the root is added again
+    invoke("remove_library_root", { rootPath, allData: false }).then(() => {
+      setRoots((prev) => prev.filter((root) => root.full_path !== rootPath));
+      onChange();
+    });
+  };
+
+  if (roots.length === 0) return null;
+
+  return (
+    <ul className="flex flex-row flex-wrap items-center gap-2 mt-2">
+      {roots.map((root) => (
+        <li key={root.id} className="flex flex-row items-center gap-1 rounded-sm bg-secondary px-2 py-1 text-xs shadow-sm">
+          <span className="max-w-64 truncate" title={root.full_path}>
+            {root.full_path}
+          </span>
+          <Button
+            variant="ghost"
+            className="h-5 w-5 p-0"
+            title="Rescan"
+            disabled={scanningRoot !== null}
+            onClick={() => invokeRescanRoot(root.full_path)}
+          >
+            <ArrowPathIcon className={`h-3 w-auto ${scanningRoot === root.full_path ? "animate-spin" : ""}`} />
+          </Button>
+          <Button variant="ghost" className="h-5 w-5 p-0" title="Remove" onClick={() => invokeRemoveRoot(root.full_path)}>
+            <XMarkIcon className="h-3 w-auto" />
+          </Button>
+        </li>
+      ))}
+    </ul>
+  );
+}
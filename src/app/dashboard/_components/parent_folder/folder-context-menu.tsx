@@ -8,13 +8,16 @@ import {
   ContextMenuSubTrigger,
   ContextMenuTrigger,
 } from "@/components/ui/context-menu";
-import { MangaFolderType, ParentFolderType } from "../../page";
+import { FitModeType, MangaFolderType, ParentFolderType, ReadingModeType } from "../../page";
 import { BackspaceIcon, BookOpenIcon, EyeIcon, EyeSlashIcon, FireIcon, FolderIcon } from "@heroicons/react/16/solid";
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import ParentFolder from "./parent-folder";
 import { cn } from "@/lib/utils";
 import Image from "next/image";
-import { BookCheckIcon } from "lucide-react";
+import { BookCheckIcon, CheckIcon, Columns2, ImageIcon, ImageUp, Undo2, ZoomIn } from "lucide-react";
+import { open } from "@tauri-apps/plugin-dialog";
+import { readingModes } from "@/app/manga/_components/lib/reading-modes";
+import { fitModes } from "@/app/manga/_components/lib/zoom";
 
 export default function FolderContextMenu({
   folder,
@@ -40,8 +43,59 @@ export default function FolderContextMenu({
     });
   };
 
+  // chapters override their own mode, parent folders set it for every chapter inside
+  const invokeSetReadingMode = (readingMode: ReadingModeType | null) => {
+    const command = isMangaFolder ? "set_chapter_reading_mode" : "set_series_reading_mode";
+    const args = isMangaFolder ? { folderPath: folder.full_path, readingMode } : { seriesPath: folder.full_path, readingMode };
+    invoke(command, args).then(() => {
+      const update = <T extends ParentFolderType>(f: T) => (f.id === folder.id ? { ...f, reading_mode: readingMode } : f);
+      setMangaFolders((prev) => prev.map(update));
+      setParentFolders((prev) => prev.map(update));
+    });
+  };
+
+  // custom keeps the folder's current zoom level, the reader's zoom buttons change it
+  const invokeSetZoom = (fitMode: FitModeType | null) => {
+    const command = isMangaFolder ? "set_chapter_zoom" : "set_series_zoom";
+    const zoomLevel = folder.zoom_level;
+    const args = isMangaFolder ? { folderPath: folder.full_path, fitMode, zoomLevel } : { seriesPath: folder.full_path, fitMode, zoomLevel };
+    invoke(command, args).then(() => {
+      const update = <T extends ParentFolderType>(f: T) => (f.id === folder.id ? { ...f, fit_mode: fitMode, zoom_level: fitMode ? zoomLevel : null } : f);
+      setMangaFolders((prev) => prev.map(update));
+      setParentFolders((prev) => prev.map(update));
+    });
+  };
+
+  // the chosen image is copied by the backend, so the original can be moved or deleted afterwards
+  const invokeSetCoverFile = async () => {
+    const filePath = await open({
+      multiple: false,
+      directory: false,
+      filters: [{ name: "Images", extensions: ["jpg", "jpeg", "png", "gif", "webp"] }],
+    });
+    if (typeof filePath !== "string") return;
+
+    invoke("set_custom_cover_file", { folderPath: folder.full_path, filePath, forSeries: false }).then((coverPath) => {
+      updateCustomCover(coverPath as string);
+    });
+  };
+
+  const invokeResetCover = () => {
+    invoke("reset_custom_cover", { folderPath: folder.full_path, forSeries: false }).then(() => {
+      updateCustomCover(null);
+    });
+  };
+
+  const updateCustomCover = (coverPath: string | null) => {
+    const update = <T extends ParentFolderType>(f: T) => (f.id === folder.id ? { ...f, custom_cover_path: coverPath } : f);
+    setMangaFolders((prev) => prev.map(update));
+    setParentFolders((prev) => prev.map(update));
+  };
+
   const handleInvokeShowInFolder = (path: string) => {
-    invoke("show_in_folder", { path });
+    invoke("show_in_folder", { path }).catch((error) => {
+      console.error("Error Showing In Folder:", path, error);
+    });
   };
 
   return (
@@ -56,6 +110,7 @@ export default function FolderContextMenu({
               !asChild && "hover:scale-[1.005]",
               "relative", // Keep the div relative for positioning
               !(folder as MangaFolderType).is_read && "brightness-50",
+              (folder as MangaFolderType).is_missing && "grayscale opacity-50",
             )}
             onKeyDown={(e) => {
               if (e.key === "Enter") {
@@ -73,7 +128,7 @@ export default function FolderContextMenu({
                 left: 0;
                 right: 0;
                 bottom: 0;
-                background-image: url(${convertFileSrc(folder.cover_panel_path)});
+                background-image: url(${convertFileSrc(folder.custom_cover_path ?? folder.cover_panel_path, "thumbnail")});
                 filter: blur(2px); /* Adjust the blur radius as needed */
                 z-index: 0; /* Place it behind the image */
                 background-size: cover;
@@ -84,7 +139,7 @@ export default function FolderContextMenu({
             <Image
               alt={folder.title}
               className="object-cover w-fit h-auto max-h-40 relative z-10" // Ensure image stays on top
-              src={convertFileSrc(folder.cover_panel_path)}
+              src={convertFileSrc(folder.custom_cover_path ?? folder.cover_panel_path, "thumbnail")}
               priority
               width={500}
               height={500}
@@ -132,6 +187,60 @@ export default function FolderContextMenu({
             <ContextMenuSeparator />
           </>
         )}
+        <ContextMenuSub>
+          <ContextMenuSubTrigger className="flex flex-row items-center gap-0.5">
+            <span>Reading Mode</span>
+            <Columns2 className="h-4 w-auto" />
+          </ContextMenuSubTrigger>
+          <ContextMenuSubContent className="ml-1">
+            {readingModes.map(({ mode, label }) => (
+              <ContextMenuItem key={mode} className="flex flex-row items-center gap-0.5" onClick={() => invokeSetReadingMode(mode)}>
+                <span>{label}</span>
+                {folder.reading_mode === mode && <CheckIcon className="h-4 w-auto" />}
+              </ContextMenuItem>
+            ))}
+            <ContextMenuSeparator />
+            <ContextMenuItem className="flex flex-row items-center gap-0.5" onClick={() => invokeSetReadingMode(null)}>
+              <span>{isMangaFolder ? "Same as Series" : "From ComicInfo"}</span>
+              {folder.reading_mode === null && <CheckIcon className="h-4 w-auto" />}
+            </ContextMenuItem>
+          </ContextMenuSubContent>
+        </ContextMenuSub>
+        <ContextMenuSub>
+          <ContextMenuSubTrigger className="flex flex-row items-center gap-0.5">
+            <span>Zoom</span>
+            <ZoomIn className="h-4 w-auto" />
+          </ContextMenuSubTrigger>
+          <ContextMenuSubContent className="ml-1">
+            {fitModes.map(({ mode, label }) => (
+              <ContextMenuItem key={mode} className="flex flex-row items-center gap-0.5" onClick={() => invokeSetZoom(mode)}>
+                <span>{label}</span>
+                {folder.fit_mode === mode && <CheckIcon className="h-4 w-auto" />}
+              </ContextMenuItem>
+            ))}
+            <ContextMenuSeparator />
+            <ContextMenuItem className="flex flex-row items-center gap-0.5" onClick={() => invokeSetZoom(null)}>
+              <span>{isMangaFolder ? "Same as Series" : "Default"}</span>
+              {folder.fit_mode === null && <CheckIcon className="h-4 w-auto" />}
+            </ContextMenuItem>
+          </ContextMenuSubContent>
+        </ContextMenuSub>
+        <ContextMenuSub>
+          <ContextMenuSubTrigger className="flex flex-row items-center gap-0.5">
+            <span>Cover</span>
+            <ImageIcon className="h-4 w-auto" />
+          </ContextMenuSubTrigger>
+          <ContextMenuSubContent className="ml-1">
+            <ContextMenuItem className="flex flex-row items-center gap-0.5" onClick={invokeSetCoverFile}>
+              <span>Choose Image File...</span>
+              <ImageUp className="h-4 w-auto" />
+            </ContextMenuItem>
+            <ContextMenuItem className="flex flex-row items-center gap-0.5" disabled={!folder.custom_cover_path} onClick={invokeResetCover}>
+              <span>Reset to Automatic</span>
+              <Undo2 className="h-4 w-auto" />
+            </ContextMenuItem>
+          </ContextMenuSubContent>
+        </ContextMenuSub>
         {!asChild && (
           <ContextMenuSub>
             <ContextMenuSubTrigger className="flex flex-row items-center gap-0.5 data-[state=open]:bg-destructive data-[state=open]:text-white transition-colors">
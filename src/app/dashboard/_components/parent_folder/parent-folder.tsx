@@ -1,8 +1,7 @@
 "use client";
-import { DirEntry, readDir } from "@tauri-apps/plugin-fs";
-import { MangaFolderType, ParentFolderType } from "../../page";
+import { ChildFoldersType, MangaFolderType, ParentFolderType } from "../../page";
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
-import { useCallback, useEffect, useState } from "react";
+import { useEffect, useState } from "react";
 import { cn } from "@/lib/utils";
 import { usePathname, useRouter } from "next/navigation";
 // import {
@@ -13,9 +12,6 @@ import { usePathname, useRouter } from "next/navigation";
 import FolderContextMenu from "./folder-context-menu";
 import Image from "next/image";
 import { MangaPanelType } from "@/app/manga/page";
-import { join } from "@tauri-apps/api/path";
-
-const fileTypes = ["jpg", "jpeg", "png", "gif", "webp"];
 
 export default function ParentFolder({ parentFolder }: { parentFolder: ParentFolderType }) {
   // these can hold both manga folders and child folders
@@ -26,65 +22,16 @@ export default function ParentFolder({ parentFolder }: { parentFolder: ParentFol
   const router = useRouter();
   const pathname = usePathname();
 
-  const invokeAddFolders = useCallback((parentDirs: string[], mangaDirs: string[]) => {
-    invoke("update_parent_folders", {
-      dirPaths: JSON.stringify(parentDirs),
-      asChild: true,
-      isExpanded: false,
-    }).then((result: unknown) => {
-      if (result) {
-        const currentParentFolders: ParentFolderType[] = result as ParentFolderType[];
-        setParentFolders(currentParentFolders);
-      }
-    });
-
-    invoke("update_manga_folders", {
-      dirPaths: JSON.stringify(mangaDirs),
-      asChild: true,
-      isExpanded: false,
-    }).then((result: unknown) => {
+  useEffect(() => {
+    // the backend already sorted the folder into volumes and chapters when its root was scanned
+    invoke("get_child_folders", { parentPath: parentFolder.full_path }).then((result: unknown) => {
       if (result) {
-        const currentMangaFolders: MangaFolderType[] = result as MangaFolderType[];
-        setMangaFolders(currentMangaFolders);
+        const children = result as ChildFoldersType;
+        setParentFolders(children.parent_folders);
+        setMangaFolders(children.manga_folders);
       }
     });
-  }, []);
-
-  const readDirSort = useCallback(
-    async (dir: string) => {
-      const parentFolderPaths: string[] = [];
-      const mangaFolderPaths: string[] = [];
-
-      const dirEntries = await readDir(dir);
-
-      const folderPromises = dirEntries.map(async (entry) => {
-        let entryPath = await join(dir, entry.name);
-        if (entry.isDirectory) {
-          const subEntries = await readDir(entryPath);
-          let firstSubEntry = subEntries[0];
-          let firstSubEntryFileType = firstSubEntry.name.split(".")[1];
-          if (firstSubEntry && firstSubEntry.name && fileTypes.includes(firstSubEntryFileType)) {
-            mangaFolderPaths.push(entryPath);
-          } else {
-            parentFolderPaths.push(entryPath);
-          }
-        }
-      });
-
-      await Promise.all(folderPromises);
-
-      if (parentFolderPaths.length > 0 || mangaFolderPaths.length > 0) {
-        parentFolderPaths.sort((a, b) => a.localeCompare(b));
-        mangaFolderPaths.sort((a, b) => a.localeCompare(b));
-        invokeAddFolders(parentFolderPaths, mangaFolderPaths);
-      }
-    },
-    [invokeAddFolders],
-  );
-
-  useEffect(() => {
-    readDirSort(parentFolder.full_path);
-  }, [parentFolder.full_path, readDirSort]);
+  }, [parentFolder.full_path]);
 
   return (
     <ul
@@ -114,9 +61,8 @@ export default function ParentFolder({ parentFolder }: { parentFolder: ParentFol
         )}
         onKeyDown={(e) => {
           if (e.key === "Enter") {
-            invoke("update_parent_folders", {
-              dirPaths: JSON.stringify([parentFolder.full_path]),
-              asChild: parentFolder.as_child,
+            invoke("set_folder_expanded", {
+              folderPath: parentFolder.full_path,
               isExpanded: !isExpanded,
             });
 
@@ -143,18 +89,18 @@ export default function ParentFolder({ parentFolder }: { parentFolder: ParentFol
             left: 0;
             right: 0;
             bottom: 0;
-            background-image: url(${convertFileSrc(parentFolder.cover_panel_path)});
+            background-image: url(${convertFileSrc(parentFolder.custom_cover_path ?? parentFolder.cover_panel_path, "thumbnail")});
             filter: blur(2px); /* Adjust the blur radius as needed */
             z-index: 0; /* Place it behind the image */
             background-size: cover;
             background-position: center;
           }
         `}</style>
-        {parentFolder.cover_panel_path ? (
+        {parentFolder.custom_cover_path || parentFolder.cover_panel_path ? (
           <Image
             alt={parentFolder.title}
             className="object-contain object-left w-full h-full relative z-10" // Ensure image stays on top
-            src={convertFileSrc(parentFolder.cover_panel_path)}
+            src={convertFileSrc(parentFolder.custom_cover_path ?? parentFolder.cover_panel_path, "thumbnail")}
             fill
             priority
             quality={100}
@@ -1,42 +1,75 @@
 "use client";
 
 import { Button } from "@/components/ui/button";
-import { PlusCircleIcon } from "@heroicons/react/16/solid";
-import { open } from "@tauri-apps/plugin-dialog";
+import { ArrowDownTrayIcon, ArrowPathIcon, PlusCircleIcon } from "@heroicons/react/16/solid";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import React, { useState, useEffect } from "react";
 import FolderContexMenu from "./_components/parent_folder/folder-context-menu";
-import { DirEntry, readDir } from "@tauri-apps/plugin-fs";
 import { useRouter } from "next/navigation";
 import DashboardFooter from "./_components/parent_folder/db-footer";
+import LibraryRoots from "./_components/library-roots";
+import Dictionaries from "./_components/dictionaries";
+import AnkiSettings from "./_components/anki-settings";
+
+export type ReadingModeType = "right_to_left" | "left_to_right" | "vertical" | "webtoon";
+
+export type FitModeType = "fit_width" | "fit_height" | "original" | "custom";
+
+export type FolderKindType = "series" | "volume";
 
 export interface ParentFolderType {
   id: string;
+  parent_id: string | null;
+  root_id: string | null;
+  kind: FolderKindType;
   title: string;
   full_path: string;
   as_child: boolean;
   is_expanded: boolean;
   cover_panel_path: string;
+  custom_cover_path: string | null;
+  reading_mode: ReadingModeType | null;
+  fit_mode: FitModeType | null;
+  zoom_level: number | null;
   created_at: string;
   updated_at: string;
 }
 
 export interface MangaFolderType extends ParentFolderType {
-  time_spent_reading: number;
   double_panels: boolean;
   is_read: boolean;
+  is_missing: boolean;
 }
 
-const fileTypes = ["jpg", "jpeg", "png", "gif", "webp"];
+export type ChildFoldersType = {
+  parent_folders: ParentFolderType[];
+  manga_folders: MangaFolderType[];
+};
+
+export type LibraryRootType = {
+  id: string;
+  full_path: string;
+  created_at: string;
+  updated_at: string;
+};
+
+export type LibraryChangeType = {
+  kind: "added" | "removed" | "renamed";
+  path: string;
+  new_path: string | null;
+};
 
 export default function Dashboard() {
   // these can hold both manga folders and child folders
   const [parentFolders, setParentFolders] = useState<ParentFolderType[]>([]);
   // these are folders that hold manga panels with no child folders
   const [mangaFolders, setMangaFolders] = useState<MangaFolderType[]>([]);
+  const [rootsKey, setRootsKey] = useState<number>(0);
   const router = useRouter();
 
-  useEffect(() => {
+  const fetchFolders = () => {
     invoke("get_parent_folders", {}).then((result: unknown) => {
       if (result) {
         setParentFolders(result as ParentFolderType[]);
@@ -48,65 +81,67 @@ export default function Dashboard() {
         setMangaFolders(result as MangaFolderType[]);
       }
     });
+  };
+
+  useEffect(() => {
+    fetchFolders();
+
+    // chapters added, removed or renamed on disk are applied by the watcher
+    const unlisten = listen<LibraryChangeType>("library://changed", () => {
+      fetchFolders();
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
   }, []);
 
   const handleOpenExplorer = () => {
     open({
-      title: "Select Manga Folder",
+      title: "Select Library Folder",
       directory: true,
       multiple: true,
-      recursive: true,
-    }).then((result: any) => {
+    }).then((result: string[] | null) => {
       if (result) {
-        readDirSort(result as string[]);
+        invokeAddLibraryRoots(result);
       }
     });
   };
 
-  function readDirSort(dirs: string[]) {
-    const parentFolderPaths: string[] = [];
-    const mangaFolderPaths: string[] = [];
-
-    for (const dir of dirs) {
-      readDir(dir)
-        .then((dirEntries: DirEntry[]) => {
-          // if the folder directly contains image files its a manga folder
-          if (dirEntries && dirEntries[0] && dirEntries?.[0]?.name) {
-            if (fileTypes.some((fileType) => dirEntries[0]!.name!.toLowerCase().includes(fileType))) {
-              mangaFolderPaths.push(dir);
-            } else {
-              parentFolderPaths.push(dir);
-            }
-          }
-        })
-        .then(() => {
-          if (parentFolderPaths.length > 0 || mangaFolderPaths.length > 0) {
-            //console.log("parentFolderPaths", parentFolderPaths);
-            //console.log("mangaFolderPaths", mangaFolderPaths);
-            invokeAddFolders(parentFolderPaths, mangaFolderPaths);
-          }
-        });
-    }
-  }
-
-  const invokeAddFolders = (parentDirs: string[], mangaDirs: string[]) => {
-    invoke("update_parent_folders", {
-      dirPaths: JSON.stringify(parentDirs),
-      asChild: false,
-      isExpanded: false,
-    }).then((result: unknown) => {
-      if (result) {
-        setParentFolders((prev) => [...prev, ...(result as ParentFolderType[])]);
-      }
+  // the backend walks each root and sorts it into series, volumes and chapters
+  const invokeAddLibraryRoots = (rootPaths: string[]) => {
+    const addRoots = rootPaths.map((rootPath) =>
+      invoke("add_library_root", { rootPath }).catch((error) => {
+        console.error("Error Adding Library Root:", rootPath, error);
+      }),
+    );
+
+    Promise.all(addRoots).then(() => {
+      fetchFolders();
+      setRootsKey((prev) => prev + 1);
     });
+  };
 
-    invoke("update_manga_folders", {
-      dirPaths: JSON.stringify(mangaDirs),
-      asChild: false,
-      isExpanded: false,
-    }).then((result: unknown) => {
-      if (result) {
-        setMangaFolders((prev) => [...prev, ...(result as MangaFolderType[])]);
+  const [isRebuildingThumbnails, setIsRebuildingThumbnails] = useState<boolean>(false);
+
+  const invokeRebuildThumbnails = () => {
+    setIsRebuildingThumbnails(true);
+    invoke("rebuild_thumbnails").finally(() => {
+      setIsRebuildingThumbnails(false);
+    });
+  };
+
+  // roots, reading progress and bookmarks, written to a file the user picks
+  const handleExportLibrary = () => {
+    save({
+      title: "Export Library",
+      defaultPath: "manga-shelf-library.json",
+      filters: [{ name: "JSON", extensions: ["json"] }],
+    }).then((path: string | null) => {
+      if (path) {
+        invoke("export_library", { path }).catch((error) => {
+          console.error("Error Exporting Library:", path, error);
+        });
       }
     });
   };
@@ -122,16 +157,42 @@ export default function Dashboard() {
   return (
     <main className="w-full flex-1 h-full">
       <div className="w-full h-full p-4 lg:px-16 xl:px-36 2xl:px-48">
-        <Button
-          className="flex flex-row justify-center items-center gap-0.5 shadow-sm
+        <div className="flex flex-row items-center gap-2">
+          <Button
+            className="flex flex-row justify-center items-center gap-0.5 shadow-sm
 					"
-          onClick={handleOpenExplorer}
-        >
-          <span className="text-center">Add Manga</span>
-          <span className="text-center">
-            <PlusCircleIcon className="h-3 w-auto" />
-          </span>
-        </Button>
+            onClick={handleOpenExplorer}
+          >
+            <span className="text-center">Add Manga</span>
+            <span className="text-center">
+              <PlusCircleIcon className="h-3 w-auto" />
+            </span>
+          </Button>
+          <Button
+            className="flex flex-row justify-center items-center gap-0.5 shadow-sm"
+            variant="secondary"
+            disabled={isRebuildingThumbnails}
+            onClick={invokeRebuildThumbnails}
+          >
+            <span className="text-center">Rebuild Thumbnails</span>
+            <span className="text-center">
+              <ArrowPathIcon className={`h-3 w-auto ${isRebuildingThumbnails ? "animate-spin" : ""}`} />
+            </span>
+          </Button>
+          <Button
+            className="flex flex-row justify-center items-center gap-0.5 shadow-sm"
+            variant="secondary"
+            onClick={handleExportLibrary}
+          >
+            <span className="text-center">Export Library</span>
+            <span className="text-center">
+              <ArrowDownTrayIcon className="h-3 w-auto" />
+            </span>
+          </Button>
+        </div>
+        <LibraryRoots onChange={fetchFolders} refreshKey={rootsKey} />
+        <Dictionaries />
+        <AnkiSettings />
         {parentFolders.length > 0 && (
           <ul className="w-fit h-fit grid grid-cols-4 gap-2 mt-4">
             {parentFolders.map((folder, index) => {
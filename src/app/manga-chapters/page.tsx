@@ -1,20 +1,17 @@
 "use client";
 import { useCallback, useEffect, useState } from "react";
 import fetchGlobalManga, { fetchGlobalParent } from "../manga/_components/lib/fetch-global-manga";
-import { MangaFolderType, ParentFolderType } from "../dashboard/page";
+import { ChildFoldersType, MangaFolderType, ParentFolderType } from "../dashboard/page";
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
-import { readDir } from "@tauri-apps/plugin-fs";
+import { listen } from "@tauri-apps/api/event";
 import FolderContextMenu from "../dashboard/_components/parent_folder/folder-context-menu";
 import { useRouter } from "next/navigation";
 import Image from "next/image";
-import { join } from "@tauri-apps/api/path";
 import { Button } from "@/components/ui/button";
 import HeroEyeIcon from "../_components/icons/hero-eye-icon";
 import { Tooltip, TooltipProvider, TooltipTrigger, TooltipContent } from "@/components/ui/tooltip";
 import { calculateTimeSpentWatching } from "../stats/_components/main-stats";
-import { MangaPanelType } from "../manga/page";
-
-const fileTypes = ["jpg", "jpeg", "png", "gif", "webp"];
+import { MangaPanelType, ReadingTimeType } from "../manga/page";
 
 export default function MangaChapters() {
   const router = useRouter();
@@ -25,66 +22,18 @@ export default function MangaChapters() {
   const [mangaFolders, setMangaFolders] = useState<MangaFolderType[]>([]);
   const [lastReadMangaFolder, setLastReadMangaFolder] = useState<MangaFolderType>();
   const [lastReadMangaPanel, setLastReadMangaPanel] = useState<MangaPanelType>();
+  const [lastReadTime, setLastReadTime] = useState<ReadingTimeType>();
 
-  const invokeAddFolders = useCallback((parentDirs: string[], mangaDirs: string[]) => {
-    invoke("update_parent_folders", {
-      dirPaths: JSON.stringify(parentDirs),
-      asChild: true,
-      isExpanded: false,
-    }).then((result: unknown) => {
-      if (result) {
-        const currentParentFolders: ParentFolderType[] = result as ParentFolderType[];
-        setParentFolders(currentParentFolders);
-      }
-    });
-
-    invoke("update_manga_folders", {
-      dirPaths: JSON.stringify(mangaDirs),
-      asChild: true,
-      isExpanded: false,
-    }).then((result: unknown) => {
+  const fetchChildFolders = useCallback((parentPath: string) => {
+    invoke("get_child_folders", { parentPath }).then((result: unknown) => {
       if (result) {
-        const currentMangaFolders: MangaFolderType[] = result as MangaFolderType[];
-        setMangaFolders(currentMangaFolders);
+        const children = result as ChildFoldersType;
+        setParentFolders(children.parent_folders);
+        setMangaFolders(children.manga_folders);
       }
     });
   }, []);
 
-  const readDirSort = useCallback(
-    async (dir: string) => {
-      const parentFolderPaths: string[] = [];
-      const mangaFolderPaths: string[] = [];
-
-      const dirEntries = await readDir(dir);
-
-      for (const entry of dirEntries) {
-        let entryPath = await join(dir, entry.name);
-        if (entry.isDirectory) {
-          const subEntries = await readDir(entryPath);
-          let firstSubEntry = subEntries[0];
-
-          if (firstSubEntry) {
-            let firstSubEntryFileType = firstSubEntry.name.split(".")[1];
-            if (fileTypes.includes(firstSubEntryFileType)) {
-              //setFirstMangaFolderPanelPath(`${entryPath}\\${firstSubEntry.name}`);
-              mangaFolderPaths.push(entryPath);
-            } else {
-              parentFolderPaths.push(entryPath);
-            }
-          }
-        }
-      }
-
-      // Sort the arrays after all entries have been processed
-      if (parentFolderPaths.length > 0 || mangaFolderPaths.length > 0) {
-        parentFolderPaths.sort((a, b) => a.localeCompare(b));
-        mangaFolderPaths.sort((a, b) => a.localeCompare(b));
-        invokeAddFolders(parentFolderPaths, mangaFolderPaths);
-      }
-    },
-    [invokeAddFolders],
-  );
-
   const handleMangaClick = (mangaFolderPath: string) => {
     console.log(mangaFolders);
     const index = mangaFolders.findIndex((fold) => fold.full_path === mangaFolderPath);
@@ -122,9 +71,18 @@ export default function MangaChapters() {
   // Second useEffect to populate the folderes
   useEffect(() => {
     if (mainParentFolder) {
-      readDirSort(mainParentFolder.full_path);
+      fetchChildFolders(mainParentFolder.full_path);
+
+      // fetch the folder again when the watcher picks up changes on disk
+      const unlisten = listen("library://changed", () => {
+        fetchChildFolders(mainParentFolder.full_path);
+      });
+
+      return () => {
+        unlisten.then((dispose) => dispose());
+      };
     }
-  }, [mainParentFolder, readDirSort]);
+  }, [mainParentFolder, fetchChildFolders]);
 
   useEffect(() => {
     if (mangaFolders && parentFolders) {
@@ -144,6 +102,9 @@ export default function MangaChapters() {
           const panel = tuple[1];
           setLastReadMangaFolder(folder);
           setLastReadMangaPanel(panel);
+          invoke("get_reading_time", { folderPath: folder.full_path }).then((time: unknown) => {
+            setLastReadTime(time as ReadingTimeType);
+          });
         }
       });
     }
@@ -158,7 +119,7 @@ export default function MangaChapters() {
               <div
                 className="absolute inset-0 z-0"
                 style={{
-                  backgroundImage: `linear-gradient(rgba(0,0,0,.3),rgba(0,0,0,.3)),url(${convertFileSrc(mainParentFolder?.cover_panel_path!)})`,
+                  backgroundImage: `linear-gradient(rgba(0,0,0,.3),rgba(0,0,0,.3)),url(${convertFileSrc((mainParentFolder?.custom_cover_path ?? mainParentFolder?.cover_panel_path)!, "thumbnail")})`,
                   backgroundSize: "cover",
                   backgroundRepeat: "no-repeat",
                   backgroundPosition: "center",
@@ -171,11 +132,11 @@ export default function MangaChapters() {
               <h2 className="mb-2 text-xs w-fit font-semibold z-15 relative bg-muted rounded-sm border-primary border-2 px-1">
                 {mainParentFolder?.updated_at}
               </h2>
-              {mainParentFolder?.cover_panel_path && mainParentFolder.title && (
+              {(mainParentFolder?.custom_cover_path || mainParentFolder?.cover_panel_path) && mainParentFolder.title && (
                 <div className="flex flex-row justify-start items-start gap-2">
                   <Image
                     alt={mainParentFolder?.title}
-                    src={convertFileSrc(mainParentFolder?.cover_panel_path!)}
+                    src={convertFileSrc((mainParentFolder?.custom_cover_path ?? mainParentFolder?.cover_panel_path)!, "thumbnail")}
                     className=" relative h-[175px] md:h-[200px] lg:h-[240px] w-auto z-100 rounded-sm border-primary border-2 shadow-lg"
                     width={500}
                     height={500}
@@ -214,7 +175,7 @@ export default function MangaChapters() {
                                     </p>
                                   ))}
                                 <p className="border-l-2 border-zinc-400 select-none pointer-events-none h-fit w-fit bg-muted shadow-md px-1 rounded-sm">
-                                  TSR ({calculateTimeSpentWatching(lastReadMangaFolder.time_spent_reading)})
+                                  TSR ({calculateTimeSpentWatching(lastReadTime?.seconds)})
                                 </p>
                               </div>
                             </div>
@@ -224,7 +185,7 @@ export default function MangaChapters() {
 =======
                               className="w-fit h-20 shadow-md rounded-sm"
 >>>>>>> 883c846f09603ec607925f5cc0ac859c260586e6
-                              src={convertFileSrc(lastReadMangaPanel?.full_path)}
+                              src={`${convertFileSrc(lastReadMangaPanel?.full_path, "thumbnail")}?size=strip`}
                               alt={`lrmp${lastReadMangaFolder.title}`}
                               width={500}
                               height={500}
@@ -0,0 +1,255 @@
+"use client";
+
+import { MangaFolderType } from "@/app/dashboard/page";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { PencilIcon, TrashIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { BookmarkPlus } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import { cn } from "@/lib/utils";
+
+export type BookmarkColorType = "red" | "orange" | "yellow" | "green" | "blue" | "purple";
+
+export type BookmarkType = {
+  id: string;
+  panel_id: string | null;
+  panel_path: string;
+  title: string | null;
+  note: string;
+  color: BookmarkColorType | null;
+  created_at: string;
+  updated_at: string;
+  // null while the panel is missing from the library
+  chapter_path: string | null;
+  chapter_title: string | null;
+};
+
+export type BookmarkTargetType = {
+  chapter_path: string;
+  panel_path: string;
+  panel_index: number;
+};
+
+type BookmarkScopeType = "chapter" | "series" | "all";
+
+const scopes: { scope: BookmarkScopeType; label: string }[] = [
+  { scope: "chapter", label: "Chapter" },
+  { scope: "series", label: "Series" },
+  { scope: "all", label: "All" },
+];
+
+const colorClasses: Record<BookmarkColorType, string> = {
+  red: "bg-red-500",
+  orange: "bg-orange-500",
+  yellow: "bg-yellow-400",
+  green: "bg-green-500",
+  blue: "bg-blue-500",
+  purple: "bg-purple-500",
+};
+
+type DraftType = {
+  title: string;
+  note: string;
+  color: BookmarkColorType | null;
+};
+
+const emptyDraft: DraftType = { title: "", note: "", color: null };
+
+function fileName(path: string) {
+  return path.split(/[\\/]/).pop() ?? path;
+}
+
+// the folder a chapter is in, its series or the volume below it
+function parentPath(path: string) {
+  return path.replace(/[\\/][^\\/]*$/, "");
+}
+
+function ColorPicker({
+  color,
+  setColor,
+}: {
+  color: BookmarkColorType | null;
+  setColor: (color: BookmarkColorType | null) => void;
+}) {
+  return (
+    <ul className="flex flex-row items-center gap-1">
+      {(Object.keys(colorClasses) as BookmarkColorType[]).map((option) => (
+        <li key={option}>
+          <button
+            title={option}
+            className={cn("h-4 w-4 rounded-full", colorClasses[option], color === option && "ring-2 ring-offset-1 ring-primary")}
+            onClick={() => setColor(color === option ? null : option)}
+          />
+        </li>
+      ))}
+    </ul>
+  );
+}
+
+// bookmarks of the open chapter, its series or the whole library, jumping to one opens its panel
+export default function Bookmarks({
+  currentManga,
+  currentPanelPath,
+  onOpenBookmark,
+}: {
+  currentManga: MangaFolderType;
+  currentPanelPath: string | null;
+  onOpenBookmark: (bookmark: BookmarkType) => void;
+}) {
+  const [scope, setScope] = useState<BookmarkScopeType>("chapter");
+  const [bookmarks, setBookmarks] = useState<BookmarkType[]>([]);
+  const [draft, setDraft] = useState<DraftType>(emptyDraft);
+  // the bookmark being edited, "new" for the current panel
+  const [editing, setEditing] = useState<string | null>(null);
+
+  const fetchBookmarks = useCallback(() => {
+    const listed =
+      scope === "chapter"
+        ? invoke("get_chapter_bookmarks", { chapterPath: currentManga.full_path })
+        : scope === "series"
+          ? invoke("get_series_bookmarks", { seriesPath: parentPath(currentManga.full_path) })
+          : invoke("get_all_bookmarks");
+    listed.then((result: unknown) => {
+      setBookmarks(result as BookmarkType[]);
+    });
+  }, [scope, currentManga]);
+
+  useEffect(() => {
+    fetchBookmarks();
+  }, [fetchBookmarks]);
+
+  const handleEdit = (bookmark: BookmarkType | null) => {
+    setEditing(bookmark ? bookmark.id : "new");
+    setDraft(bookmark ? { title: bookmark.title ?? "", note: bookmark.note, color: bookmark.color } : emptyDraft);
+  };
+
+  const handleSave = () => {
+    const saved =
+      editing === "new"
+        ? invoke("create_bookmark", { panelPath: currentPanelPath, ...draft })
+        : invoke("update_bookmark", { id: editing, ...draft });
+    saved
+      .then(() => {
+        setEditing(null);
+        fetchBookmarks();
+      })
+      .catch((error) => {
+        console.log("Error Saving Bookmark:", error);
+      });
+  };
+
+  const handleDelete = (bookmark: BookmarkType) => {
+    invoke("delete_bookmark", { id: bookmark.id }).then(() => {
+      if (editing === bookmark.id) setEditing(null);
+      fetchBookmarks();
+    });
+  };
+
+  const editor = (
+    <div className="flex flex-col gap-1.5 rounded-sm bg-background p-1.5">
+      <Input
+        className="h-7 text-xs"
+        placeholder="Title"
+        value={draft.title}
+        onChange={(event) => setDraft((prev) => ({ ...prev, title: event.target.value }))}
+      />
+      <textarea
+        className="min-h-16 w-full rounded-md border border-input bg-transparent px-3 py-1 text-xs shadow-sm focus-visible:outline-none focus-visible:ring-1 focus-visible:ring-ring"
+        placeholder="Note"
+        value={draft.note}
+        onChange={(event) => setDraft((prev) => ({ ...prev, note: event.target.value }))}
+      />
+      <div className="flex flex-row items-center justify-between gap-1">
+        <ColorPicker color={draft.color} setColor={(color) => setDraft((prev) => ({ ...prev, color }))} />
+        <div className="flex flex-row gap-1">
+          <Button className="h-6 px-2 text-xs" variant="outline" onClick={() => setEditing(null)}>
+            Cancel
+          </Button>
+          <Button className="h-6 px-2 text-xs" onClick={handleSave}>
+            Save
+          </Button>
+        </div>
+      </div>
+    </div>
+  );
+
+  return (
+    <aside className="fixed right-1 top-24 z-40 flex max-h-[75dvh] w-72 flex-col gap-1.5 rounded-sm border-2 border-accent bg-secondary p-1.5 shadow-lg">
+      <div className="flex flex-row items-center justify-between gap-1">
+        <ul className="flex flex-row gap-1">
+          {scopes.map((option) => (
+            <li key={option.scope}>
+              <Button
+                className={cn("h-6 px-2 text-xs", scope === option.scope && "bg-accent text-accent-foreground")}
+                onClick={() => setScope(option.scope)}
+              >
+                {option.label}
+              </Button>
+            </li>
+          ))}
+        </ul>
+        <Button
+          className="h-6 px-1"
+          title="Bookmark This Panel"
+          disabled={!currentPanelPath}
+          onClick={() => handleEdit(null)}
+        >
+          <BookmarkPlus className="h-4 w-4" />
+        </Button>
+      </div>
+      {editing === "new" && editor}
+      <ul className="flex flex-col gap-1 overflow-y-auto">
+        {bookmarks.length === 0 && <li className="p-1 text-xs text-muted-foreground">No bookmarks</li>}
+        {bookmarks.map((bookmark) =>
+          editing === bookmark.id ? (
+            <li key={bookmark.id}>{editor}</li>
+          ) : (
+            <li
+              key={bookmark.id}
+              className={cn(
+                "flex flex-row items-start gap-1.5 rounded-sm bg-background p-1.5",
+                bookmark.chapter_path ? "cursor-pointer hover:bg-accent/40" : "opacity-60",
+              )}
+              title={bookmark.chapter_path ? undefined : "The panel is missing from the library"}
+              onClick={() => bookmark.chapter_path && onOpenBookmark(bookmark)}
+            >
+              <span
+                className={cn("mt-1 h-2.5 w-2.5 shrink-0 rounded-full", bookmark.color ? colorClasses[bookmark.color] : "bg-muted")}
+              />
+              <div className="flex min-w-0 grow flex-col">
+                <h2 className="truncate text-xs font-semibold">{bookmark.title ?? fileName(bookmark.panel_path)}</h2>
+                {scope !== "chapter" && (
+                  <p className="truncate text-[10px] text-muted-foreground">
+                    {bookmark.chapter_title ?? fileName(parentPath(bookmark.panel_path))}
+                  </p>
+                )}
+                {bookmark.note && <p className="whitespace-pre-wrap break-words text-xs">{bookmark.note}</p>}
+              </div>
+              <div className="flex shrink-0 flex-row gap-0.5">
+                <button
+                  title="Edit Bookmark"
+                  onClick={(event) => {
+                    event.stopPropagation();
+                    handleEdit(bookmark);
+                  }}
+                >
+                  <PencilIcon className="h-3 w-3" />
+                </button>
+                <button
+                  title="Delete Bookmark"
+                  onClick={(event) => {
+                    event.stopPropagation();
+                    handleDelete(bookmark);
+                  }}
+                >
+                  <TrashIcon className="h-3 w-3" />
+                </button>
+              </div>
+            </li>
+          ),
+        )}
+      </ul>
+    </aside>
+  );
+}
@@ -0,0 +1,97 @@
+"use client";
+
+import { useRef, useState } from "react";
+
+// a region of the panel in the image's own pixels
+export type CoverRegionType = {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+};
+
+type Point = { x: number; y: number };
+
+// drags shorter than this are clicks and pick the whole panel
+const MIN_DRAG = 8;
+
+export default function CoverPicker({ onPick }: { onPick: (region: CoverRegionType | null) => void }) {
+  const overlayRef = useRef<HTMLDivElement>(null);
+  const [start, setStart] = useState<Point | null>(null);
+  const [end, setEnd] = useState<Point | null>(null);
+
+  const pointFromEvent = (event: React.PointerEvent<HTMLDivElement>): Point => {
+    const bounds = event.currentTarget.getBoundingClientRect();
+    return {
+      x: Math.min(Math.max(event.clientX - bounds.left, 0), bounds.width),
+      y: Math.min(Math.max(event.clientY - bounds.top, 0), bounds.height),
+    };
+  };
+
+  const handlePointerDown = (event: React.PointerEvent<HTMLDivElement>) => {
+    event.currentTarget.setPointerCapture(event.pointerId);
+    const point = pointFromEvent(event);
+    setStart(point);
+    setEnd(point);
+  };
+
+  const handlePointerMove = (event: React.PointerEvent<HTMLDivElement>) => {
+    if (start) setEnd(pointFromEvent(event));
+  };
+
+  const handlePointerUp = (event: React.PointerEvent<HTMLDivElement>) => {
+    if (!start) return;
+    const point = pointFromEvent(event);
+    setStart(null);
+    setEnd(null);
+
+    const overlay = overlayRef.current;
+    const image = overlay?.parentElement?.querySelector("img");
+    if (!overlay || !image || (Math.abs(point.x - start.x) < MIN_DRAG && Math.abs(point.y - start.y) < MIN_DRAG)) {
+      onPick(null);
+      return;
+    }
+
+    // the selection is drawn over the panel's box, move it onto the displayed image and
+    // scale it back up to the panel's own size
+    const overlayBounds = overlay.getBoundingClientRect();
+    const imageBounds = image.getBoundingClientRect();
+    const toImage = (value: number, offset: number, size: number) => Math.min(Math.max(value - offset, 0), size);
+    const left = toImage(Math.min(start.x, point.x), imageBounds.left - overlayBounds.left, imageBounds.width);
+    const right = toImage(Math.max(start.x, point.x), imageBounds.left - overlayBounds.left, imageBounds.width);
+    const top = toImage(Math.min(start.y, point.y), imageBounds.top - overlayBounds.top, imageBounds.height);
+    const bottom = toImage(Math.max(start.y, point.y), imageBounds.top - overlayBounds.top, imageBounds.height);
+
+    const scaleX = image.naturalWidth / imageBounds.width;
+    const scaleY = image.naturalHeight / imageBounds.height;
+    const x = Math.round(left * scaleX);
+    const y = Math.round(top * scaleY);
+    const width = Math.min(Math.round((right - left) * scaleX), image.naturalWidth - x);
+    const height = Math.min(Math.round((bottom - top) * scaleY), image.naturalHeight - y);
+    // a drag that missed the image entirely falls back to the whole panel
+    onPick(width > 0 && height > 0 ? { x, y, width, height } : null);
+  };
+
+  return (
+    <div
+      ref={overlayRef}
+      className="absolute inset-0 z-20 cursor-crosshair bg-black/20 select-none"
+      title="Click to use the whole panel, drag to crop"
+      onPointerDown={handlePointerDown}
+      onPointerMove={handlePointerMove}
+      onPointerUp={handlePointerUp}
+    >
+      {start && end && (
+        <div
+          className="absolute border-2 border-accent bg-accent/20 pointer-events-none"
+          style={{
+            left: Math.min(start.x, end.x),
+            top: Math.min(start.y, end.y),
+            width: Math.abs(end.x - start.x),
+            height: Math.abs(end.y - start.y),
+          }}
+        />
+      )}
+    </div>
+  );
+}
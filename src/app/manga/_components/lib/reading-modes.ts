@@ -0,0 +1,23 @@
+import { ReadingModeType } from "@/app/dashboard/page";
+
+export type EffectiveReadingModeType = {
+  mode: ReadingModeType;
+  source: "chapter" | "series" | "comic_info" | "default";
+  double_panels: boolean;
+};
+
+export const readingModes: { mode: ReadingModeType; label: string }[] = [
+  { mode: "right_to_left", label: "Right to Left" },
+  { mode: "left_to_right", label: "Left to Right" },
+  { mode: "vertical", label: "Vertical" },
+  { mode: "webtoon", label: "Webtoon" },
+];
+
+// the keys that turn to the next and previous panel in each mode
+export const navigationKeys: Record<ReadingModeType, { next: string; previous: string } | null> = {
+  right_to_left: { next: "ArrowLeft", previous: "ArrowRight" },
+  left_to_right: { next: "ArrowRight", previous: "ArrowLeft" },
+  vertical: { next: "ArrowDown", previous: "ArrowUp" },
+  // webtoon chapters are scrolled, the arrow keys keep their default behaviour
+  webtoon: null,
+};
@@ -0,0 +1,18 @@
+export type LayoutPanelType = {
+  index: number;
+  name: string;
+  path: string;
+  width: number;
+  height: number;
+  kind: "single" | "spread" | "cover";
+};
+
+// one or two panels shown side by side, in reading order
+export type SpreadType = {
+  panels: LayoutPanelType[];
+};
+
+export type PanelLayoutType = {
+  spread_offset: boolean;
+  spreads: SpreadType[];
+};
@@ -0,0 +1,14 @@
+import { FitModeType } from "@/app/dashboard/page";
+
+export type EffectiveZoomType = {
+  fit_mode: FitModeType;
+  zoom_level: number;
+  source: "chapter" | "series" | "default";
+};
+
+export const fitModes: { mode: FitModeType; label: string }[] = [
+  { mode: "fit_height", label: "Fit Height" },
+  { mode: "fit_width", label: "Fit Width" },
+  { mode: "original", label: "Original Size" },
+  { mode: "custom", label: "Custom" },
+];
@@ -0,0 +1,189 @@
+"use client";
+
+import { PlusCircleIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+
+export type FrequencyType = {
+  dictionary: string;
+  value: number;
+  display_value: string | null;
+};
+
+export type TermEntryType = {
+  expression: string;
+  reading: string;
+  source: string;
+  reasons: string[];
+  definition_tags: string[];
+  term_tags: string[];
+  glossary: string[];
+  score: number;
+  dictionary: string;
+  frequencies: FrequencyType[];
+};
+
+export type KanjiEntryType = {
+  character: string;
+  onyomi: string[];
+  kunyomi: string[];
+  tags: string[];
+  meanings: string[];
+  dictionary: string;
+  frequencies: FrequencyType[];
+};
+
+export type LookupResultType = {
+  terms: TermEntryType[];
+  kanji: KanjiEntryType[];
+};
+
+export type LookupRequestType = {
+  blockId: string;
+  text: string;
+  cursor: number;
+  x: number;
+  y: number;
+};
+
+// "new" until checked against anki, and "unavailable" when anki couldn't be reached
+type MineStateType = "new" | "addable" | "duplicate" | "adding" | "added" | "unavailable";
+
+const mineLabels: Record<MineStateType, string> = {
+  new: "Add to Anki",
+  addable: "Add to Anki",
+  duplicate: "Already in Anki",
+  adding: "Adding...",
+  added: "Added to Anki",
+  unavailable: "Add to Anki",
+};
+
+function minedWord(blockId: string, term: TermEntryType) {
+  return {
+    block_id: blockId,
+    word: term.expression,
+    reading: term.reading,
+    definition: term.glossary.join("\n"),
+  };
+}
+
+function Frequencies({ frequencies }: { frequencies: FrequencyType[] }) {
+  if (frequencies.length === 0) return null;
+
+  return (
+    <ul className="flex flex-row flex-wrap gap-1">
+      {frequencies.map((frequency, index) => (
+        <li key={index} className="rounded-sm bg-gray-200 px-1 text-[10px]">
+          {frequency.dictionary} {frequency.display_value ?? frequency.value}
+        </li>
+      ))}
+    </ul>
+  );
+}
+
+// dictionary entries for the text under a click on the text layer
+export default function LookupPopup({ request, onClose }: { request: LookupRequestType; onClose: () => void }) {
+  const [result, setResult] = useState<LookupResultType | null>(null);
+  const [mineStates, setMineStates] = useState<MineStateType[]>([]);
+  const [mineError, setMineError] = useState<string | null>(null);
+
+  useEffect(() => {
+    let isCurrent = true;
+    setMineError(null);
+
+    const setMineState = (index: number, state: MineStateType) => {
+      if (!isCurrent) return;
+      setMineStates((prev) => prev.map((previous, i) => (i === index ? state : previous)));
+    };
+
+    invoke<LookupResultType>("lookup_text", { text: request.text, cursor: request.cursor }).then((lookup) => {
+      if (!isCurrent) return;
+      setResult(lookup);
+      setMineStates(lookup.terms.map(() => "new"));
+
+      lookup.terms.forEach((term, index) => {
+        invoke<boolean>("can_mine_word", { word: minedWord(request.blockId, term) })
+          .then((canMine) => setMineState(index, canMine ? "addable" : "duplicate"))
+          .catch(() => setMineState(index, "unavailable"));
+      });
+    });
+
+    return () => {
+      isCurrent = false;
+    };
+  }, [request]);
+
+  useEffect(() => {
+    const handleKeyDown = (event: KeyboardEvent) => {
+      if (event.key === "Escape") onClose();
+    };
+    window.addEventListener("keydown", handleKeyDown);
+
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, [onClose]);
+
+  const invokeMineWord = (term: TermEntryType, index: number) => {
+    setMineStates((prev) => prev.map((state, i) => (i === index ? "adding" : state)));
+    invoke("mine_word", { word: minedWord(request.blockId, term) })
+      .then(() => {
+        setMineStates((prev) => prev.map((state, i) => (i === index ? "added" : state)));
+      })
+      .catch((e) => {
+        setMineError((e as { message?: string }).message ?? String(e));
+        setMineStates((prev) => prev.map((state, i) => (i === index ? "unavailable" : state)));
+      });
+  };
+
+  if (!result) {
+    return null;
+  }
+
+  return (
+    <div
+      className="fixed z-50 pointer-events-auto max-h-96 w-96 overflow-y-auto rounded-sm bg-white p-2 text-sm text-black shadow-lg"
+      style={{ left: Math.min(request.x, window.innerWidth - 400), top: Math.min(request.y, window.innerHeight - 400) }}
+      onMouseLeave={onClose}
+    >
+      {result.terms.length === 0 && result.kanji.length === 0 && <p className="text-xs text-gray-500">No results</p>}
+      {mineError && <p className="text-xs text-red-500">{mineError}</p>}
+      <ul className="flex flex-col gap-2">
+        {result.terms.map((term, index) => (
+          <li key={index} className="flex flex-col gap-0.5 border-b border-gray-200 pb-1">
+            <div className="flex flex-row items-baseline gap-2">
+              <span className="text-lg">{term.expression}</span>
+              {term.reading !== term.expression && <span className="text-xs">{term.reading}</span>}
+              {term.reasons.length > 0 && <span className="text-[10px] text-gray-500">« {term.reasons.join(" « ")}</span>}
+              <button
+                className="ml-auto flex flex-row items-center gap-0.5 text-[10px] text-gray-500 enabled:hover:text-black"
+                disabled={["duplicate", "adding", "added"].includes(mineStates[index])}
+                onClick={() => invokeMineWord(term, index)}
+              >
+                {mineLabels[mineStates[index] ?? "new"]}
+                <PlusCircleIcon className="h-3 w-auto" />
+              </button>
+            </div>
+            <Frequencies frequencies={term.frequencies} />
+            <p className="text-[10px] text-gray-500">
+              {term.dictionary} {term.definition_tags.join(" ")}
+            </p>
+            <ol className="list-decimal pl-4 whitespace-pre-line">
+              {term.glossary.map((gloss, glossIndex) => (
+                <li key={glossIndex}>{gloss}</li>
+              ))}
+            </ol>
+          </li>
+        ))}
+        {result.kanji.map((kanji, index) => (
+          <li key={`kanji-${index}`} className="flex flex-col gap-0.5">
+            <div className="flex flex-row items-baseline gap-2">
+              <span className="text-2xl">{kanji.character}</span>
+              <span className="text-xs">{[...kanji.onyomi, ...kanji.kunyomi].join("、")}</span>
+            </div>
+            <Frequencies frequencies={kanji.frequencies} />
+            <p>{kanji.meanings.join(", ")}</p>
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
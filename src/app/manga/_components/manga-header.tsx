@@ -1,6 +1,6 @@
 "use client";
 
-import { MangaFolderType } from "@/app/dashboard/page";
+import { FitModeType, MangaFolderType, ReadingModeType } from "@/app/dashboard/page";
 import { Button } from "@/components/ui/button";
 import {
   ChevronDoubleLeftIcon,
@@ -13,17 +13,53 @@ import {
 import { invoke } from "@tauri-apps/api/core";
 import {
   //BetweenVerticalStart,
+  ArrowDown,
+  ArrowLeft,
+  ArrowRight,
+  Bookmark,
   Columns2,
+  Expand,
+  FoldHorizontal,
+  ImagePlus,
+  MoveHorizontal,
+  MoveVertical,
   RectangleVertical,
+  Ruler,
+  ScrollText,
 } from "lucide-react";
 import { useCallback, useEffect } from "react";
+import { cn } from "@/lib/utils";
+import { EffectiveReadingModeType, navigationKeys, readingModes } from "./lib/reading-modes";
+import { EffectiveZoomType, fitModes } from "./lib/zoom";
+
+const readingModeIcons: Record<ReadingModeType, React.ReactNode> = {
+  right_to_left: <ArrowLeft className="h-4 w-4" />,
+  left_to_right: <ArrowRight className="h-4 w-4" />,
+  vertical: <ArrowDown className="h-4 w-4" />,
+  webtoon: <ScrollText className="h-4 w-4" />,
+};
+
+const fitModeIcons: Record<FitModeType, React.ReactNode> = {
+  fit_height: <MoveVertical className="h-4 w-4" />,
+  fit_width: <MoveHorizontal className="h-4 w-4" />,
+  original: <Expand className="h-4 w-4" />,
+  custom: <Ruler className="h-4 w-4" />,
+};
 
 const MangaHeader = ({
   currentManga,
+  readingMode,
+  handleSetReadingMode,
   doublePanels,
-  currentPanelPath,
+  spreadOffset,
+  handleToggleSpreadOffset,
+  fitMode,
   zoomLevel,
-  setZoomLevel,
+  handleSetZoom,
+  coverTarget,
+  setCoverTarget,
+  showBookmarks,
+  setShowBookmarks,
   setIsDoublePanels,
   handleNextPanel,
   handleNextSinglePanel,
@@ -33,10 +69,18 @@ const MangaHeader = ({
   handleSetFirstPanel,
 }: {
   currentManga: MangaFolderType;
+  readingMode: ReadingModeType;
+  handleSetReadingMode: (effective: EffectiveReadingModeType) => void;
   doublePanels: boolean;
-  currentPanelPath: string;
+  spreadOffset: boolean;
+  handleToggleSpreadOffset: () => void;
+  fitMode: FitModeType;
   zoomLevel: number;
-  setZoomLevel: React.Dispatch<React.SetStateAction<number>>;
+  handleSetZoom: (effective: EffectiveZoomType) => void;
+  coverTarget: "series" | "chapter" | null;
+  setCoverTarget: React.Dispatch<React.SetStateAction<"series" | "chapter" | null>>;
+  showBookmarks: boolean;
+  setShowBookmarks: React.Dispatch<React.SetStateAction<boolean>>;
   setIsDoublePanels: React.Dispatch<React.SetStateAction<boolean>>;
   handleNextPanel: () => void;
   handleNextSinglePanel: () => void;
@@ -45,36 +89,53 @@ const MangaHeader = ({
   handleSetLastPanel: () => void;
   handleSetFirstPanel: () => void;
 }) => {
+  // saved to the series, or to the chapter when it overrides the series' zoom
+  const invokeUpdateZoom = useCallback(
+    (newFitMode: FitModeType, newZoomLevel: number) => {
+      invoke("update_zoom", {
+        folderPath: currentManga.full_path,
+        fitMode: newFitMode,
+        zoomLevel: newZoomLevel,
+      }).then((result) => {
+        handleSetZoom(result as EffectiveZoomType);
+      });
+    },
+    [currentManga, handleSetZoom],
+  );
+
+  // zooming switches to a custom size, starting from the last one used
   const handleMagnify = useCallback(() => {
-    setZoomLevel((prev) => prev + 10);
-    invoke("update_manga_panel", {
-      dirPaths: JSON.stringify([currentPanelPath]),
-      isRead: true,
-      zoomLevel: zoomLevel + 10,
-    });
-  }, [currentPanelPath, zoomLevel, setZoomLevel]);
+    invokeUpdateZoom("custom", zoomLevel + 10);
+  }, [zoomLevel, invokeUpdateZoom]);
 
   const handleMinify = useCallback(() => {
-    setZoomLevel((prev) => prev - 10);
-    invoke("update_manga_panel", {
-      dirPaths: JSON.stringify([currentPanelPath]),
-      isRead: true,
-      zoomLevel: zoomLevel - 10,
-    });
-  }, [currentPanelPath, zoomLevel, setZoomLevel]);
+    invokeUpdateZoom("custom", Math.max(zoomLevel - 10, 10));
+  }, [zoomLevel, invokeUpdateZoom]);
+
+  const handleCycleFitMode = () => {
+    const index = fitModes.findIndex(({ mode }) => mode === fitMode);
+    invokeUpdateZoom(fitModes[(index + 1) % fitModes.length].mode, zoomLevel);
+  };
+
+  // only the paged horizontal modes can show two panels side by side
+  const pairsPanels = readingMode === "right_to_left" || readingMode === "left_to_right";
+  const keys = navigationKeys[readingMode];
+  // the arrow buttons point the way the panels turn
+  const isRightToLeft = readingMode === "right_to_left";
 
   const handleKeyDown = useCallback(
     (event: KeyboardEvent) => {
       // set first and last panel
-      if (event.ctrlKey && event.key === "ArrowRight") {
+      if (keys && event.ctrlKey && event.key === keys.previous) {
         handleSetFirstPanel();
-      } else if (event.ctrlKey && event.key === "ArrowLeft") {
+      } else if (keys && event.ctrlKey && event.key === keys.next) {
         handleSetLastPanel();
       }
       // previous panels
-      else if (event.shiftKey && event.key === "ArrowRight") {
+      else if (keys && event.shiftKey && event.key === keys.previous) {
         handlePreviousSinglePanel();
-      } else if (event.key === "ArrowRight") {
+      } else if (keys && event.key === keys.previous) {
+        event.preventDefault();
         if (doublePanels) {
           handlePreviousPanel();
         } else {
@@ -82,9 +143,10 @@ const MangaHeader = ({
         }
       }
       // next panels
-      else if (event.shiftKey && event.key === "ArrowLeft") {
+      else if (keys && event.shiftKey && event.key === keys.next) {
         handleNextSinglePanel();
-      } else if (event.key === "ArrowLeft") {
+      } else if (keys && event.key === keys.next) {
+        event.preventDefault();
         if (doublePanels) {
           handleNextPanel();
         } else {
@@ -92,9 +154,13 @@ const MangaHeader = ({
         }
       }
 			// double panels
-			else if (event.key === "p") {
+			else if (event.key === "p" && pairsPanels) {
 				handleSetDoublePanels();
 			}
+			// shift the pairing by one panel
+			else if (event.key === "o" && doublePanels) {
+				handleToggleSpreadOffset();
+			}
 
       // handle zoom level
       if (event.ctrlKey && event.key === "=") {
@@ -113,6 +179,9 @@ const MangaHeader = ({
       handleMagnify,
       handleMinify,
       doublePanels,
+      handleToggleSpreadOffset,
+      keys,
+      pairsPanels,
     ],
   );
 
@@ -126,6 +195,17 @@ const MangaHeader = ({
     });
   };
 
+  // overrides the mode of this chapter only, series wide modes are set from the folder's context menu
+  const handleCycleReadingMode = () => {
+    const index = readingModes.findIndex(({ mode }) => mode === readingMode);
+    invoke("set_chapter_reading_mode", {
+      folderPath: currentManga.full_path,
+      readingMode: readingModes[(index + 1) % readingModes.length].mode,
+    }).then((result) => {
+      handleSetReadingMode(result as EffectiveReadingModeType);
+    });
+  };
+
   useEffect(() => {
     addEventListener("keydown", handleKeyDown);
 
@@ -151,24 +231,86 @@ const MangaHeader = ({
           <li className="flex flex-row justify-center items-center">
             <Button
               className="py-0.5 px-1 shadow-lg"
-              onClick={handleSetDoublePanels}
+              title={fitModes.find(({ mode }) => mode === fitMode)?.label}
+              onClick={handleCycleFitMode}
+            >
+              {fitModeIcons[fitMode]}
+            </Button>
+          </li>
+          {pairsPanels && (
+            <li className="flex flex-row justify-center items-center">
+              <Button
+                className="py-0.5 px-1 shadow-lg"
+                onClick={handleSetDoublePanels}
+              >
+                {doublePanels ? (
+                  <RectangleVertical className="h-4 w-4" />
+                ) : (
+                  <Columns2 className="h-4 w-4" />
+                )}
+              </Button>
+            </li>
+          )}
+          {pairsPanels && doublePanels && (
+            <li className="flex flex-row justify-center items-center">
+              <Button
+                className={cn("py-0.5 px-1 shadow-lg", spreadOffset && "bg-accent text-accent-foreground")}
+                title="Shift Pairs by One Panel"
+                onClick={handleToggleSpreadOffset}
+              >
+                <FoldHorizontal className="h-4 w-4" />
+              </Button>
+            </li>
+          )}
+          <li className="flex flex-row justify-center items-center">
+            <Button
+              className="py-0.5 px-1 shadow-lg"
+              title={readingModes.find(({ mode }) => mode === readingMode)?.label}
+              onClick={handleCycleReadingMode}
+            >
+              {readingModeIcons[readingMode]}
+            </Button>
+          </li>
+          {readingMode !== "webtoon" && (
+            <li className="flex flex-row justify-center items-center">
+              <Button
+                className={cn("py-0.5 px-1 shadow-lg", coverTarget && "bg-accent text-accent-foreground")}
+                title="Pick Cover From Panel"
+                onClick={() => setCoverTarget((prev) => (prev ? null : "series"))}
+              >
+                <ImagePlus className="h-4 w-4" />
+              </Button>
+            </li>
+          )}
+          <li className="flex flex-row justify-center items-center">
+            <Button
+              className={cn("py-0.5 px-1 shadow-lg", showBookmarks && "bg-accent text-accent-foreground")}
+              title="Bookmarks"
+              onClick={() => setShowBookmarks((prev) => !prev)}
             >
-              {doublePanels ? (
-                <RectangleVertical className="h-4 w-4" />
-              ) : (
-                <Columns2 className="h-4 w-4" />
-              )}
+              <Bookmark className="h-4 w-4" />
             </Button>
           </li>
+          {coverTarget && (
+            <li className="flex flex-row justify-center items-center">
+              <Button
+                className="py-0.5 px-1 shadow-lg text-xs font-semibold"
+                title="Save the Cover to the Series or This Chapter"
+                onClick={() => setCoverTarget((prev) => (prev === "series" ? "chapter" : "series"))}
+              >
+                {coverTarget === "series" ? "Series Cover" : "Chapter Cover"}
+              </Button>
+            </li>
+          )}
         </ul>
         <ul className="w-full h-full flex flex-row justify-center items-center gap-2">
           <li className="flex flex-row justify-center items-center">
-            <Button className="p-0 shadow-lg" onClick={handleSetLastPanel}>
+            <Button className="p-0 shadow-lg" onClick={isRightToLeft ? handleSetLastPanel : handleSetFirstPanel}>
               <ChevronDoubleLeftIcon className="h-5" />
             </Button>
           </li>
           <li className="flex flex-row justify-center items-center">
-            <Button className="p-0 shadow-lg" onClick={handleNextPanel}>
+            <Button className="p-0 shadow-lg" onClick={isRightToLeft ? handleNextPanel : handlePreviousPanel}>
               <ChevronLeftIcon className="h-5" />
             </Button>
           </li>
@@ -178,12 +320,12 @@ const MangaHeader = ({
             </h1>
           </li>
           <li className="flex flex-row justify-center items-center">
-            <Button className="p-0 shadow-lg" onClick={handlePreviousPanel}>
+            <Button className="p-0 shadow-lg" onClick={isRightToLeft ? handlePreviousPanel : handleNextPanel}>
               <ChevronRightIcon className="h-5" />
             </Button>
           </li>
           <li className="flex flex-row justify-center items-center">
-            <Button className="p-0 shadow-lg" onClick={handleSetFirstPanel}>
+            <Button className="p-0 shadow-lg" onClick={isRightToLeft ? handleSetFirstPanel : handleSetLastPanel}>
               <ChevronDoubleRightIcon className="h-5" />
             </Button>
           </li>
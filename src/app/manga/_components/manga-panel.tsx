@@ -2,21 +2,37 @@ import { cn } from "@/lib/utils";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import Image from "next/image";
 import { FileEntry } from "../page";
+import { FitModeType } from "@/app/dashboard/page";
+import CoverPicker, { CoverRegionType } from "./cover-picker";
+import TextLayer from "./text-layer";
+
+// the size of the panel's box and of the image inside it for each fit mode
+const fitModeStyles: Record<FitModeType, { box: (zoomLevel: number) => React.CSSProperties; image: string }> = {
+  fit_height: { box: () => ({ height: "100%", width: "auto" }), image: "w-fit h-full" },
+  fit_width: { box: () => ({ height: "auto", width: "100%" }), image: "w-full h-auto" },
+  original: { box: () => ({ height: "auto", width: "auto" }), image: "w-auto h-auto max-w-none" },
+  custom: { box: (zoomLevel) => ({ height: `${zoomLevel}px`, width: "auto" }), image: "w-fit h-full" },
+};
 
 export default function MangaPanel({
   currentPanel,
   secondPanel,
+  fitMode,
   zoomLevel,
   width,
   height,
   isDoublePanels,
+  onPickCover,
 }: {
   currentPanel: FileEntry;
   secondPanel: boolean;
+  fitMode: FitModeType;
   zoomLevel: number;
   width: number;
   height: number;
   isDoublePanels?: boolean;
+  // set while the reader is picking a cover from this panel
+  onPickCover?: (panel: FileEntry, region: CoverRegionType | null) => void;
 }) {
   return (
     <div className={cn("w-full h-full flex flex-col justify-center items-center pt-1", !isDoublePanels && secondPanel && "hidden")}>
@@ -29,24 +45,28 @@ export default function MangaPanel({
           )}
           <div
             id="IMAGE-DIV"
-            style={{
-              height: `${zoomLevel}px`,
-              width: "auto",
-            }}
+            className="relative"
+            style={fitModeStyles[fitMode].box(zoomLevel)}
           >
             <Image
-              src={convertFileSrc(currentPanel.path)}
+              src={convertFileSrc(currentPanel.path, "panel")}
               alt={currentPanel.name}
               width={width}
               height={height}
               quality={100}
               priority={true}
               className={cn(
-                "w-fit h-full pointer-events-none",
+                fitModeStyles[fitMode].image,
+                "pointer-events-none",
                 !secondPanel && "shadow-[10px_0_20px_-10px_rgba(0,0,0,0.45)]",
                 secondPanel && "shadow-[-10px_0_20px_-10px_rgba(0,0,0,0.45)]",
               )}
             />
+            {onPickCover ? (
+              <CoverPicker onPick={(region) => onPickCover(currentPanel, region)} />
+            ) : (
+              <TextLayer panelPath={currentPanel.path} />
+            )}
           </div>
         </>
       )}
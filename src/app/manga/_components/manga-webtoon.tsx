@@ -0,0 +1,94 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+import Image from "next/image";
+import { cn } from "@/lib/utils";
+import { useEffect, useRef } from "react";
+import { FileEntry } from "../page";
+import { FitModeType } from "@/app/dashboard/page";
+
+// webtoon panels are stacked, so every fit mode is about the strip's width
+const stripWidths: Record<FitModeType, (zoomLevel: number) => string> = {
+  fit_width: () => "100%",
+  fit_height: () => "auto",
+  original: () => "auto",
+  custom: (zoomLevel) => `${zoomLevel}px`,
+};
+
+export default function MangaWebtoon({
+  mangaPanels,
+  startPanelIndex,
+  fitMode,
+  zoomLevel,
+  onPanelVisible,
+}: {
+  mangaPanels: FileEntry[];
+  startPanelIndex: number;
+  fitMode: FitModeType;
+  zoomLevel: number;
+  onPanelVisible: (index: number) => void;
+}) {
+  const panelRefs = useRef<(HTMLDivElement | null)[]>([]);
+  const onPanelVisibleRef = useRef(onPanelVisible);
+  onPanelVisibleRef.current = onPanelVisible;
+  const visiblePanelIndex = useRef(-1);
+
+  // jump when the index was changed from outside, e.g. the first and last panel buttons,
+  // not when it follows the scrolling
+  useEffect(() => {
+    if (startPanelIndex !== visiblePanelIndex.current) {
+      panelRefs.current[startPanelIndex]?.scrollIntoView();
+    }
+  }, [mangaPanels, startPanelIndex]);
+
+  useEffect(() => {
+    const observer = new IntersectionObserver(
+      (entries) => {
+        for (const entry of entries) {
+          if (entry.isIntersecting) {
+            visiblePanelIndex.current = Number((entry.target as HTMLElement).dataset.index);
+            onPanelVisibleRef.current(visiblePanelIndex.current);
+          }
+        }
+      },
+      // a panel counts as reached once it crosses the middle of the screen
+      { rootMargin: "-50% 0px -50% 0px" },
+    );
+
+    for (const panel of panelRefs.current) {
+      if (panel) observer.observe(panel);
+    }
+
+    return () => {
+      observer.disconnect();
+    };
+  }, [mangaPanels]);
+
+  return (
+    <div className="w-full h-full overflow-y-auto flex flex-col items-center">
+      {mangaPanels.map((panel, index) => (
+        <div
+          key={`${panel.path}-manga-webtoon`}
+          ref={(element) => {
+            panelRefs.current[index] = element;
+          }}
+          data-index={index}
+          style={{ width: stripWidths[fitMode](zoomLevel), maxWidth: "100%" }}
+        >
+          <Image
+            src={convertFileSrc(panel.path, "panel")}
+            alt={panel.name}
+            width={800}
+            height={1200}
+            quality={100}
+            priority={Math.abs(index - startPanelIndex) < 3}
+            className={cn(
+              "pointer-events-none",
+              fitMode === "fit_height" && "w-auto h-[85dvh]",
+              fitMode === "original" && "w-auto h-auto",
+              (fitMode === "fit_width" || fitMode === "custom") && "w-full h-auto",
+            )}
+          />
+        </div>
+      ))}
+    </div>
+  );
+}
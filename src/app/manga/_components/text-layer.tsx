@@ -0,0 +1,96 @@
+"use client";
+
+import { cn } from "@/lib/utils";
+import { invoke } from "@tauri-apps/api/core";
+import { MouseEvent, useCallback, useEffect, useState } from "react";
+import LookupPopup, { LookupRequestType } from "./lookup-popup";
+
+// a block of text recognised by mokuro, the box is in the pixels of the image it read
+export type TextBlockType = {
+  id: string;
+  panel_id: string;
+  position: number;
+  x1: number;
+  y1: number;
+  x2: number;
+  y2: number;
+  is_vertical: boolean;
+  font_size: number | null;
+  lines: string[];
+  image_width: number;
+  image_height: number;
+};
+
+// draws the panel's text over the image, hidden until hovered so it can be selected, copied
+// and clicked to look up
+export default function TextLayer({ panelPath }: { panelPath: string }) {
+  const [blocks, setBlocks] = useState<TextBlockType[]>([]);
+  const [lookup, setLookup] = useState<LookupRequestType | null>(null);
+
+  useEffect(() => {
+    let isCurrent = true;
+    invoke<TextBlockType[]>("get_panel_text_blocks", { panelPath }).then((result) => {
+      if (isCurrent) setBlocks(result);
+    });
+
+    return () => {
+      isCurrent = false;
+    };
+  }, [panelPath]);
+
+  const closeLookup = useCallback(() => setLookup(null), []);
+
+  if (blocks.length === 0) {
+    return null;
+  }
+
+  // looks up the block's text from the clicked character on, the cursor counts code points
+  const handleLookup = (event: MouseEvent<HTMLDivElement>, block: TextBlockType) => {
+    // a drag is a selection to copy, not a lookup
+    if (window.getSelection()?.toString()) return;
+    const range = document.caretRangeFromPoint(event.clientX, event.clientY);
+    const line = range?.startContainer.parentElement?.closest("[data-line]");
+    if (!range || !line) return;
+
+    const lineIndex = Number(line.getAttribute("data-line"));
+    const before = block.lines.slice(0, lineIndex).join("") + (range.startContainer.textContent ?? "").slice(0, range.startOffset);
+    setLookup({
+      blockId: block.id,
+      text: block.lines.join(""),
+      cursor: Array.from(before).length,
+      x: event.clientX,
+      y: event.clientY,
+    });
+  };
+
+  return (
+    // sized like the panel's box, so font sizes can be given in container units
+    <div className="absolute inset-0 pointer-events-none" style={{ containerType: "size" }}>
+      {blocks.map((block) => (
+        <div
+          key={block.id}
+          className={cn(
+            "absolute pointer-events-auto select-text text-transparent leading-tight whitespace-nowrap",
+            "hover:text-black hover:bg-white hover:z-10",
+          )}
+          style={{
+            left: `${(block.x1 / block.image_width) * 100}%`,
+            top: `${(block.y1 / block.image_height) * 100}%`,
+            minWidth: `${((block.x2 - block.x1) / block.image_width) * 100}%`,
+            minHeight: `${((block.y2 - block.y1) / block.image_height) * 100}%`,
+            writingMode: block.is_vertical ? "vertical-rl" : "horizontal-tb",
+            fontSize: block.font_size ? `${(block.font_size / block.image_height) * 100}cqh` : undefined,
+          }}
+          onClick={(event) => handleLookup(event, block)}
+        >
+          {block.lines.map((line, index) => (
+            <p key={index} data-line={index}>
+              {line}
+            </p>
+          ))}
+        </div>
+      ))}
+      {lookup && <LookupPopup request={lookup} onClose={closeLookup} />}
+    </div>
+  );
+}
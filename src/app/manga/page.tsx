@@ -1,14 +1,19 @@
 "use client";
 
 import fetchGlobalManga from "./_components/lib/fetch-global-manga";
-import { useCallback, useEffect, useState } from "react";
+import { useCallback, useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { DirEntry, readDir } from "@tauri-apps/plugin-fs";
-import { MangaFolderType } from "../dashboard/page";
+import { FitModeType, MangaFolderType, ReadingModeType } from "../dashboard/page";
 import MangaPanel from "./_components/manga-panel";
 import MangaHeader from "./_components/manga-header";
-import { join } from "@tauri-apps/api/path";
+import MangaWebtoon from "./_components/manga-webtoon";
+import { EffectiveReadingModeType } from "./_components/lib/reading-modes";
+import { EffectiveZoomType } from "./_components/lib/zoom";
+import { PanelLayoutType } from "./_components/lib/spreads";
+import { CoverRegionType } from "./_components/cover-picker";
+import Bookmarks, { BookmarkTargetType, BookmarkType } from "./_components/bookmarks";
 import { getCurrentWindow, PhysicalSize } from "@tauri-apps/api/window";
+import { cn } from "@/lib/utils";
 
 export type FileEntry = {
   name: string;
@@ -20,16 +25,37 @@ export type FileEntry = {
 
 export type MangaPanelType = {
   id: string;
+  folder_id: string | null;
   title: string;
   full_path: string;
   is_read: boolean;
   width: number;
   height: number;
   zoom_level: number;
+  is_missing: boolean;
   created_at: string;
   updated_at: string;
 };
 
+export type ReadingSessionType = {
+  id: string;
+  folder_id: string | null;
+  started_at: string;
+  ended_at: string;
+  pages_turned: number;
+  seconds: number;
+};
+
+export type ReadingTimeType = {
+  seconds: number;
+  pages_turned: number;
+  sessions: number;
+};
+
+// how often an open session is saved, closing the app loses at most this much reading time.
+// the backend caps each save near this interval, so keep them in sync
+const SESSION_HEARTBEAT_MS = 30_000;
+
 export default function Manga() {
   const [currentManga, setCurrentManga] = useState<MangaFolderType | null>(null);
   const [mangaPanels, setMangaPanels] = useState<FileEntry[]>([]);
@@ -39,8 +65,21 @@ export default function Manga() {
     width: 0,
     height: 0,
   });
+  // stored per series, a chapter can override it
   const [zoomLevel, setZoomLevel] = useState(490);
+  const [fitMode, setFitMode] = useState<FitModeType>("fit_height");
   const [isDoublePanels, setIsDoublePanels] = useState<boolean>(false);
+  // the backend pairs the panels, keeping covers and wide spreads on their own
+  const [panelLayout, setPanelLayout] = useState<PanelLayoutType | null>(null);
+  // resolved by the backend from the chapter, its series, then ComicInfo.xml
+  const [readingMode, setReadingMode] = useState<ReadingModeType>("right_to_left");
+  // where a picked cover is saved, null while not picking one
+  const [coverTarget, setCoverTarget] = useState<"series" | "chapter" | null>(null);
+  const [showBookmarks, setShowBookmarks] = useState<boolean>(false);
+  // panels turned in the current reading session
+  const pagesTurned = useRef<number>(0);
+  // the panel an opened bookmark points to, used instead of the last read panel of its chapter
+  const bookmarkedPanelIndex = useRef<number | null>(null);
 
   useEffect(() => {
     // Fetch the global manga data
@@ -48,9 +87,6 @@ export default function Manga() {
       if (manga) {
         setCurrentManga(manga as MangaFolderType);
         console.log("manga:", manga);
-        if (manga.double_panels) {
-          setIsDoublePanels(true);
-        }
       }
     });
 
@@ -81,38 +117,20 @@ export default function Manga() {
     if (currentManga) {
       console.log("currentManga:", currentManga);
 
-      // Read the directory contents
-      const result = await readDir(currentManga.full_path);
+      // List the chapter's panels, works for both folders and .cbz/.zip archives
+      const result = (await invoke("get_manga_panels", {
+        folderPath: currentManga.full_path,
+      })) as { name: string; path: string }[];
 
       if (result) {
-        // Sort the result based on the file names (using numeric sorting)
-        let sorted = result.sort((a, b) => {
-          if (a.name && b.name) {
-            return a.name.localeCompare(b.name, undefined, {
-              numeric: true,
-            });
-          } else if (a.name) {
-            return -1;
-          } else if (b.name) {
-            return 1;
-          } else {
-            return 0;
-          }
-        });
-
-        // Map the sorted entries into FileEntry objects
-        let fileEntries: FileEntry[] = await Promise.all(
-          sorted.map(async (dirEntry) => {
-            let path = await join(currentManga.full_path, dirEntry.name);
-            return {
-              name: dirEntry.name,
-              path,
-              isFile: dirEntry.isFile,
-              isDirectory: dirEntry.isDirectory,
-              isSymLink: dirEntry.isSymlink,
-            };
-          }),
-        );
+        // The backend already returns the panels naturally sorted
+        let fileEntries: FileEntry[] = result.map((panel) => ({
+          name: panel.name,
+          path: panel.path,
+          isFile: true,
+          isDirectory: false,
+          isSymLink: false,
+        }));
 
         // Set the sorted file entries into state
         setMangaPanels(fileEntries);
@@ -122,18 +140,24 @@ export default function Manga() {
           invoke("update_manga_panel", {
             dirPaths: [fileEntries[0].path],
             isRead: true,
-            zoomLevel: 0,
           });
         }
       }
 
-      // Find and set the last read panel index
-      const lastReadPanelIndex = await invoke("find_last_read_panel", {
+      if (bookmarkedPanelIndex.current !== null) {
+        setCurrentPanelIndex(bookmarkedPanelIndex.current);
+        bookmarkedPanelIndex.current = null;
+        return;
+      }
+
+      // Find the last read panel and open it, the first panel when none was read
+      const lastReadPanelPath = (await invoke("find_last_read_panel", {
         chapterPath: currentManga.full_path,
-      });
-      console.log("previous:", lastReadPanelIndex);
+      })) as string | null;
+      const lastReadPanelIndex = result ? result.findIndex((panel) => panel.path === lastReadPanelPath) : -1;
+      console.log("previous:", lastReadPanelPath);
 
-      setCurrentPanelIndex(lastReadPanelIndex as number);
+      setCurrentPanelIndex(Math.max(lastReadPanelIndex, 0));
     }
   }, [currentManga]);
 
@@ -143,11 +167,7 @@ export default function Manga() {
       invoke("get_manga_panel", {
         path: mangaPanels[currentPanelIndex].path,
       }).then((panel: unknown) => {
-        let knownPanel = panel as MangaPanelType;
-        setCurrentMangaPanel(knownPanel);
-        if (knownPanel.zoom_level > 0) {
-          setZoomLevel(knownPanel.zoom_level);
-        }
+        setCurrentMangaPanel(panel as MangaPanelType);
       });
     }
   }, [currentManga, currentPanelIndex, mangaPanels]);
@@ -169,25 +189,129 @@ export default function Manga() {
     if (currentManga) invokeFindLastReadPanel();
   }, [currentManga, invokeFindLastReadPanel]);
 
-  // previous panels
-  const handlePreviousPanel = () => {
-    // if the current panel index is 1, it will set it to 0
-    if (currentPanelIndex - 2 === -1) {
+  useEffect(() => {
+    if (currentManga) {
+      invoke("get_reading_mode", { folderPath: currentManga.full_path }).then((result: unknown) => {
+        handleSetReadingMode(result as EffectiveReadingModeType);
+      });
+      invoke("get_zoom", { folderPath: currentManga.full_path }).then((result: unknown) => {
+        handleSetZoom(result as EffectiveZoomType);
+      });
+      setPanelLayout(null);
+      invoke("get_panel_layout", { folderPath: currentManga.full_path }).then((result: unknown) => {
+        setPanelLayout(result as PanelLayoutType);
+      });
+    }
+  }, [currentManga]);
+
+  // every chapter opened gets its own reading session, kept up to date until it's closed
+  useEffect(() => {
+    if (!currentManga) return;
+
+    let sessionId: string | null = null;
+    pagesTurned.current = 0;
+
+    const started = invoke("start_reading_session", { folderPath: currentManga.full_path }).then((session: unknown) => {
+      sessionId = (session as ReadingSessionType).id;
+    });
+    const saveSession = (turned: number) => {
+      if (sessionId) {
+        invoke("update_reading_session", { sessionId, pagesTurned: turned });
+      }
+    };
+    const heartbeat = setInterval(() => saveSession(pagesTurned.current), SESSION_HEARTBEAT_MS);
+
+    return () => {
+      clearInterval(heartbeat);
+      // read now, the next chapter's session starts counting from zero before this resolves
+      const turned = pagesTurned.current;
+      started.then(() => saveSession(turned));
+    };
+  }, [currentManga]);
+
+  const handleSetReadingMode = (effective: EffectiveReadingModeType) => {
+    setReadingMode(effective.mode);
+    // double panels are only kept for the modes that pair panels
+    setIsDoublePanels(effective.double_panels);
+  };
+
+  const handleSetZoom = (effective: EffectiveZoomType) => {
+    setFitMode(effective.fit_mode);
+    setZoomLevel(effective.zoom_level);
+  };
+
+  // webtoon chapters are scrolled, everything up to the visible panel counts as read
+  const handleWebtoonPanelVisible = (index: number) => {
+    if (index > currentPanelIndex) {
+      invoke("update_manga_panel", {
+        dirPaths: mangaPanels.slice(currentPanelIndex + 1, index + 1).map((panel) => panel.path),
+        isRead: true,
+      });
+    } else if (index < currentPanelIndex) {
       invoke("update_manga_panel", {
-        dirPaths: [mangaPanels[currentPanelIndex - 1].path, mangaPanels[currentPanelIndex].path],
+        dirPaths: mangaPanels.slice(index + 1, currentPanelIndex + 1).map((panel) => panel.path),
         isRead: false,
-        zoomLevel: zoomLevel,
       });
+    }
+    pagesTurned.current += Math.abs(index - currentPanelIndex);
+    setCurrentPanelIndex(index);
+  };
 
-      setCurrentPanelIndex(0);
-    } else if (currentPanelIndex - 2 > -1) {
+  const handleToggleSpreadOffset = () => {
+    if (currentManga && panelLayout) {
+      invoke("set_spread_offset", {
+        folderPath: currentManga.full_path,
+        spreadOffset: !panelLayout.spread_offset,
+      }).then((result: unknown) => {
+        setPanelLayout(result as PanelLayoutType);
+      });
+    }
+  };
+
+  // a click uses the whole panel as the cover, a drag crops it
+  const handlePickCover = (panel: FileEntry, region: CoverRegionType | null) => {
+    if (!currentManga || !coverTarget) return;
+    const args = { folderPath: currentManga.full_path, panelPath: panel.path, forSeries: coverTarget === "series" };
+    const picked = region ? invoke("set_custom_cover_crop", { ...args, ...region }) : invoke("set_custom_cover_panel", args);
+    picked.then(() => setCoverTarget(null));
+  };
+
+  // the backend makes the bookmark's chapter the global one when it isn't open already
+  const handleOpenBookmark = (bookmark: BookmarkType) => {
+    invoke("open_bookmark", { id: bookmark.id }).then((result: unknown) => {
+      const target = result as BookmarkTargetType;
+      if (currentManga && target.chapter_path === currentManga.full_path) {
+        setCurrentPanelIndex(target.panel_index);
+        return;
+      }
+      bookmarkedPanelIndex.current = target.panel_index;
+      fetchGlobalManga().then((manga) => {
+        if (manga) setCurrentManga(manga);
+      });
+    });
+  };
+
+  const currentSpreadIndex = panelLayout
+    ? panelLayout.spreads.findIndex((spread) => spread.panels.some((panel) => panel.index === currentPanelIndex))
+    : -1;
+  const currentSpread = isDoublePanels && panelLayout ? panelLayout.spreads[currentSpreadIndex] : undefined;
+
+  // previous panels
+  const handlePreviousPanel = () => {
+    if (!currentSpread || !panelLayout) {
+      handlePreviousSinglePanel();
+      return;
+    }
+
+    const previousSpread = panelLayout.spreads[currentSpreadIndex - 1];
+    if (previousSpread) {
       invoke("update_manga_panel", {
-        dirPaths: [mangaPanels[currentPanelIndex - 1].path, mangaPanels[currentPanelIndex].path],
+        dirPaths: currentSpread.panels.map((panel) => panel.path),
         isRead: false,
-        zoomLevel: zoomLevel,
       });
 
-      setCurrentPanelIndex((prev) => prev - 2);
+      pagesTurned.current += 1;
+      setCurrentPanelIndex(previousSpread.panels[0].index);
     }
   };
 
@@ -196,23 +320,27 @@ export default function Manga() {
       invoke("update_manga_panel", {
         dirPaths: [mangaPanels[currentPanelIndex].path],
         isRead: false,
-        zoomLevel: zoomLevel,
       });
+      pagesTurned.current += 1;
       setCurrentPanelIndex((prev) => prev - 1);
     }
   };
 
   // next panels
   const handleNextPanel = () => {
-    if (currentPanelIndex + 2 <= mangaPanels.length - 1) {
+    if (!currentSpread || !panelLayout) {
+      handleNextSinglePanel();
+      return;
+    }
+
+    const nextSpread = panelLayout.spreads[currentSpreadIndex + 1];
+    if (nextSpread) {
       invoke("update_manga_panel", {
-        dirPaths: [mangaPanels[currentPanelIndex + 1].path, mangaPanels[currentPanelIndex + 2].path],
+        dirPaths: nextSpread.panels.map((panel) => panel.path),
         isRead: true,
-        zoomLevel: zoomLevel,
       });
-      setCurrentPanelIndex((prev) => prev + 2);
-
-      // then update the bar chart
+      pagesTurned.current += 1;
+      setCurrentPanelIndex(nextSpread.panels[0].index);
     }
   };
 
@@ -221,11 +349,9 @@ export default function Manga() {
       invoke("update_manga_panel", {
         dirPaths: [mangaPanels[currentPanelIndex + 1].path],
         isRead: true,
-        zoomLevel: zoomLevel,
       });
+      pagesTurned.current += 1;
       setCurrentPanelIndex((prev) => prev + 1);
-
-      // then update the bar chart
     }
   };
 
@@ -241,7 +367,6 @@ export default function Manga() {
       invoke("update_manga_panel", {
         dirPaths: [panelDirs],
         isRead: true,
-        zoomLevel: zoomLevel,
       });
 
       if (currentPanelIndex === mangaPanels.length - 1) {
@@ -279,7 +404,6 @@ export default function Manga() {
       invoke("update_manga_panel", {
         dirPaths: panelDirs,
         isRead: false,
-        zoomLevel: zoomLevel,
       });
 
       if (currentPanelIndex === 0) {
@@ -314,6 +438,8 @@ export default function Manga() {
           <MangaHeader
             key={`${currentManga.title}-manga-header`}
             currentManga={currentManga}
+            readingMode={readingMode}
+            handleSetReadingMode={handleSetReadingMode}
             setIsDoublePanels={setIsDoublePanels}
             handleNextPanel={handleNextPanel}
             handleNextSinglePanel={handleNextSinglePanel}
@@ -322,37 +448,73 @@ export default function Manga() {
             handleSetLastPanel={handleSetLastPanel}
             handleSetFirstPanel={handleSetFirstPanel}
             doublePanels={isDoublePanels}
-            currentPanelPath={mangaPanels[currentPanelIndex].path}
+            spreadOffset={panelLayout?.spread_offset ?? false}
+            handleToggleSpreadOffset={handleToggleSpreadOffset}
+            fitMode={fitMode}
             zoomLevel={zoomLevel}
-            setZoomLevel={setZoomLevel}
+            handleSetZoom={handleSetZoom}
+            coverTarget={coverTarget}
+            setCoverTarget={setCoverTarget}
+            showBookmarks={showBookmarks}
+            setShowBookmarks={setShowBookmarks}
           />
-          <div className="h-full flex flex-row justify-center items-center">
-            <>
-              {mangaPanels[currentPanelIndex + 1] && (
+          {showBookmarks && (
+            <Bookmarks
+              currentManga={currentManga}
+              currentPanelPath={mangaPanels[currentPanelIndex]?.path ?? null}
+              onOpenBookmark={handleOpenBookmark}
+            />
+          )}
+          {readingMode === "webtoon" ? (
+            <MangaWebtoon
+              key={`${currentManga.full_path}-manga-webtoon`}
+              mangaPanels={mangaPanels}
+              startPanelIndex={currentPanelIndex}
+              fitMode={fitMode}
+              zoomLevel={zoomLevel}
+              onPanelVisible={handleWebtoonPanelVisible}
+            />
+          ) : (
+            <div
+              className={cn(
+                "h-full flex justify-center items-center overflow-auto",
+                readingMode === "right_to_left" && "flex-row",
+                readingMode === "left_to_right" && "flex-row-reverse",
+                readingMode === "vertical" && "flex-col",
+              )}
+            >
+              {currentSpread ? (
+                // the later panel comes first so it sits on the reading side of the row
+                [...currentSpread.panels].reverse().map((panel) => (
+                  <MangaPanel
+                    key={`${panel.path}-manga-panel-spread`}
+                    currentPanel={mangaPanels[panel.index]}
+                    secondPanel={panel.index !== currentSpread.panels[0].index}
+                    fitMode={fitMode}
+                    zoomLevel={zoomLevel}
+                    width={panel.width || currentMangaPanel.width}
+                    height={panel.height || currentMangaPanel.height}
+                    isDoublePanels={true}
+                    onPickCover={coverTarget ? handlePickCover : undefined}
+                  />
+                ))
+              ) : (
                 <MangaPanel
-                  key={`${mangaPanels[currentPanelIndex].path}-manga-panel-next`}
-                  currentPanel={mangaPanels[currentPanelIndex + 1]}
-                  secondPanel={true}
+                  key={`${mangaPanels[currentPanelIndex].path}-manga-panel-current`}
+                  currentPanel={mangaPanels[currentPanelIndex]}
+                  secondPanel={false}
+                  fitMode={fitMode}
                   zoomLevel={zoomLevel}
                   width={currentMangaPanel.width}
                   height={currentMangaPanel.height}
-                  isDoublePanels={isDoublePanels}
+                  onPickCover={coverTarget ? handlePickCover : undefined}
                 />
               )}
-
-              <MangaPanel
-                key={`${mangaPanels[currentPanelIndex].path}-manga-panel-current`}
-                currentPanel={mangaPanels[currentPanelIndex]}
-                secondPanel={false}
-                zoomLevel={zoomLevel}
-                width={currentMangaPanel.width}
-                height={currentMangaPanel.height}
-              />
-            </>
-            <h1 className="fixed left-50 bottom-1 text-xs font-semibold text-muted-foreground pointer-events-none">
-              {`${currentPanelIndex}/${mangaPanels.length - 1}`}
-            </h1>
-          </div>
+              <h1 className="fixed left-50 bottom-1 text-xs font-semibold text-muted-foreground pointer-events-none">
+                {`${currentPanelIndex}/${mangaPanels.length - 1}`}
+              </h1>
+            </div>
+          )}
         </>
       )}
     </main>
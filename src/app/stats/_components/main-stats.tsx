@@ -2,6 +2,7 @@
 
 import { Separator } from "@/components/ui/separator";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useEffect, useState } from "react";
 import { Eye, EyeOff, Folder, Timer, Video } from "lucide-react";
 import { ClockIcon, EyeIcon, EyeSlashIcon, FolderIcon, VideoCameraIcon } from "@heroicons/react/16/solid";
@@ -41,6 +42,15 @@ export default function MainStats() {
         //console.log(stats);
       }
     });
+
+    // a background scan that finishes while the page is open brings fresh totals
+    const unlisten = listen<{ stats: MainStatsType }>("scan://finished", ({ payload }) => {
+      setMainStats(payload.stats);
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
   }, []);
 
   useEffect(() => {
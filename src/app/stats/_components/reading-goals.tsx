@@ -0,0 +1,118 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Separator } from "@/components/ui/separator";
+import { FireIcon, TrophyIcon, XMarkIcon } from "@heroicons/react/16/solid";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+export type GoalPeriodType = "daily" | "weekly";
+
+export type GoalUnitType = "minutes" | "pages";
+
+export type ReadingGoalType = {
+  period: GoalPeriodType;
+  unit: GoalUnitType;
+  target: number;
+  created_at: string;
+  updated_at: string;
+};
+
+export type GoalProgressType = {
+  goal: ReadingGoalType;
+  progress: number;
+  is_complete: boolean;
+};
+
+export type GoalsOverviewType = {
+  goals: GoalProgressType[];
+  streaks: { current: number; longest: number };
+};
+
+const periods: GoalPeriodType[] = ["daily", "weekly"];
+
+export default function ReadingGoals() {
+  const [overview, setOverview] = useState<GoalsOverviewType>();
+  const [period, setPeriod] = useState<GoalPeriodType>("daily");
+  const [unit, setUnit] = useState<GoalUnitType>("minutes");
+  const [target, setTarget] = useState<number>(30);
+
+  const fetchProgress = () => {
+    invoke("get_goal_progress").then((result: unknown) => {
+      setOverview(result as GoalsOverviewType);
+    });
+  };
+
+  useEffect(() => {
+    fetchProgress();
+
+    const unlisten = listen<GoalProgressType>("goal://reached", () => {
+      fetchProgress();
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
+  }, []);
+
+  const invokeSetGoal = () => {
+    invoke("set_reading_goal", { period, unit, target }).then(fetchProgress);
+  };
+
+  const invokeRemoveGoal = (goalPeriod: GoalPeriodType) => {
+    invoke("remove_reading_goal", { period: goalPeriod }).then(fetchProgress);
+  };
+
+  return (
+    <div className="w-full flex flex-col justify-center items-center h-fit bg-card rounded-xl shadow-md outline outline-border p-2 select-none">
+      <h1 className="font-bold rounded-md px-1">Goals</h1>
+      <Separator className="h-[2px] w-1/5 my-1.5 mr-0.5" />
+      <ul className="w-full flex flex-row flex-wrap justify-center items-start gap-3 pb-0.5">
+        <li className="flex flex-col justify-center items-start gap-0.5 text-xs bg-muted px-2 pb-1.5 pt-0.5 rounded-md">
+          <label className="font-semibold underline flex flex-row gap-1">
+            Current Streak <FireIcon className="w-3 h-auto" />
+          </label>
+          <span className="font-medium bg-accent rounded-sm px-0.5">{overview?.streaks.current ?? 0} days</span>
+        </li>
+        <li className="flex flex-col justify-center items-start gap-0.5 text-xs bg-muted px-2 pb-1.5 pt-0.5 rounded-md">
+          <label className="font-semibold underline flex flex-row gap-1">
+            Longest Streak <TrophyIcon className="w-3 h-auto" />
+          </label>
+          <span className="font-medium bg-accent rounded-sm px-0.5">{overview?.streaks.longest ?? 0} days</span>
+        </li>
+        {overview?.goals.map(({ goal, progress, is_complete }) => (
+          <li key={goal.period} className="flex flex-col justify-center items-start gap-0.5 text-xs bg-muted px-2 pb-1.5 pt-0.5 rounded-md">
+            <label className="font-semibold underline flex flex-row items-center gap-1 capitalize">
+              {goal.period} Goal
+              <button title="Remove Goal" onClick={() => invokeRemoveGoal(goal.period)}>
+                <XMarkIcon className="w-3 h-auto" />
+              </button>
+            </label>
+            <span className={`font-medium rounded-sm px-0.5 ${is_complete ? "bg-green-300" : "bg-accent"}`}>
+              {Math.min(progress, goal.target)}/{goal.target} {goal.unit}
+            </span>
+          </li>
+        ))}
+      </ul>
+      <div className="flex flex-row items-center gap-1 text-xs pt-1">
+        <select className="h-7 rounded-md border border-input bg-transparent px-1" value={period} onChange={(e) => setPeriod(e.target.value as GoalPeriodType)}>
+          {periods.map((option) => (
+            <option key={option} value={option}>
+              {option === "daily" ? "Daily" : "Weekly"}
+            </option>
+          ))}
+        </select>
+        <Input className="h-7 w-16 text-xs" type="number" min={1} value={target} onChange={(e) => setTarget(Math.max(1, Number(e.target.value)))} />
+        <select className="h-7 rounded-md border border-input bg-transparent px-1" value={unit} onChange={(e) => setUnit(e.target.value as GoalUnitType)}>
+          <option value="minutes">Minutes</option>
+          <option value="pages">Pages</option>
+        </select>
+        <Button className="h-7 px-2 text-xs" onClick={invokeSetGoal}>
+          Set Goal
+        </Button>
+      </div>
+    </div>
+  );
+}
@@ -8,35 +8,36 @@ import {
   CardHeader,
   CardTitle,
 } from "@/components/ui/card";
-import { invoke } from "@tauri-apps/api/core";
 import "chart.js/auto";
 import { useEffect, useState } from "react";
 import { Bar } from "react-chartjs-2";
+import {
+  ChartPointType,
+  fetchReadingChart,
+  fromLocalDate,
+  toHours,
+} from "../reading-chart";
 
 export default function DailyTab() {
-
-  const [stats, setStats] = useState<number[]>();
+  const [points, setPoints] = useState<ChartPointType[]>([]);
 
   useEffect(() => {
-      invoke("create_chart_stats", { range: "daily", }).then((daily) => {
-        setStats(daily as number[]);
-    })
-  }, [])
+    // monday to sunday of the current week
+    const now = new Date();
+    const monday = new Date(now.getFullYear(), now.getMonth(), now.getDate() - ((now.getDay() + 6) % 7));
+    const sunday = new Date(monday.getFullYear(), monday.getMonth(), monday.getDate() + 6);
+
+    fetchReadingChart(monday, sunday, "day").then(setPoints);
+  }, []);
 
   const data = {
-    labels: [
-      "Sun",
-      "Mon",
-      "Tue",
-      "Wed",
-      "Thu",
-      "Fri",
-      "Sat",
-    ],
+    labels: points.map((point) =>
+      fromLocalDate(point.start).toLocaleDateString(undefined, { weekday: "short" }),
+    ),
     datasets: [
       {
         label: " Hours Read ",
-        data: stats,
+        data: points.map((point) => toHours(point.seconds)),
         backgroundColor: [
           "rgba(255, 99, 132, 0.2)",
           "rgba(255, 159, 64, 0.2)",
@@ -8,41 +8,34 @@ import {
   CardHeader,
   CardTitle,
 } from "@/components/ui/card";
-import { invoke } from "@tauri-apps/api/core";
 import "chart.js/auto";
 import { useEffect, useState } from "react";
 import { Bar } from "react-chartjs-2";
+import {
+  ChartPointType,
+  fetchReadingChart,
+  fromLocalDate,
+  toHours,
+} from "../reading-chart";
 
 export default function MonthlyTab() {
-  const [stats, setStats] = useState<number[]>();
+  const [points, setPoints] = useState<ChartPointType[]>([]);
 
   useEffect(() => {
-      invoke("create_chart_stats", { range: "monthly" }).then(
-        (daily) => {
-          setStats(daily as number[]);
-        },
-      );
+    // every month of the current year
+    const year = new Date().getFullYear();
+
+    fetchReadingChart(new Date(year, 0, 1), new Date(year, 11, 31), "month").then(setPoints);
   }, []);
 
   const data = {
-    labels: [
-      "Jan",
-      "Feb",
-      "Mar",
-      "Apr",
-      "May",
-      "Jun",
-      "Jul",
-      "Aug",
-      "Sep",
-      "Oct",
-      "Nov",
-      "Dec",
-    ],
+    labels: points.map((point) =>
+      fromLocalDate(point.start).toLocaleDateString(undefined, { month: "short" }),
+    ),
     datasets: [
       {
         label: " Hours Read ",
-        data: stats,
+        data: points.map((point) => toHours(point.seconds)),
         backgroundColor: [
           "rgba(255, 99, 132, 0.2)",
           "rgba(255, 159, 64, 0.2)",
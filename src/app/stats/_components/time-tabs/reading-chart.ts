@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ChartBucketType = "day" | "week" | "month" | "year";
+
+export type ChartPointType = {
+  label: string;
+  start: string;
+  end: string;
+  seconds: number;
+  pages_turned: number;
+};
+
+// built from the local calendar date, toISOString would shift it to utc
+export const toLocalDate = (date: Date) =>
+  [
+    date.getFullYear(),
+    String(date.getMonth() + 1).padStart(2, "0"),
+    String(date.getDate()).padStart(2, "0"),
+  ].join("-");
+
+// parsed as local midnight, a bare "YYYY-MM-DD" is read as utc
+export const fromLocalDate = (date: string) => {
+  const [year, month, day] = date.split("-").map(Number);
+  return new Date(year, month - 1, day);
+};
+
+export const toHours = (seconds: number) =>
+  Math.round((seconds / 3600) * 100) / 100;
+
+export async function fetchReadingChart(
+  start: Date,
+  end: Date,
+  bucket: ChartBucketType,
+) {
+  return invoke<ChartPointType[]>("get_reading_chart", {
+    start: toLocalDate(start),
+    end: toLocalDate(end),
+    bucket,
+  });
+}
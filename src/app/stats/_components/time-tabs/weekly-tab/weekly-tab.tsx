@@ -8,38 +8,34 @@ import {
   CardHeader,
   CardTitle,
 } from "@/components/ui/card";
-import { invoke } from "@tauri-apps/api/core";
 import "chart.js/auto";
 import { useEffect, useState } from "react";
 import { Bar } from "react-chartjs-2";
+import {
+  ChartPointType,
+  fetchReadingChart,
+  fromLocalDate,
+  toHours,
+} from "../reading-chart";
 
 export default function WeeklyTab() {
-  let now = new Date();
-  let month = now.getMonth(); // get the current month
-  let year = now.getFullYear(); // get the current year
-  let daysInMonth = new Date(year, month + 1, 0).getDate();
-
-  const labels = Array.from({ length: daysInMonth }, (_, i) =>
-    (i + 1).toString(),
-  );
-
-  const [stats, setStats] = useState<number[]>();
+  const [points, setPoints] = useState<ChartPointType[]>([]);
 
   useEffect(() => {
-      invoke("create_chart_stats", {
-        range: "weekly",
-        daysInMonth: labels.length,
-      }).then((daily) => {
-        setStats(daily as number[]);
-      });
+    // every day of the current month
+    const now = new Date();
+    const first = new Date(now.getFullYear(), now.getMonth(), 1);
+    const last = new Date(now.getFullYear(), now.getMonth() + 1, 0);
+
+    fetchReadingChart(first, last, "day").then(setPoints);
   }, []);
 
   const data = {
-    labels,
+    labels: points.map((point) => fromLocalDate(point.start).getDate().toString()),
     datasets: [
       {
         label: " Hours Read ",
-        data: stats,
+        data: points.map((point) => toHours(point.seconds)),
         backgroundColor: [
           "rgba(255, 99, 132, 0.2)",
           "rgba(255, 159, 64, 0.2)",
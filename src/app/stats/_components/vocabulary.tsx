@@ -0,0 +1,138 @@
+"use client";
+
+import { Button } from "@/components/ui/button";
+import { Separator } from "@/components/ui/separator";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+
+export type JlptLevelType = "n5" | "n4" | "n3" | "n2" | "n1" | "beyond_n1" | "unranked";
+
+export type VocabularyStatsType = {
+  chapters: number;
+  token_count: number;
+  unique_words: number;
+  unique_kanji: number;
+  known_words: number;
+  known_tokens: number;
+  comprehension: number;
+  jlpt: { level: JlptLevelType; words: number }[];
+  frequency_bands: { min_rank: number; max_rank: number | null; words: number }[];
+  unranked_words: number;
+};
+
+export type SeriesComprehensionType = {
+  id: string;
+  title: string;
+  full_path: string;
+  stats: VocabularyStatsType;
+};
+
+const levelLabels: Record<JlptLevelType, string> = {
+  n5: "N5",
+  n4: "N4",
+  n3: "N3",
+  n2: "N2",
+  n1: "N1",
+  beyond_n1: "N1+",
+  unranked: "?",
+};
+
+function LevelBar({ stats }: { stats: VocabularyStatsType }) {
+  return (
+    <ul className="w-full h-2 flex flex-row rounded-sm overflow-hidden bg-muted">
+      {stats.jlpt
+        .filter(({ words }) => words > 0)
+        .map(({ level, words }, index) => (
+          <li
+            key={level}
+            title={`${levelLabels[level]}: ${words} words`}
+            className="h-full bg-accent-foreground"
+            style={{ width: `${(words / stats.unique_words) * 100}%`, opacity: 1 - index * 0.12 }}
+          />
+        ))}
+    </ul>
+  );
+}
+
+// series ranked by how much of their text is made of known words, for picking what to read next
+export default function Vocabulary() {
+  const [ranking, setRanking] = useState<SeriesComprehensionType[]>([]);
+  const [knownWords, setKnownWords] = useState(0);
+  const [progress, setProgress] = useState<{ analyzed: number; total: number } | null>(null);
+
+  const fetchRanking = () => {
+    invoke<SeriesComprehensionType[]>("rank_series_by_comprehension").then(setRanking);
+    invoke<number>("get_known_word_count").then(setKnownWords);
+  };
+
+  useEffect(() => {
+    fetchRanking();
+
+    const unlisten = listen<{ analyzed: number; total: number }>("vocabulary://progress", ({ payload }) => {
+      setProgress(payload);
+    });
+
+    return () => {
+      unlisten.then((dispose) => dispose());
+    };
+  }, []);
+
+  const invokeAnalyze = () => {
+    invoke("analyze_library_vocabulary")
+      .catch((error) => console.error("Error Analyzing Vocabulary:", error))
+      .finally(() => {
+        setProgress(null);
+        fetchRanking();
+      });
+  };
+
+  const handleImportKnownWords = () => {
+    open({
+      title: "Select Known Words",
+      filters: [{ name: "Word List or Anki Export", extensions: ["txt", "csv", "tsv"] }],
+    }).then((path: string | null) => {
+      if (!path) return;
+      invoke<number>("import_known_words", { path, replace: true }).then((count) => {
+        setKnownWords(count);
+        fetchRanking();
+      });
+    });
+  };
+
+  return (
+    <div className="w-full flex flex-col justify-center items-center h-fit bg-card rounded-xl shadow-md outline outline-border p-2 select-none">
+      <h1 className="font-bold rounded-md px-1">Vocabulary</h1>
+      <Separator className="h-[2px] w-1/5 my-1.5 mr-0.5" />
+      <div className="flex flex-row items-center gap-2 text-xs pb-2">
+        <span className="font-medium bg-accent rounded-sm px-0.5">{knownWords} known words</span>
+        <Button className="h-7 px-2 text-xs" variant="secondary" onClick={handleImportKnownWords}>
+          Import Known Words
+        </Button>
+        <Button className="h-7 px-2 text-xs" disabled={progress !== null} onClick={invokeAnalyze}>
+          {progress ? `Analyzing ${progress.analyzed}/${progress.total}` : "Analyze Library"}
+        </Button>
+      </div>
+      <ol className="w-full flex flex-col gap-1.5 text-xs">
+        {ranking.map(({ id, title, stats }, index) => (
+          <li key={id} className="flex flex-col gap-1 bg-muted px-2 py-1 rounded-md">
+            <div className="flex flex-row items-center justify-between gap-2">
+              <span className="font-semibold truncate">
+                {index + 1}. {title}
+              </span>
+              <span className="font-medium bg-accent rounded-sm px-0.5 shrink-0">{(stats.comprehension * 100).toFixed(1)}% known</span>
+            </div>
+            <div className="flex flex-row gap-3 text-muted-foreground">
+              <span>{stats.unique_words} words</span>
+              <span>{stats.unique_words - stats.known_words} unknown</span>
+              <span>{stats.unique_kanji} kanji</span>
+              <span>{stats.chapters} chapters</span>
+            </div>
+            <LevelBar stats={stats} />
+          </li>
+        ))}
+      </ol>
+    </div>
+  );
+}
@@ -1,11 +1,15 @@
 import MainStats from "./_components/main-stats";
+import ReadingGoals from "./_components/reading-goals";
 import TimeTabs from "./_components/time-tabs/time-tabs";
+import Vocabulary from "./_components/vocabulary";
 
 export default function StatsPage() {
   return (
     <main className="w-full h-full flex flex-col justify-start items-center p-5 gap-4 md:px-30 lg:px-16 xl:px-36 2xl:px-48">
       <MainStats />
+      <ReadingGoals />
         <TimeTabs />
+      <Vocabulary />
     </main>
   );
 }